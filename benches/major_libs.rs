@@ -7,7 +7,7 @@ extern crate criterion;
 use criterion::black_box;
 use criterion::Criterion;
 
-use ress::Scanner;
+use ress::{KindScanner, Scanner};
 use std::fs::read_to_string;
 use std::path::PathBuf;
 
@@ -160,6 +160,20 @@ fn run_bench_(c: &mut Criterion, js: &str, name: &str) {
             }
         })
     });
+    group.bench_function(format!("{}_no_locations", name), |b| {
+        b.iter(|| {
+            for i in Scanner::without_locations(&js) {
+                black_box(i.unwrap());
+            }
+        })
+    });
+    group.bench_function(format!("{}_kinds", name), |b| {
+        b.iter(|| {
+            for i in KindScanner::new(&js) {
+                black_box(i.unwrap());
+            }
+        })
+    });
     group.finish();
 }
 
@@ -1,5 +1,5 @@
 use docopt::Docopt;
-use ress::prelude::*;
+use ress::{tokens::TokenKind, KindScanner};
 #[macro_use]
 extern crate serde_derive;
 
@@ -29,9 +29,9 @@ fn main() {
     let mut counts = get_initial_counts();
     let mut total = 0;
 
-    for maybe in Scanner::new(&js) {
-        let item = maybe.expect("failed to scan token");
-        let key = token_type_str(&item.token);
+    for maybe in KindScanner::new(&js) {
+        let (kind, _span) = maybe.expect("failed to scan token");
+        let key = token_type_str(kind);
         counts.entry(key).and_modify(|c| *c += 1);
         total += 1;
     }
@@ -41,19 +41,19 @@ fn main() {
     println!("total: {}", total);
 }
 
-fn token_type_str(tok: &Token<&str>) -> &'static str {
-    match tok {
-        Token::Null => "null",
-        Token::Boolean(_) => "bool",
-        Token::Ident(_) => "ident",
-        Token::Number(_) => "number",
-        Token::String(_) => "string",
-        Token::Keyword(_) => "keyword",
-        Token::Punct(_) => "punct",
-        Token::RegEx(_) => "regex",
-        Token::Template(_) => "template",
-        Token::Comment(_) => "comment",
-        Token::EoF => "eof",
+fn token_type_str(kind: TokenKind) -> &'static str {
+    match kind {
+        TokenKind::Null => "null",
+        TokenKind::Boolean(_) => "bool",
+        TokenKind::Ident => "ident",
+        TokenKind::Number(_) => "number",
+        TokenKind::String(_) => "string",
+        TokenKind::Keyword(_) => "keyword",
+        TokenKind::Punct(_) => "punct",
+        TokenKind::RegEx => "regex",
+        TokenKind::Template(_) => "template",
+        TokenKind::Comment(_) => "comment",
+        TokenKind::EoF => "eof",
     }
 }
 
@@ -0,0 +1,109 @@
+//! Generate `src/tokenizer/unicode_tables.rs` from the
+//! Unicode Character Database files in `assets/ucd`
+//!
+//! ```sh
+//! cargo run --example gen_unicode_tables [ucd dir] [output file]
+//! ```
+//!
+//! To move to a new version of Unicode, replace
+//! `DerivedCoreProperties.txt` and `DerivedGeneralCategory.txt`
+//! (from the `extracted` directory) with the new files and
+//! run this again.
+use std::{fmt::Write as _, fs, path::Path};
+
+fn main() {
+    let mut args = std::env::args().skip(1);
+    let ucd = args.next().unwrap_or_else(|| "assets/ucd".to_string());
+    let out = args
+        .next()
+        .unwrap_or_else(|| "src/tokenizer/unicode_tables.rs".to_string());
+    let ucd = Path::new(&ucd);
+    let core = read(&ucd.join("DerivedCoreProperties.txt"));
+    let categories = read(&ucd.join("DerivedGeneralCategory.txt"));
+    let version = version(&core);
+    assert_eq!(
+        version,
+        self::version(&categories),
+        "UCD files are from different versions"
+    );
+
+    let mut tables = String::new();
+    writeln!(
+        tables,
+        "// DO NOT EDIT THIS FILE. It was generated from the Unicode {}.{}.{}\n\
+         // UCD files in assets/ucd by `cargo run --example gen_unicode_tables`.\n\
+         \n\
+         /// The version of Unicode used for identifiers and whitespace\n\
+         pub const UNICODE_VERSION: (u8, u8, u8) = ({}, {}, {});",
+        version.0, version.1, version.2, version.0, version.1, version.2
+    )
+    .unwrap();
+    for (name, text, property) in [
+        ("ID_START", &core, "ID_Start"),
+        ("ID_CONTINUE", &core, "ID_Continue"),
+        ("SPACE_SEPARATOR", &categories, "Zs"),
+    ] {
+        let ranges = ranges(text, property);
+        assert!(!ranges.is_empty(), "no code points found for {}", property);
+        writeln!(tables, "\n/// Every code point with {}", property).unwrap();
+        writeln!(tables, "pub(crate) static {}: &[(char, char)] = &[", name).unwrap();
+        for (start, end) in ranges {
+            writeln!(tables, "    ('\\u{{{:x}}}', '\\u{{{:x}}}'),", start, end).unwrap();
+        }
+        writeln!(tables, "];").unwrap();
+    }
+    fs::write(&out, tables).unwrap_or_else(|e| panic!("failed to write {}: {}", out, e));
+    println!("wrote Unicode {:?} tables to {}", version, out);
+}
+
+fn read(path: &Path) -> String {
+    fs::read_to_string(path).unwrap_or_else(|e| panic!("failed to read {}: {}", path.display(), e))
+}
+
+/// The version from a UCD file's first line,
+/// like `# DerivedCoreProperties-16.0.0.txt`
+fn version(text: &str) -> (u8, u8, u8) {
+    let first = text.lines().next().unwrap_or_default();
+    let version = first
+        .rsplit('-')
+        .next()
+        .and_then(|v| v.strip_suffix(".txt"))
+        .unwrap_or_else(|| panic!("no version in {:?}", first));
+    let mut parts = version.split('.').map(|p| p.parse().unwrap());
+    (
+        parts.next().unwrap(),
+        parts.next().unwrap(),
+        parts.next().unwrap(),
+    )
+}
+
+/// The sorted and merged ranges of code points that
+/// have `property`, skipping surrogates
+fn ranges(text: &str, property: &str) -> Vec<(u32, u32)> {
+    let mut ranges: Vec<(u32, u32)> = text
+        .lines()
+        .filter_map(|line| {
+            let line = line.split('#').next()?;
+            let (points, prop) = line.split_once(';')?;
+            if prop.trim() != property {
+                return None;
+            }
+            let points = points.trim();
+            let (start, end) = points.split_once("..").unwrap_or((points, points));
+            Some((
+                u32::from_str_radix(start, 16).unwrap(),
+                u32::from_str_radix(end, 16).unwrap(),
+            ))
+        })
+        .filter(|(start, _)| char::from_u32(*start).is_some())
+        .collect();
+    ranges.sort_unstable();
+    let mut merged: Vec<(u32, u32)> = Vec::with_capacity(ranges.len());
+    for (start, end) in ranges {
+        match merged.last_mut() {
+            Some(last) if start <= last.1 + 1 => last.1 = last.1.max(end),
+            _ => merged.push((start, end)),
+        }
+    }
+    merged
+}
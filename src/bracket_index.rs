@@ -0,0 +1,403 @@
+use crate::{
+    error::{DelimiterError, DelimiterLocation, Error, ErrorKind},
+    token_tree::{delimiter_location, Delimiter},
+    tokens::{Punct, Template, Token},
+    Item, Position, Scanner, ScannerOptions, SourceLocation, Span,
+};
+use alloc::vec::Vec;
+
+type Res<T> = Result<T, Error>;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+/// What kind of text a `FoldingRange` covers
+pub enum FoldingKind {
+    /// A `{` through its matching `}`
+    Block,
+    /// A multi-line comment or a run of single
+    /// line comments on consecutive lines
+    Comment,
+    /// A template literal from its opening
+    /// to its closing backtick
+    Template,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+/// A region of the text that spans more than one
+/// line, lines are 1 indexed and inclusive
+pub struct FoldingRange {
+    pub start_line: usize,
+    pub end_line: usize,
+    pub kind: FoldingKind,
+}
+
+#[derive(Clone, Copy, Debug)]
+struct Entry {
+    location: DelimiterLocation,
+    partner: Option<usize>,
+}
+
+/// An index of every delimiter in a file built from
+/// a single scan, for matching brackets and
+/// computing folding ranges.
+///
+/// `Delimiter::Template` entries are the `${` and `}`
+/// around each template substitution. Unlike `TokenTree`
+/// building, unbalanced delimiters don't stop the scan,
+/// each one is recorded and available via `errors`.
+///
+/// ```
+/// use ress::BracketIndex;
+/// let js = "function f() {\n    return [1, 2];\n}";
+/// let index = BracketIndex::new(js).unwrap();
+/// let close = index.matching(13).unwrap();
+/// assert_eq!(close.span.start, 34);
+/// assert_eq!(index.matching(34).unwrap().span.start, 13);
+/// assert_eq!(index.folding_ranges()[0].end_line, 3);
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct BracketIndex {
+    delimiters: Vec<Entry>,
+    stack: Vec<usize>,
+    template_starts: Vec<usize>,
+    folding_ranges: Vec<FoldingRange>,
+    errors: Vec<DelimiterError>,
+    comment_run: Option<(usize, usize)>,
+    last_line: usize,
+}
+
+impl BracketIndex {
+    /// Scan the provided JS text and index all
+    /// of its delimiters
+    pub fn new(text: &str) -> Res<Self> {
+        Self::from_items(Scanner::with_options(
+            text,
+            ScannerOptions::new().recover(true),
+        ))
+    }
+    /// Index all of the delimiters in a stream of items,
+    /// any error other than an unmatched delimiter will
+    /// stop the indexing. Only a `Scanner` built with
+    /// `ScannerOptions::recover` keeps going after an
+    /// unmatched delimiter
+    pub fn from_items<'a, I>(items: I) -> Res<Self>
+    where
+        I: IntoIterator<Item = Res<Item<&'a str>>>,
+    {
+        let mut ret = Self::default();
+        for item in items {
+            let e = match item {
+                Ok(item) => {
+                    ret.push(&item);
+                    continue;
+                }
+                Err(e) => e,
+            };
+            match &e.kind {
+                ErrorKind::Delimiter(d) if d.close.is_some() => {
+                    let close = d.close.unwrap();
+                    ret.flush_comments();
+                    ret.last_line = close.location.end.line;
+                    ret.close(close);
+                }
+                _ => return Err(e),
+            }
+        }
+        ret.flush_comments();
+        for idx in core::mem::take(&mut ret.stack) {
+            ret.errors.push(DelimiterError {
+                open: Some(ret.delimiters[idx].location),
+                close: None,
+            });
+        }
+        ret.folding_ranges
+            .sort_by_key(|r| (r.start_line, r.end_line));
+        Ok(ret)
+    }
+    /// Find the delimiter matching the one at the
+    /// provided byte offset, `None` if there is no delimiter
+    /// at that offset or it was unmatched
+    pub fn matching(&self, offset: usize) -> Option<DelimiterLocation> {
+        let idx = self.delimiter_index(offset)?;
+        let partner = self.delimiters[idx].partner?;
+        Some(self.delimiters[partner].location)
+    }
+    /// Find the delimiter at the provided byte offset
+    pub fn delimiter_at(&self, offset: usize) -> Option<DelimiterLocation> {
+        let idx = self.delimiter_index(offset)?;
+        Some(self.delimiters[idx].location)
+    }
+    /// All of the matched pairs of delimiters, ordered
+    /// by their close delimiter
+    pub fn pairs(&self) -> impl Iterator<Item = (DelimiterLocation, DelimiterLocation)> + '_ {
+        self.delimiters
+            .iter()
+            .enumerate()
+            .filter_map(move |(i, e)| {
+                let partner = e.partner?;
+                if partner < i {
+                    Some((self.delimiters[partner].location, e.location))
+                } else {
+                    None
+                }
+            })
+    }
+    /// All of the ranges that span more than one line,
+    /// ordered by their start line
+    pub fn folding_ranges(&self) -> &[FoldingRange] {
+        &self.folding_ranges
+    }
+    /// Any unbalanced delimiters found in the text
+    pub fn errors(&self) -> &[DelimiterError] {
+        &self.errors
+    }
+
+    fn delimiter_index(&self, offset: usize) -> Option<usize> {
+        let idx = match self
+            .delimiters
+            .binary_search_by_key(&offset, |e| e.location.span.start)
+        {
+            Ok(idx) => idx,
+            Err(0) => return None,
+            Err(idx) => idx - 1,
+        };
+        if self.delimiters[idx].location.span.end > offset {
+            Some(idx)
+        } else {
+            None
+        }
+    }
+
+    fn push(&mut self, item: &Item<&str>) {
+        if let Token::Comment(comment) = &item.token {
+            if comment.is_single_line() && item.location.start.line > self.last_line {
+                let line = item.location.start.line;
+                match &mut self.comment_run {
+                    Some((_, end)) if *end + 1 == line => *end = line,
+                    _ => {
+                        self.flush_comments();
+                        self.comment_run = Some((line, line));
+                    }
+                }
+                self.last_line = item.location.end.line;
+                return;
+            }
+            if comment.is_multi_line() {
+                self.fold(item.location, FoldingKind::Comment);
+            }
+        }
+        self.flush_comments();
+        if !item.is_eof() {
+            self.last_line = item.location.end.line;
+        }
+        match &item.token {
+            Token::Punct(Punct::OpenParen) => self.open(delimiter_location(Delimiter::Paren, item)),
+            Token::Punct(Punct::OpenBracket) | Token::Punct(Punct::HashOpenBracket) => {
+                self.open(delimiter_location(Delimiter::Bracket, item))
+            }
+            Token::Punct(Punct::OpenBrace) | Token::Punct(Punct::HashOpenBrace) => {
+                self.open(delimiter_location(Delimiter::Brace, item))
+            }
+            Token::Punct(Punct::CloseParen) => {
+                self.close(delimiter_location(Delimiter::Paren, item))
+            }
+            Token::Punct(Punct::CloseBracket) => {
+                self.close(delimiter_location(Delimiter::Bracket, item))
+            }
+            Token::Punct(Punct::CloseBrace) => {
+                self.close(delimiter_location(Delimiter::Brace, item))
+            }
+            Token::Template(Template::NoSub(_)) => self.fold(item.location, FoldingKind::Template),
+            Token::Template(Template::Head(_)) => {
+                self.template_starts.push(item.location.start.line);
+                self.open(substitution_open(item));
+            }
+            Token::Template(Template::Middle(_)) => {
+                self.close(substitution_close(item));
+                self.open(substitution_open(item));
+            }
+            Token::Template(Template::Tail(_)) => {
+                self.close(substitution_close(item));
+                if let Some(start_line) = self.template_starts.pop() {
+                    self.push_fold(start_line, item.location.end.line, FoldingKind::Template);
+                }
+            }
+            _ => (),
+        }
+    }
+
+    fn open(&mut self, location: DelimiterLocation) {
+        self.stack.push(self.delimiters.len());
+        self.delimiters.push(Entry {
+            location,
+            partner: None,
+        });
+    }
+
+    fn close(&mut self, location: DelimiterLocation) {
+        let idx = self.delimiters.len();
+        self.delimiters.push(Entry {
+            location,
+            partner: None,
+        });
+        let pos = match self
+            .stack
+            .iter()
+            .rposition(|&i| self.delimiters[i].location.delimiter == location.delimiter)
+        {
+            Some(pos) => pos,
+            None => {
+                let open = self.stack.last().map(|&i| self.delimiters[i].location);
+                self.errors.push(DelimiterError {
+                    open,
+                    close: Some(location),
+                });
+                return;
+            }
+        };
+        // anything opened after our match is left unclosed
+        for &unclosed in &self.stack[pos + 1..] {
+            self.errors.push(DelimiterError {
+                open: Some(self.delimiters[unclosed].location),
+                close: Some(location),
+            });
+        }
+        let open = self.stack[pos];
+        self.stack.truncate(pos);
+        self.delimiters[open].partner = Some(idx);
+        self.delimiters[idx].partner = Some(open);
+        if location.delimiter == Delimiter::Brace {
+            let start_line = self.delimiters[open].location.location.start.line;
+            self.push_fold(start_line, location.location.end.line, FoldingKind::Block);
+        }
+    }
+
+    fn flush_comments(&mut self) {
+        if let Some((start, end)) = self.comment_run.take() {
+            self.push_fold(start, end, FoldingKind::Comment);
+        }
+    }
+
+    fn fold(&mut self, location: SourceLocation, kind: FoldingKind) {
+        self.push_fold(location.start.line, location.end.line, kind)
+    }
+
+    fn push_fold(&mut self, start_line: usize, end_line: usize, kind: FoldingKind) {
+        if end_line > start_line {
+            self.folding_ranges.push(FoldingRange {
+                start_line,
+                end_line,
+                kind,
+            });
+        }
+    }
+}
+
+/// The `${` at the end of a template head or middle
+fn substitution_open(item: &Item<&str>) -> DelimiterLocation {
+    let end = item.location.end;
+    DelimiterLocation {
+        delimiter: Delimiter::Template,
+        span: Span::new(item.span.end - 2, item.span.end),
+        location: SourceLocation::new(Position::new(end.line, end.column.saturating_sub(2)), end),
+    }
+}
+
+/// The `}` at the start of a template middle or tail
+fn substitution_close(item: &Item<&str>) -> DelimiterLocation {
+    let start = item.location.start;
+    DelimiterLocation {
+        delimiter: Delimiter::Template,
+        span: Span::new(item.span.start, item.span.start + 1),
+        location: SourceLocation::new(start, Position::new(start.line, start.column + 1)),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn matching_pairs() {
+        let js = "f(a, [b, {c: 1}])";
+        let index = BracketIndex::new(js).unwrap();
+        assert_eq!(index.matching(1).unwrap().span, Span::new(16, 17));
+        assert_eq!(index.matching(16).unwrap().span, Span::new(1, 2));
+        assert_eq!(index.matching(5).unwrap().delimiter, Delimiter::Bracket);
+        assert_eq!(index.matching(9).unwrap().span, Span::new(14, 15));
+        assert!(index.matching(0).is_none());
+        assert!(index.matching(2).is_none());
+        assert_eq!(index.pairs().count(), 3);
+        assert!(index.errors().is_empty());
+    }
+
+    #[test]
+    fn template_substitutions() {
+        let js = "`a${ {b: 1} }c${ d }e`";
+        let index = BracketIndex::new(js).unwrap();
+        let open = index.delimiter_at(3).unwrap();
+        assert_eq!(open.delimiter, Delimiter::Template);
+        assert_eq!(open.span, Span::new(2, 4));
+        assert_eq!(index.matching(2).unwrap().span, Span::new(12, 13));
+        assert_eq!(index.matching(12).unwrap().span, Span::new(2, 4));
+        assert_eq!(index.matching(5).unwrap().span, Span::new(10, 11));
+        assert_eq!(index.matching(14).unwrap().span, Span::new(19, 20));
+    }
+
+    #[test]
+    fn folding() {
+        let js = "// one
+// two
+function f() {
+    /*
+     * docs
+     */
+    let x = `a
+${b}`; // trailing
+    // lonely
+}";
+        let index = BracketIndex::new(js).unwrap();
+        assert_eq!(
+            index.folding_ranges(),
+            &[
+                FoldingRange {
+                    start_line: 1,
+                    end_line: 2,
+                    kind: FoldingKind::Comment,
+                },
+                FoldingRange {
+                    start_line: 3,
+                    end_line: 10,
+                    kind: FoldingKind::Block,
+                },
+                FoldingRange {
+                    start_line: 4,
+                    end_line: 6,
+                    kind: FoldingKind::Comment,
+                },
+                FoldingRange {
+                    start_line: 7,
+                    end_line: 8,
+                    kind: FoldingKind::Template,
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn unbalanced() {
+        let index = BracketIndex::new("{\n  a)\n  [b\n}").unwrap();
+        let errors = index.errors();
+        assert_eq!(errors.len(), 2);
+        assert_eq!(
+            errors[0].to_string(),
+            "mismatched delimiters, { at 1:1 closed by ) at 2:4"
+        );
+        assert_eq!(
+            errors[1].to_string(),
+            "mismatched delimiters, [ at 3:3 closed by } at 4:1"
+        );
+        assert_eq!(index.matching(0).unwrap().span, Span::new(12, 13));
+        let index = BracketIndex::new("(a").unwrap();
+        assert_eq!(index.errors()[0].to_string(), "unclosed delimiter ( at 1:1");
+    }
+}
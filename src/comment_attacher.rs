@@ -0,0 +1,180 @@
+use crate::{error::Error, is_line_term, tokens::Token, Item, Scanner};
+use alloc::vec::Vec;
+
+type Res<T> = Result<T, Error>;
+
+#[derive(Clone, Debug, PartialEq)]
+/// A single significant token along with the
+/// comments that belong to it
+pub struct AttachedItem<T> {
+    pub item: Item<T>,
+    /// Any comments between the previous token's
+    /// line and this token
+    pub leading_comments: Vec<Item<T>>,
+    /// Any comments that start on the same line
+    /// this token ends on
+    pub trailing_comments: Vec<Item<T>>,
+}
+
+/// An adapter over `Scanner` that yields only significant
+/// tokens, each with the comments attached to it.
+///
+/// Comments that start on the same line a token ends on are
+/// attached to that token as trailing comments, every other
+/// comment leads the next token. Comments at the end of the
+/// file will lead the `EoF` item.
+///
+/// ```
+/// use ress::CommentAttacher;
+/// let js = "// leading\nlet x = 1; // trailing";
+/// let items: Vec<_> = CommentAttacher::new(js).map(|i| i.unwrap()).collect();
+/// assert_eq!(items[0].leading_comments.len(), 1);
+/// assert_eq!(items[4].trailing_comments.len(), 1);
+/// ```
+pub struct CommentAttacher<'a> {
+    scanner: Scanner<'a>,
+    pending: Option<AttachedItem<&'a str>>,
+    leading: Vec<Item<&'a str>>,
+    on_same_line: bool,
+    error: Option<Error>,
+}
+
+impl<'a> CommentAttacher<'a> {
+    /// Create a new `CommentAttacher` by providing the
+    /// JS text
+    pub fn new(text: &'a str) -> Self {
+        Scanner::new(text).into()
+    }
+    /// Handle a single comment, either adding it to the
+    /// pending token's trailing comments or holding
+    /// it for the next token
+    fn push_comment(&mut self, item: Item<&'a str>) {
+        let spans_lines = match &item.token {
+            Token::Comment(c) => {
+                c.content.contains(is_line_term)
+                    || c.tail_content.map(|t| t.contains(is_line_term)) == Some(true)
+            }
+            _ => false,
+        };
+        match &mut self.pending {
+            Some(pending) if self.on_same_line && self.leading.is_empty() => {
+                pending.trailing_comments.push(item)
+            }
+            _ => self.leading.push(item),
+        }
+        self.on_same_line = self.on_same_line && !spans_lines;
+    }
+}
+
+impl<'a> From<Scanner<'a>> for CommentAttacher<'a> {
+    fn from(scanner: Scanner<'a>) -> Self {
+        Self {
+            scanner,
+            pending: None,
+            leading: Vec::new(),
+            on_same_line: false,
+            error: None,
+        }
+    }
+}
+
+impl<'a> Iterator for CommentAttacher<'a> {
+    type Item = Res<AttachedItem<&'a str>>;
+    fn next(&mut self) -> Option<Self::Item> {
+        if let Some(e) = self.error.take() {
+            return Some(Err(e));
+        }
+        loop {
+            let item = match self.scanner.next() {
+                Some(Ok(item)) => item,
+                Some(Err(e)) => {
+                    if let Some(pending) = self.pending.take() {
+                        self.error = Some(e);
+                        return Some(Ok(pending));
+                    }
+                    return Some(Err(e));
+                }
+                None => {
+                    let mut pending = self.pending.take()?;
+                    pending.trailing_comments.append(&mut self.leading);
+                    return Some(Ok(pending));
+                }
+            };
+            if item.token.is_comment() {
+                self.push_comment(item);
+                self.on_same_line = self.on_same_line && !self.scanner.has_pending_new_line();
+                continue;
+            }
+            let next = AttachedItem {
+                item,
+                leading_comments: core::mem::take(&mut self.leading),
+                trailing_comments: Vec::new(),
+            };
+            self.on_same_line = !self.scanner.has_pending_new_line();
+            if let Some(prev) = self.pending.replace(next) {
+                return Some(Ok(prev));
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::tokens::{Comment, Punct};
+
+    #[test]
+    fn leading_and_trailing() {
+        let js = "#!/usr/bin/env node
+/** docs */
+function thing() { // after brace
+    // inside
+    return 1; /* after return */ // also after return
+}
+// at the end";
+        let items: Vec<_> = CommentAttacher::new(js).map(|i| i.unwrap()).collect();
+        let function = &items[0];
+        assert_eq!(
+            function
+                .leading_comments
+                .iter()
+                .map(|c| &c.token)
+                .collect::<Vec<_>>(),
+            vec![
+                &Token::Comment(Comment::new_hashbang("/usr/bin/env node")),
+                &Token::Comment(Comment::new_multi_line("* docs ")),
+            ]
+        );
+        let open_brace = &items[4];
+        assert!(open_brace.item.token.matches_punct(Punct::OpenBrace));
+        assert_eq!(open_brace.trailing_comments.len(), 1);
+        let ret = &items[5];
+        assert_eq!(ret.leading_comments.len(), 1);
+        assert!(ret.trailing_comments.is_empty());
+        let semi = &items[7];
+        assert!(semi.item.token.matches_punct(Punct::SemiColon));
+        assert_eq!(semi.trailing_comments.len(), 2);
+        let eof = items.last().unwrap();
+        assert!(eof.item.is_eof());
+        assert_eq!(eof.leading_comments.len(), 1);
+    }
+
+    #[test]
+    fn multi_line_trailing_ends_line() {
+        let js = "a /*
+*/ // leads b
+b";
+        let items: Vec<_> = CommentAttacher::new(js).map(|i| i.unwrap()).collect();
+        assert_eq!(items[0].trailing_comments.len(), 1);
+        assert_eq!(items[1].leading_comments.len(), 1);
+        assert!(items[1].item.token.matches_ident_str("b"));
+    }
+
+    #[test]
+    fn error_after_pending() {
+        let mut attacher = CommentAttacher::new("a 'b");
+        assert!(attacher.next().unwrap().is_ok());
+        assert!(attacher.next().unwrap().is_err());
+        assert!(attacher.next().is_none());
+    }
+}
@@ -1,15 +1,111 @@
+use crate::{es_version::Feature, token_tree::Delimiter, Position, SourceLocation, Span};
+use alloc::{boxed::Box, string::String};
+
+/// An error found while scanning
+///
+/// More fields may be added, build one with `Error::new`
 #[derive(Clone, Debug, PartialEq, Eq)]
+#[non_exhaustive]
 pub struct Error {
     pub line: usize,
     pub column: usize,
     pub msg: String,
     pub idx: usize,
+    pub kind: ErrorKind,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+/// The category of an `Error`
+pub enum ErrorKind {
+    /// The text could not be tokenized
+    Syntax,
+    /// A close paren or brace was found without
+    /// a matching open
+    Delimiter(Box<DelimiterError>),
+    /// The input could not be read or was
+    /// not valid UTF-8
+    Input,
+    /// More delimiters were open at once than
+    /// `Limits::max_depth` allows
+    NestingTooDeep,
+    /// A token was longer than `Limits::max_token_len`
+    TokenTooLong,
+    /// More tokens were found than `Limits::max_tokens`
+    TooManyTokens,
+    /// The callback given to `Limits::step_budget`
+    /// stopped the scan
+    Cancelled,
+    /// A token used a `Feature` newer than the
+    /// `EsVersion` being targeted
+    Unsupported(Feature),
 }
 
-impl ::std::error::Error for Error {}
+#[cfg(feature = "std")]
+impl std::error::Error for Error {}
+
+impl Error {
+    /// A `ErrorKind::Syntax` error with the message
+    /// `msg` at `idx`, on `line` and `column`
+    pub fn new(line: usize, column: usize, msg: String, idx: usize) -> Self {
+        Self {
+            line,
+            column,
+            msg,
+            idx,
+            kind: ErrorKind::Syntax,
+        }
+    }
+    /// The error for a `)` or `}` with nothing open to close,
+    /// `open` being the innermost delimiter still open
+    pub(crate) fn unmatched_close(
+        close: DelimiterLocation,
+        open: Option<DelimiterLocation>,
+    ) -> Self {
+        let name = match close.delimiter {
+            Delimiter::Paren => "paren",
+            Delimiter::Bracket => "bracket",
+            _ => "brace",
+        };
+        let msg = if let Some(open) = &open {
+            format!(
+                "unmatched close {} (innermost open delimiter is {} at {})",
+                name,
+                open.delimiter.open_str(),
+                open.location.start
+            )
+        } else {
+            format!("unmatched close {}", name)
+        };
+        Error {
+            line: close.location.start.line,
+            column: close.location.start.column,
+            msg,
+            idx: close.span.start,
+            kind: ErrorKind::Delimiter(Box::new(DelimiterError {
+                open,
+                close: Some(close),
+            })),
+        }
+    }
+}
 
-impl ::std::fmt::Display for Error {
-    fn fmt(&self, f: &mut ::std::fmt::Formatter) -> ::std::fmt::Result {
+impl ErrorKind {
+    /// If this error came from one of a scanner's `Limits`,
+    /// which always stop the scan
+    pub(crate) fn is_limit(&self) -> bool {
+        matches!(
+            self,
+            ErrorKind::NestingTooDeep
+                | ErrorKind::TokenTooLong
+                | ErrorKind::TooManyTokens
+                | ErrorKind::Cancelled
+        )
+    }
+}
+
+impl ::core::fmt::Display for Error {
+    fn fmt(&self, f: &mut ::core::fmt::Formatter) -> ::core::fmt::Result {
         write!(f, "{} at {}:{}", self.msg, self.line, self.column)
     }
 }
@@ -20,10 +116,111 @@ pub struct RawError {
     pub msg: String,
 }
 
-impl ::std::error::Error for RawError {}
+#[cfg(feature = "std")]
+impl std::error::Error for RawError {}
 
-impl ::std::fmt::Display for RawError {
-    fn fmt(&self, f: &mut ::std::fmt::Formatter) -> ::std::fmt::Result {
+impl ::core::fmt::Display for RawError {
+    fn fmt(&self, f: &mut ::core::fmt::Formatter) -> ::core::fmt::Result {
         write!(f, "{} at {}", self.msg, self.idx)
     }
 }
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+/// A delimiter that was not properly matched, at least
+/// one of `open` or `close` will always be provided
+pub struct DelimiterError {
+    /// The open delimiter that was left unclosed, `None`
+    /// when a close delimiter was found with nothing open
+    pub open: Option<DelimiterLocation>,
+    /// The close delimiter that didn't match `open`, `None`
+    /// when the end of the file was reached first
+    pub close: Option<DelimiterLocation>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+/// Where a single delimiter was found
+pub struct DelimiterLocation {
+    pub delimiter: Delimiter,
+    pub span: Span,
+    pub location: SourceLocation,
+}
+
+impl DelimiterLocation {
+    /// The location of a single character delimiter
+    /// starting at the byte index and position provided
+    pub(crate) fn single(delimiter: Delimiter, start: usize, position: Position) -> Self {
+        Self {
+            delimiter,
+            span: Span::new(start, start + 1),
+            location: SourceLocation::new(
+                position,
+                Position::new(position.line, position.column + 1),
+            ),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for DelimiterError {}
+
+impl ::core::fmt::Display for DelimiterError {
+    fn fmt(&self, f: &mut ::core::fmt::Formatter) -> ::core::fmt::Result {
+        match (&self.open, &self.close) {
+            (Some(open), Some(close)) => write!(
+                f,
+                "mismatched delimiters, {} at {} closed by {} at {}",
+                open.delimiter.open_str(),
+                open.location.start,
+                close.delimiter.close_str(),
+                close.location.start
+            ),
+            (Some(open), None) => write!(
+                f,
+                "unclosed delimiter {} at {}",
+                open.delimiter.open_str(),
+                open.location.start
+            ),
+            (None, Some(close)) => write!(
+                f,
+                "unmatched delimiter {} at {}",
+                close.delimiter.close_str(),
+                close.location.start
+            ),
+            (None, None) => write!(f, "unmatched delimiter"),
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+/// Why a `TokenCache` could not be loaded
+pub enum CacheError {
+    /// The bytes are not a token cache
+    NotACache,
+    /// The cache was written by another version
+    /// of ress, which is included
+    Version(String),
+    /// The source text is not the text the
+    /// cache was written for
+    SourceChanged,
+    /// The cache ended early or held an invalid token
+    Corrupt,
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for CacheError {}
+
+impl ::core::fmt::Display for CacheError {
+    fn fmt(&self, f: &mut ::core::fmt::Formatter) -> ::core::fmt::Result {
+        match self {
+            CacheError::NotACache => write!(f, "not a token cache"),
+            CacheError::Version(v) => write!(
+                f,
+                "token cache written by ress {}, expected {}",
+                v,
+                env!("CARGO_PKG_VERSION")
+            ),
+            CacheError::SourceChanged => write!(f, "token cache source text has changed"),
+            CacheError::Corrupt => write!(f, "token cache is corrupt"),
+        }
+    }
+}
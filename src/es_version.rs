@@ -0,0 +1,413 @@
+use core::fmt;
+
+use crate::{
+    tokenizer::{RawToken, Tokenizer},
+    tokens::{Es3Word, Keyword, Punct, Token},
+    Item, SourceLocation, Span,
+};
+
+/// An edition of ECMAScript that a `Scanner` can
+/// target, see `ScannerOptions::target`
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum EsVersion {
+    Es3,
+    Es5,
+    Es2015,
+    Es2016,
+    Es2017,
+    Es2018,
+    Es2019,
+    Es2020,
+    Es2021,
+    Es2022,
+    Es2023,
+    Es2024,
+    /// Every feature this crate can scan
+    #[default]
+    Latest,
+}
+
+impl fmt::Display for EsVersion {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let year = match self {
+            EsVersion::Es3 => return write!(f, "ES3"),
+            EsVersion::Es5 => return write!(f, "ES5"),
+            EsVersion::Latest => return write!(f, "the latest ECMAScript"),
+            EsVersion::Es2015 => 2015,
+            EsVersion::Es2016 => 2016,
+            EsVersion::Es2017 => 2017,
+            EsVersion::Es2018 => 2018,
+            EsVersion::Es2019 => 2019,
+            EsVersion::Es2020 => 2020,
+            EsVersion::Es2021 => 2021,
+            EsVersion::Es2022 => 2022,
+            EsVersion::Es2023 => 2023,
+            EsVersion::Es2024 => 2024,
+        };
+        write!(f, "ES{}", year)
+    }
+}
+
+/// A lexical feature that isn't part of every `EsVersion`
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Feature {
+    /// A template literal
+    Template,
+    /// `let` or `const` as a keyword
+    LexicalDeclaration,
+    /// `=>`
+    Arrow,
+    /// `...`
+    Spread,
+    /// A number like `0b1` or `0o7`
+    BinaryOctal,
+    /// `**` or `**=`
+    Exponent,
+    /// A number like `1n`
+    BigInt,
+    /// `?.`
+    OptionalChaining,
+    /// `??`
+    NullishCoalescing,
+    /// `||=`, `&&=` or `??=`
+    LogicalAssignment,
+    /// A number like `1_000`
+    NumericSeparator,
+    /// A `#` starting a private name
+    PrivateName,
+    /// A `#!` comment at the start of the text
+    Hashbang,
+    /// A regular expression flag
+    RegexFlag(char),
+}
+
+impl Feature {
+    /// The first `EsVersion` that allows this feature
+    pub fn since(&self) -> EsVersion {
+        match self {
+            Feature::Template
+            | Feature::LexicalDeclaration
+            | Feature::Arrow
+            | Feature::Spread
+            | Feature::BinaryOctal => EsVersion::Es2015,
+            Feature::Exponent => EsVersion::Es2016,
+            Feature::BigInt | Feature::OptionalChaining | Feature::NullishCoalescing => {
+                EsVersion::Es2020
+            }
+            Feature::NumericSeparator | Feature::LogicalAssignment => EsVersion::Es2021,
+            Feature::PrivateName => EsVersion::Es2022,
+            Feature::Hashbang => EsVersion::Es2023,
+            Feature::RegexFlag(flag) => match flag {
+                'u' | 'y' => EsVersion::Es2015,
+                's' => EsVersion::Es2018,
+                'd' => EsVersion::Es2022,
+                'v' => EsVersion::Es2024,
+                _ => EsVersion::Es3,
+            },
+        }
+    }
+}
+
+impl fmt::Display for Feature {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Feature::Template => write!(f, "template literal"),
+            Feature::LexicalDeclaration => write!(f, "`let`/`const` declaration"),
+            Feature::Arrow => write!(f, "arrow function"),
+            Feature::Spread => write!(f, "spread or rest `...`"),
+            Feature::BinaryOctal => write!(f, "binary or octal literal"),
+            Feature::Exponent => write!(f, "exponent operator"),
+            Feature::BigInt => write!(f, "BigInt literal"),
+            Feature::OptionalChaining => write!(f, "optional chaining `?.`"),
+            Feature::NullishCoalescing => write!(f, "nullish coalescing `??`"),
+            Feature::LogicalAssignment => write!(f, "logical assignment"),
+            Feature::NumericSeparator => write!(f, "numeric separator"),
+            Feature::PrivateName => write!(f, "private name"),
+            Feature::Hashbang => write!(f, "hashbang comment"),
+            Feature::RegexFlag(flag) => write!(f, "regular expression flag `{}`", flag),
+        }
+    }
+}
+
+/// A token using a `Feature` newer than the `EsVersion`
+/// a `Scanner` targets
+///
+/// ```
+/// use ress::{EsVersion, Feature, Scanner, ScannerOptions};
+/// use std::sync::mpsc::channel;
+/// let (tx, rx) = channel();
+/// let options = ScannerOptions::new()
+///     .target(EsVersion::Es5)
+///     .on_unsupported(move |u| tx.send(u).unwrap());
+/// Scanner::with_options("let a = 2 ** 10n;", options).for_each(drop);
+/// let found: Vec<_> = rx.try_iter().map(|u| u.feature).collect();
+/// assert_eq!(
+///     found,
+///     [Feature::LexicalDeclaration, Feature::Exponent, Feature::BigInt]
+/// );
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Unsupported {
+    pub feature: Feature,
+    /// The token using `feature`
+    pub span: Span,
+    /// Zeroed when the `Scanner` isn't tracking locations
+    pub location: SourceLocation,
+}
+
+impl fmt::Display for Unsupported {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{} at {} requires {}",
+            self.feature,
+            self.location.start,
+            self.feature.since()
+        )
+    }
+}
+
+/// Call `f` with each `Feature` in `item` that `target` doesn't allow,
+/// `text` is what `item` was scanned from
+///
+/// This scanner has no `?.`, `??` or logical assignment tokens so
+/// those are found by looking at the text right after a `?`, `||`
+/// or `&&`
+pub(crate) fn unsupported(
+    item: &Item<&str>,
+    text: &str,
+    target: EsVersion,
+    mut f: impl FnMut(Feature),
+) {
+    if target == EsVersion::Latest {
+        return;
+    }
+    let mut check = |feature: Feature| {
+        if feature.since() > target {
+            f(feature)
+        }
+    };
+    let after = text.get(item.span.end..).unwrap_or_default();
+    match &item.token {
+        Token::Template(t) if t.is_head() || t.is_no_sub() => check(Feature::Template),
+        Token::Keyword(Keyword::Let(_)) if starts_binding(after) => {
+            check(Feature::LexicalDeclaration)
+        }
+        Token::Keyword(Keyword::Const(_)) => check(Feature::LexicalDeclaration),
+        Token::Punct(Punct::QuestionMark) => {
+            if after.starts_with("?=") {
+                check(Feature::LogicalAssignment)
+            } else if after.starts_with('?') {
+                check(Feature::NullishCoalescing)
+            } else if after.starts_with('.')
+                // `a?.5:b` is a conditional
+                && !after[1..].starts_with(|c: char| c.is_ascii_digit())
+            {
+                check(Feature::OptionalChaining)
+            }
+        }
+        Token::Punct(Punct::DoublePipe) | Token::Punct(Punct::DoubleAmpersand)
+            if after.starts_with('=') =>
+        {
+            check(Feature::LogicalAssignment)
+        }
+        Token::Punct(Punct::EqualGreaterThan) => check(Feature::Arrow),
+        Token::Punct(Punct::Ellipsis) => check(Feature::Spread),
+        Token::Punct(Punct::DoubleAsterisk) | Token::Punct(Punct::DoubleAsteriskEqual) => {
+            check(Feature::Exponent)
+        }
+        Token::Punct(Punct::Hash) => check(Feature::PrivateName),
+        Token::Number(n) => {
+            if n.is_bin() || n.is_oct() {
+                check(Feature::BinaryOctal);
+            }
+            if n.is_big_int() {
+                check(Feature::BigInt);
+            }
+            if n.has_separator() {
+                check(Feature::NumericSeparator);
+            }
+        }
+        Token::Comment(c) if c.is_hashbang() => check(Feature::Hashbang),
+        Token::RegEx(r) => {
+            for flag in r.flags.unwrap_or_default().chars() {
+                check(Feature::RegexFlag(flag));
+            }
+        }
+        _ => {}
+    }
+}
+
+/// If the next token in `text` is an identifier, `[` or `{`, the
+/// only places `let` starts a declaration instead of being a
+/// plain identifier like in `var let = 1` or `let.x`
+fn starts_binding(text: &str) -> bool {
+    let mut tokenizer = Tokenizer::new(text);
+    loop {
+        tokenizer.skip_whitespace();
+        match tokenizer.next(false).map(|i| i.ty) {
+            Ok(RawToken::Comment { .. }) => continue,
+            Ok(RawToken::Ident) | Ok(RawToken::Punct(Punct::OpenBracket)) => return true,
+            Ok(RawToken::Punct(Punct::OpenBrace)) => return true,
+            _ => return false,
+        }
+    }
+}
+
+/// Scan an identifier spelled like one of the words
+/// only ES3 reserves as a `Keyword::Es3Reserved`
+pub(crate) fn es3_reserved(item: Item<&str>) -> Item<&str> {
+    let Item {
+        token,
+        span,
+        location,
+    } = item;
+    let token = match token {
+        Token::Ident(i) => match Es3Word::from_word(i.as_ref()) {
+            Some(word) => Token::Keyword(Keyword::Es3Reserved(word, word.as_str())),
+            None => Token::Ident(i),
+        },
+        token => token,
+    };
+    Item::new(token, span, location)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{error::ErrorKind, Scanner, ScannerOptions};
+    use std::sync::{Arc, Mutex};
+
+    fn report(js: &str, target: EsVersion) -> Vec<(Feature, usize)> {
+        let found = Arc::new(Mutex::new(Vec::new()));
+        let sink = Arc::clone(&found);
+        let options = ScannerOptions::new()
+            .target(target)
+            .on_unsupported(move |u| sink.lock().unwrap().push((u.feature, u.span.start)));
+        for item in Scanner::with_options(js, options) {
+            item.unwrap();
+        }
+        let found = found.lock().unwrap();
+        found.clone()
+    }
+
+    #[test]
+    fn versions() {
+        assert!(EsVersion::Es3 < EsVersion::Es5);
+        assert!(EsVersion::Es2024 < EsVersion::Latest);
+        assert_eq!(EsVersion::default(), EsVersion::Latest);
+        assert_eq!(EsVersion::Es5.to_string(), "ES5");
+        assert_eq!(EsVersion::Es2020.to_string(), "ES2020");
+    }
+
+    #[test]
+    fn features() {
+        let js = "#!node\nconst f = (...a) => `${a}${0b1}` ** 0o7 + 1_0n; /a/gsu; this.#p";
+        let expected = [
+            (Feature::Hashbang, 0),
+            (Feature::LexicalDeclaration, 7),
+            (Feature::Spread, 18),
+            (Feature::Arrow, 24),
+            (Feature::Template, 27),
+            (Feature::BinaryOctal, 34),
+            (Feature::Exponent, 40),
+            (Feature::BinaryOctal, 43),
+            (Feature::BigInt, 49),
+            (Feature::NumericSeparator, 49),
+            (Feature::RegexFlag('s'), 55),
+            (Feature::RegexFlag('u'), 55),
+            (Feature::PrivateName, 68),
+        ];
+        assert_eq!(report(js, EsVersion::Es5), expected);
+        assert_eq!(report(js, EsVersion::Es3), expected);
+        assert_eq!(
+            report(js, EsVersion::Es2020),
+            [
+                (Feature::Hashbang, 0),
+                (Feature::NumericSeparator, 49),
+                (Feature::PrivateName, 68),
+            ]
+        );
+        assert_eq!(report(js, EsVersion::Latest), []);
+    }
+
+    #[test]
+    fn let_declarations() {
+        let js = "var let = 1; let.x; let\n[a] = b; let /* c */ {c} = d; let e;";
+        let expected = [
+            (Feature::LexicalDeclaration, 20),
+            (Feature::LexicalDeclaration, 33),
+            (Feature::LexicalDeclaration, 54),
+        ];
+        assert_eq!(report(js, EsVersion::Es5), expected);
+        let options = ScannerOptions::new().target(EsVersion::Es5);
+        assert!(Scanner::with_options("var let = 1; let.x", options).all(|i| i.is_ok()));
+    }
+
+    #[test]
+    fn operators() {
+        let js = "a?.b; a ?? b; a ||= b; a &&= b; a ??= b; a ? .5 : b; a?.5:b";
+        assert_eq!(
+            report(js, EsVersion::Es2019),
+            [
+                (Feature::OptionalChaining, 1),
+                (Feature::NullishCoalescing, 8),
+                (Feature::LogicalAssignment, 16),
+                (Feature::LogicalAssignment, 25),
+                (Feature::LogicalAssignment, 34),
+            ]
+        );
+        assert_eq!(
+            report(js, EsVersion::Es2020),
+            [
+                (Feature::LogicalAssignment, 16),
+                (Feature::LogicalAssignment, 25),
+                (Feature::LogicalAssignment, 34),
+            ]
+        );
+        assert_eq!(report(js, EsVersion::Es2021), []);
+    }
+
+    #[test]
+    fn es3_reserved() {
+        let js = "var int = goto.char; var count;";
+        let words = |target| -> Vec<_> {
+            let options = ScannerOptions::new().target(target);
+            Scanner::with_options(js, options)
+                .map(|i| i.unwrap().token)
+                .filter(|t| t.is_ident() || t.is_keyword())
+                .collect()
+        };
+        assert_eq!(
+            words(EsVersion::Es3),
+            [
+                Token::Keyword(Keyword::Var("var")),
+                Token::Keyword(Keyword::Es3Reserved(Es3Word::Int, "int")),
+                Token::Keyword(Keyword::Es3Reserved(Es3Word::Goto, "goto")),
+                Token::Keyword(Keyword::Es3Reserved(Es3Word::Char, "char")),
+                Token::Keyword(Keyword::Var("var")),
+                Token::Ident("count".into()),
+            ]
+        );
+        let es5 = words(EsVersion::Es5);
+        assert_eq!(es5.iter().filter(|t| t.is_ident()).count(), 4);
+        assert_eq!(report(js, EsVersion::Es3), []);
+        assert!(Keyword::Es3Reserved(Es3Word::Int, "int").is_reserved());
+        assert_eq!(
+            Token::Keyword(Keyword::Es3Reserved(Es3Word::Goto, "goto")).to_string(),
+            "goto"
+        );
+    }
+
+    #[test]
+    fn strict() {
+        let options = ScannerOptions::new().target(EsVersion::Es2015);
+        let items: Vec<_> = Scanner::with_options("a **= 2;\nb", options).collect();
+        assert_eq!(items.len(), 2);
+        let err = items[1].as_ref().unwrap_err();
+        assert_eq!(err.kind, ErrorKind::Unsupported(Feature::Exponent));
+        assert_eq!((err.line, err.column, err.idx), (1, 3, 2));
+    }
+}
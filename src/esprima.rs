@@ -0,0 +1,389 @@
+//! Conversions to and from the token shape produced by
+//! [Esprima](https://esprima.org)'s `tokenize` with the
+//! `range`, `loc` and `comment` options enabled
+//!
+//! Ranges and columns are counted in UTF-16 code units, lines
+//! are 1-based while columns are 0-based, as Esprima reports them.
+//! Converting between these and the byte offsets of a `Span`
+//! needs the source text, which a `Utf16Index` is built from.
+//!
+//! ```
+//! use ress::{esprima::{EsprimaToken, Utf16Index}, Scanner};
+//! let js = "'é' + x";
+//! let index = Utf16Index::new(js);
+//! let item = Scanner::new(js).nth(2).unwrap().unwrap();
+//! let token = EsprimaToken::from_item(&item, &index).unwrap();
+//! assert_eq!(
+//!     serde_json::to_string(&token).unwrap(),
+//!     r#"{"type":"Identifier","value":"x","range":[6,7],"loc":{"start":{"line":1,"column":6},"end":{"line":1,"column":7}}}"#
+//! );
+//! assert_eq!(token.into_item(&index).unwrap(), item.into_owned());
+//! ```
+use alloc::{
+    string::{String, ToString},
+    vec::Vec,
+};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    error::{Error, ErrorKind},
+    tokenizer::{RawToken, Tokenizer},
+    tokens::{Comment, CommentKind, Ident, Template, Token},
+    Item, LineIndex, Position, Scanner, SourceLocation, Span,
+};
+
+/// Scan all of `text` into the tokens Esprima's
+/// `tokenize` would produce with `range`, `loc` and
+/// `comment` enabled
+pub fn tokenize(text: &str) -> Result<Vec<EsprimaToken>, Error> {
+    let index = Utf16Index::new(text);
+    let mut ret = Vec::new();
+    for item in Scanner::new(text) {
+        if let Some(token) = EsprimaToken::from_item(&item?, &index) {
+            ret.push(token);
+        }
+    }
+    Ok(ret)
+}
+
+/// Converts the byte offsets of some text into the UTF-16
+/// offsets and line/column positions Esprima reports, and back
+pub struct Utf16Index<'a> {
+    text: &'a str,
+    lines: LineIndex,
+    /// The UTF-16 offset each line starts at
+    line_starts: Vec<usize>,
+}
+
+impl<'a> Utf16Index<'a> {
+    pub fn new(text: &'a str) -> Self {
+        let lines = LineIndex::new(text);
+        let mut line_starts = Vec::with_capacity(lines.line_count());
+        let (mut prev, mut offset) = (0, 0);
+        for line in 1..=lines.line_count() {
+            let start = lines.line_start(line).unwrap_or(prev);
+            offset += utf16_len(&text[prev..start]);
+            line_starts.push(offset);
+            prev = start;
+        }
+        Self {
+            text,
+            lines,
+            line_starts,
+        }
+    }
+    /// The UTF-16 offset of the byte index `idx`
+    pub fn offset(&self, idx: usize) -> usize {
+        let (line, start) = self.line_of(idx);
+        self.line_starts[line - 1] + utf16_len(&self.text[start..idx.min(self.text.len())])
+    }
+    /// The line and 0-based UTF-16 column of the byte index `idx`
+    pub fn position(&self, idx: usize) -> Position {
+        let (line, start) = self.line_of(idx);
+        Position::new(line, utf16_len(&self.text[start..idx.min(self.text.len())]))
+    }
+    /// The byte index of the UTF-16 `offset`, `None` if it is
+    /// past the end of the text or in the middle of a character
+    pub fn byte_index(&self, offset: usize) -> Option<usize> {
+        let line = self.line_starts.partition_point(|start| *start <= offset);
+        let mut idx = self.lines.line_start(line)?;
+        let mut remaining = offset - self.line_starts[line - 1];
+        for c in self.text[idx..].chars() {
+            if remaining == 0 {
+                break;
+            }
+            remaining = remaining.checked_sub(c.len_utf16())?;
+            idx += c.len_utf8();
+        }
+        if remaining == 0 {
+            Some(idx)
+        } else {
+            None
+        }
+    }
+    /// The 1-based line the byte index `idx`
+    /// is on and the byte index that line starts at
+    fn line_of(&self, idx: usize) -> (usize, usize) {
+        let line = self.lines.position(idx).line;
+        (line, self.lines.line_start(line).unwrap_or(0))
+    }
+}
+
+fn utf16_len(s: &str) -> usize {
+    s.chars().map(char::len_utf16).sum()
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+/// The `type` of an Esprima token
+pub enum EsprimaKind {
+    Boolean,
+    Identifier,
+    Keyword,
+    Null,
+    Numeric,
+    Punctuator,
+    String,
+    RegularExpression,
+    Template,
+    LineComment,
+    BlockComment,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+/// The `pattern` and `flags` of a regular expression token
+pub struct EsprimaRegex {
+    pub pattern: String,
+    pub flags: String,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+/// A single token as Esprima would report it
+pub struct EsprimaToken {
+    #[serde(rename = "type")]
+    pub kind: EsprimaKind,
+    /// The source text of the token, for comments
+    /// this excludes the comment delimiters
+    pub value: String,
+    /// The UTF-16 offsets of the start and end of the token
+    pub range: [usize; 2],
+    /// The location of the token, the columns here are
+    /// 0-based and counted in UTF-16 code units
+    pub loc: SourceLocation,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub regex: Option<EsprimaRegex>,
+}
+
+impl EsprimaToken {
+    /// Convert an `Item` scanned from the text `index` was built
+    /// from into an Esprima token, returning `None` for the end
+    /// of file which Esprima does not report
+    ///
+    /// HTML comments and hashbangs become line comments,
+    /// ress keywords are all reported as `Keyword`.
+    pub fn from_item<T: AsRef<str>>(item: &Item<T>, index: &Utf16Index) -> Option<Self> {
+        let mut regex = None;
+        let (kind, value) = match &item.token {
+            Token::EoF => return None,
+            Token::Boolean(_) => (EsprimaKind::Boolean, item.token.to_string()),
+            Token::Ident(_) => (EsprimaKind::Identifier, item.token.to_string()),
+            Token::Keyword(_) => (EsprimaKind::Keyword, item.token.to_string()),
+            Token::Null => (EsprimaKind::Null, item.token.to_string()),
+            Token::Number(_) => (EsprimaKind::Numeric, item.token.to_string()),
+            Token::Punct(_) => (EsprimaKind::Punctuator, item.token.to_string()),
+            Token::String(_) => (EsprimaKind::String, item.token.to_string()),
+            Token::Template(_) => (EsprimaKind::Template, item.token.to_string()),
+            Token::RegEx(r) => {
+                regex = Some(EsprimaRegex {
+                    pattern: r.body.as_ref().to_string(),
+                    flags: r
+                        .flags
+                        .as_ref()
+                        .map(|f| f.as_ref().to_string())
+                        .unwrap_or_default(),
+                });
+                (EsprimaKind::RegularExpression, item.token.to_string())
+            }
+            Token::Comment(c) => {
+                let kind = if c.kind == CommentKind::Multi {
+                    EsprimaKind::BlockComment
+                } else {
+                    EsprimaKind::LineComment
+                };
+                (kind, c.content.as_ref().to_string())
+            }
+        };
+        let Span { start, end } = item.span;
+        Some(Self {
+            kind,
+            value,
+            range: [index.offset(start), index.offset(end)],
+            loc: SourceLocation::new(index.position(start), index.position(end)),
+            regex,
+        })
+    }
+
+    /// Convert this Esprima token from the text `index` was
+    /// built from back into an `Item`, failing if the `value` is
+    /// not a single token of this token's `type` or the `range`
+    /// isn't in the text
+    pub fn into_item(self, index: &Utf16Index) -> Result<Item<String>, Error> {
+        let span = match (
+            index.byte_index(self.range[0]),
+            index.byte_index(self.range[1]),
+        ) {
+            (Some(start), Some(end)) if start <= end => Some(Span::new(start, end)),
+            _ => None,
+        };
+        let (token, span) = match (self.token(), span) {
+            (Some(token), Some(span)) => (token, span),
+            (_, span) => {
+                let msg = if span.is_some() {
+                    format!("invalid {:?} token: {:?}", self.kind, self.value)
+                } else {
+                    format!("range {:?} is not in the text", self.range)
+                };
+                return Err(Error {
+                    line: self.loc.start.line,
+                    column: self.loc.start.column + 1,
+                    msg,
+                    idx: span.map_or(self.range[0], |s| s.start),
+                    kind: ErrorKind::Syntax,
+                });
+            }
+        };
+        Ok(Item::new(token, span, index.lines.location(span)))
+    }
+
+    fn token(&self) -> Option<Token<String>> {
+        let value = self.value.as_str();
+        let token = match self.kind {
+            EsprimaKind::Identifier => Token::Ident(Ident::from(value)),
+            EsprimaKind::LineComment => Token::Comment(Comment::new_single_line(value)),
+            EsprimaKind::BlockComment => Token::Comment(Comment::new_multi_line(value)),
+            EsprimaKind::Punctuator => {
+                let raw = Tokenizer::new(value).next(false).ok()?;
+                match raw.ty {
+                    RawToken::Punct(p) if raw.end == value.len() => Token::Punct(p),
+                    _ => return None,
+                }
+            }
+            EsprimaKind::Template if value.starts_with('}') => {
+                // a template continuing after a substitution
+                // can only be scanned from its own start
+                return match scan_one(&format!("`{}", &value[1..]))? {
+                    Token::Template(Template::Head(t)) => {
+                        Some(Token::Template(Template::Middle(t)))
+                    }
+                    Token::Template(Template::NoSub(t)) => Some(Token::Template(Template::Tail(t))),
+                    _ => None,
+                };
+            }
+            _ => {
+                let token = scan_one(value)?;
+                let matches = match self.kind {
+                    EsprimaKind::Boolean => token.is_boolean(),
+                    EsprimaKind::Keyword => token.is_keyword(),
+                    EsprimaKind::Null => token.is_null(),
+                    EsprimaKind::Numeric => token.is_number(),
+                    EsprimaKind::String => token.is_string(),
+                    EsprimaKind::RegularExpression => token.is_regex(),
+                    EsprimaKind::Template => token.is_template(),
+                    _ => false,
+                };
+                return if matches { Some(token) } else { None };
+            }
+        };
+        Some(token.into_owned())
+    }
+}
+
+/// Scan `js` expecting exactly one token
+fn scan_one(js: &str) -> Option<Token<String>> {
+    let item = Scanner::new(js).next()?.ok()?;
+    if item.span.end != js.len() {
+        return None;
+    }
+    Some(item.token.into_owned())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn round_trip() {
+        let js = "function f(a) {
+    // line \u{1F600}
+    return `a${a}b${/* block é */ 'c'}d` / 2 + /x\\/y/gi + 0xff + true + null;
+}";
+        let index = Utf16Index::new(js);
+        let items: Vec<_> = Scanner::new(js)
+            .map(|i| i.unwrap().into_owned())
+            .filter(|i: &Item<String>| !i.token.is_eof())
+            .collect();
+        let json = serde_json::to_string(&tokenize(js).unwrap()).unwrap();
+        let back: Vec<EsprimaToken> = serde_json::from_str(&json).unwrap();
+        let back: Vec<_> = back
+            .into_iter()
+            .map(|t| t.into_item(&index).unwrap())
+            .collect();
+        assert_eq!(back, items);
+    }
+
+    #[test]
+    fn esprima_shape() {
+        let tokens = tokenize("\n  x = /a/g; // c").unwrap();
+        assert_eq!(
+            serde_json::to_value(&tokens[1]).unwrap(),
+            serde_json::json!({
+                "type": "Punctuator",
+                "value": "=",
+                "range": [5, 6],
+                "loc": {"start": {"line": 2, "column": 4}, "end": {"line": 2, "column": 5}},
+            })
+        );
+        assert_eq!(
+            serde_json::to_value(&tokens[2]).unwrap()["regex"],
+            serde_json::json!({"pattern": "a", "flags": "g"})
+        );
+        assert_eq!(tokens[4].kind, EsprimaKind::LineComment);
+        assert_eq!(tokens[4].value, " c");
+        assert_eq!(tokens.len(), 5);
+    }
+
+    #[test]
+    fn utf16() {
+        // é is 1 UTF-16 code unit and 😀 is a surrogate pair of 2
+        let tokens = tokenize("'é' + x\n'\u{1F600}' + y").unwrap();
+        let ranges: Vec<_> = tokens
+            .iter()
+            .map(|t| (t.range, t.loc.start.column, t.loc.end.column))
+            .collect();
+        assert_eq!(
+            ranges,
+            [
+                ([0, 3], 0, 3),
+                ([4, 5], 4, 5),
+                ([6, 7], 6, 7),
+                ([8, 12], 0, 4),
+                ([13, 14], 5, 6),
+                ([15, 16], 7, 8),
+            ]
+        );
+        let index = Utf16Index::new("a\u{1F600}b");
+        assert_eq!(index.byte_index(3), Some(5));
+        assert_eq!(index.byte_index(2), None);
+        assert_eq!(index.byte_index(5), None);
+    }
+
+    #[test]
+    fn invalid_tokens() {
+        let index = Utf16Index::new("   +++ x 'a 1 2 }a");
+        for (kind, value) in &[
+            (EsprimaKind::Punctuator, "+++"),
+            (EsprimaKind::Keyword, "x"),
+            (EsprimaKind::String, "'a"),
+            (EsprimaKind::Numeric, "1 2"),
+            (EsprimaKind::Template, "}a"),
+        ] {
+            let token = EsprimaToken {
+                kind: *kind,
+                value: value.to_string(),
+                range: [3, 3 + value.len()],
+                loc: SourceLocation::new(Position::new(1, 3), Position::new(1, 3 + value.len())),
+                regex: None,
+            };
+            let err = token.into_item(&index).unwrap_err();
+            assert_eq!((err.idx, err.line, err.column), (3, 1, 4));
+        }
+        let token = EsprimaToken {
+            kind: EsprimaKind::Identifier,
+            value: "x".to_string(),
+            range: [30, 31],
+            loc: SourceLocation::new(Position::new(1, 30), Position::new(1, 31)),
+            regex: None,
+        };
+        let err = token.into_item(&index).unwrap_err();
+        assert_eq!(err.msg, "range [30, 31] is not in the text");
+    }
+}
@@ -0,0 +1,289 @@
+use alloc::{string::String, vec, vec::Vec};
+
+use crate::{
+    error::Error,
+    token_cache::fnv1a,
+    tokens::{Keyword, Token, KEYWORDS},
+    Item, Scanner,
+};
+
+type Res<T> = Result<T, Error>;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+/// A handle to a string held by an `Interner`
+pub struct Symbol(u32);
+
+impl Symbol {
+    /// The index of this symbol in its `Interner`
+    pub fn as_u32(self) -> u32 {
+        self.0
+    }
+}
+
+/// A table of strings, handing out a `Symbol` for each
+/// unique string that can be compared or hashed in O(1)
+///
+/// Every keyword is interned up front, so `Interner::keyword`
+/// is the same in every `Interner`. The strings are kept one
+/// after another in a single buffer and looked up through
+/// a table of `Symbol`s, so each is only stored once.
+///
+/// ```
+/// use ress::Interner;
+/// let mut interner = Interner::new();
+/// let a = interner.intern("thing");
+/// assert_eq!(interner.intern("thing"), a);
+/// assert_eq!(interner.resolve(a), "thing");
+/// assert_eq!(interner.get("if"), Some(Interner::keyword(&ress::tokens::Keyword::If(()))));
+/// ```
+#[derive(Clone, Debug)]
+pub struct Interner {
+    /// Every interned string
+    text: String,
+    /// Where each string ends in `text`, by `Symbol`
+    ends: Vec<usize>,
+    /// An open addressing table keyed by the hash of the
+    /// string each `Symbol` stands for, its length is
+    /// always a power of 2 and at least twice `ends.len()`
+    table: Vec<Option<Symbol>>,
+}
+
+impl Interner {
+    /// Create a new `Interner` holding only the keywords
+    pub fn new() -> Self {
+        let mut ret = Self {
+            text: String::new(),
+            ends: Vec::new(),
+            table: vec![None; 128],
+        };
+        for k in KEYWORDS.iter() {
+            ret.intern(k.as_str());
+        }
+        ret
+    }
+
+    /// Get the `Symbol` for `s`, adding it if
+    /// it hasn't been seen before
+    pub fn intern(&mut self, s: &str) -> Symbol {
+        let slot = match self.slot(s) {
+            Ok(sym) => return sym,
+            Err(slot) => slot,
+        };
+        let sym = Symbol(self.ends.len() as u32);
+        self.text.push_str(s);
+        self.ends.push(self.text.len());
+        self.table[slot] = Some(sym);
+        if self.ends.len() * 2 > self.table.len() {
+            self.grow();
+        }
+        sym
+    }
+
+    /// Get the `Symbol` for `s` if it has been interned
+    pub fn get(&self, s: &str) -> Option<Symbol> {
+        self.slot(s).ok()
+    }
+
+    /// The string a `Symbol` stands for
+    ///
+    /// # Panics
+    /// If `sym` was created by another `Interner`
+    pub fn resolve(&self, sym: Symbol) -> &str {
+        let idx = sym.0 as usize;
+        let start = if idx == 0 { 0 } else { self.ends[idx - 1] };
+        &self.text[start..self.ends[idx]]
+    }
+
+    /// The `Symbol` for a keyword, this will be the
+    /// same no matter how it was written in the JS text
+    pub fn keyword<T>(keyword: &Keyword<T>) -> Symbol {
+        Symbol(keyword.index() as u32)
+    }
+
+    /// The number of strings interned
+    pub fn len(&self) -> usize {
+        self.ends.len()
+    }
+
+    /// If no strings have been interned, this is
+    /// never true since keywords are interned up front
+    pub fn is_empty(&self) -> bool {
+        self.ends.is_empty()
+    }
+
+    /// The symbol for `s`, or the empty slot
+    /// in `table` it would go in
+    fn slot(&self, s: &str) -> Result<Symbol, usize> {
+        let mask = self.table.len() - 1;
+        let mut slot = fnv1a(s.as_bytes()) as usize & mask;
+        loop {
+            match self.table[slot] {
+                Some(sym) if self.resolve(sym) == s => return Ok(sym),
+                Some(_) => slot = (slot + 1) & mask,
+                None => return Err(slot),
+            }
+        }
+    }
+
+    fn grow(&mut self) {
+        self.table = vec![None; self.table.len() * 2];
+        for idx in 0..self.ends.len() {
+            let sym = Symbol(idx as u32);
+            if let Err(slot) = self.slot(self.resolve(sym)) {
+                self.table[slot] = Some(sym);
+            }
+        }
+    }
+}
+
+impl Default for Interner {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+/// The text of a token from an `InterningScanner`, borrowed
+/// from the original text along with a `Symbol` for
+/// identifiers and keywords
+pub struct Interned<'a> {
+    text: &'a str,
+    symbol: Option<Symbol>,
+}
+
+impl<'a> Interned<'a> {
+    /// The original text of the token
+    pub fn text(&self) -> &'a str {
+        self.text
+    }
+    /// The `Symbol` for an identifier or keyword, `None`
+    /// for the text of any other token
+    pub fn symbol(&self) -> Option<Symbol> {
+        self.symbol
+    }
+}
+
+impl<'a> AsRef<str> for Interned<'a> {
+    fn as_ref(&self) -> &str {
+        self.text
+    }
+}
+
+/// A scanner that interns the names of identifiers and
+/// keywords, yielding `Item`s that hold an `Interned` in
+/// place of their text. The text of every other token is
+/// left borrowed rather than growing the `Interner`
+///
+/// ```
+/// use ress::{Interner, InterningScanner, tokens::Token};
+/// let mut interner = Interner::new();
+/// let items: Vec<_> = InterningScanner::new("a.b = 'c'", &mut interner)
+///     .map(|i| i.unwrap().token)
+///     .collect();
+/// if let Token::Ident(b) = &items[2] {
+///     assert_eq!(interner.resolve(b.symbol().unwrap()), "b");
+/// }
+/// assert_eq!(interner.get("c"), None);
+/// ```
+pub struct InterningScanner<'a, 'i> {
+    scanner: Scanner<'a>,
+    interner: &'i mut Interner,
+}
+
+impl<'a, 'i> InterningScanner<'a, 'i> {
+    /// Create a new `InterningScanner` adding
+    /// to the `Interner` provided
+    pub fn new(text: &'a str, interner: &'i mut Interner) -> Self {
+        Self {
+            scanner: Scanner::new(text),
+            interner,
+        }
+    }
+}
+
+impl<'a, 'i> Iterator for InterningScanner<'a, 'i> {
+    type Item = Res<Item<Interned<'a>>>;
+    fn next(&mut self) -> Option<Self::Item> {
+        let item = match self.scanner.next()? {
+            Ok(item) => item,
+            Err(e) => return Some(Err(e)),
+        };
+        let token = match item.token {
+            Token::Keyword(k) => {
+                let symbol = Some(Interner::keyword(&k));
+                Token::Keyword(k.map(|text| Interned { text, symbol }))
+            }
+            Token::Ident(i) => {
+                let symbol = Some(self.interner.intern(i.as_ref()));
+                Token::Ident(i.map(|text| Interned { text, symbol }))
+            }
+            token => token.map(|text| Interned { text, symbol: None }),
+        };
+        Some(Ok(Item::new(token, item.span, item.location)))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn keywords() {
+        let interner = Interner::new();
+        assert_eq!(interner.len(), KEYWORDS.len());
+        for k in KEYWORDS.iter() {
+            let sym = Interner::keyword(k);
+            assert_eq!(interner.get(k.as_str()), Some(sym));
+            assert_eq!(interner.resolve(sym), k.as_str());
+        }
+    }
+
+    #[test]
+    fn grow() {
+        let mut interner = Interner::new();
+        let names: Vec<_> = (0..1000).map(|i| format!("n{}", i)).collect();
+        let symbols: Vec<_> = names.iter().map(|n| interner.intern(n)).collect();
+        assert_eq!(interner.len(), KEYWORDS.len() + names.len());
+        for (name, sym) in names.iter().zip(symbols) {
+            assert_eq!(interner.get(name), Some(sym));
+            assert_eq!(interner.intern(name), sym);
+            assert_eq!(interner.resolve(sym), name);
+        }
+        assert_eq!(interner.get("n1000"), None);
+        assert_eq!(interner.text.len(), interner.ends[interner.len() - 1]);
+    }
+
+    #[test]
+    fn scanner() {
+        let js = "function f(a) { return a.b + 'b' + f(a.b) + 'c' + 1 }";
+        let mut interner = Interner::new();
+        let items: Vec<_> = InterningScanner::new(js, &mut interner)
+            .map(|i| i.unwrap())
+            .collect();
+        let expected: Vec<_> = Scanner::new(js).map(|i| i.unwrap()).collect();
+        assert_eq!(items.len(), expected.len());
+        for (item, expected) in items.iter().zip(expected) {
+            assert_eq!(item.span, expected.span);
+            assert_eq!(item.location, expected.location);
+            let text = item.token.clone().map(|s| s.text());
+            assert_eq!(text, expected.token);
+            let named = matches!(item.token, Token::Ident(_) | Token::Keyword(_));
+            item.token
+                .clone()
+                .map(|s| assert_eq!(s.symbol().is_some(), named));
+        }
+        match &items[9].token {
+            Token::Ident(b) => assert_eq!(b.symbol(), interner.get("b")),
+            _ => panic!("expected an ident: {:?}", items[9]),
+        }
+        match &items[0].token {
+            Token::Keyword(Keyword::Function(f)) => {
+                assert_eq!(f.symbol(), Some(Interner::keyword(&Keyword::Function(()))))
+            }
+            _ => panic!("expected function: {:?}", items[0]),
+        }
+        // only f, a and b are added, not 'b', 'c' or 1
+        assert_eq!(interner.len(), KEYWORDS.len() + 3);
+        assert_eq!(interner.get("c"), None);
+    }
+}
@@ -0,0 +1,233 @@
+use crate::{
+    error::{DelimiterLocation, Error, ErrorKind, RawError},
+    line_index::LineIndex,
+    look_behind::{MetaToken, RegexDetector},
+    tokenizer::{RawToken, StringKind, TemplateKind, Tokenizer},
+    tokens::{CommentKind, Quote, TemplatePart, TokenKind},
+    ManualScanner, Position, Span,
+};
+
+type Res<T> = Result<T, Error>;
+
+/// A scanner that only reports the kind and span of each token
+///
+/// This skips building a `Token` and any line/column tracking,
+/// only counting new lines as needed to tell a regex from
+/// division. Errors will still include their line/column.
+///
+/// ```
+/// use ress::{KindScanner, tokens::TokenKind};
+/// let kinds: Vec<_> = KindScanner::new("x = /a/g")
+///     .map(|r| r.unwrap().0)
+///     .collect();
+/// assert_eq!(kinds[2], TokenKind::RegEx);
+/// ```
+pub struct KindScanner<'a> {
+    original: &'a str,
+    stream: Tokenizer<'a>,
+    regex_detector: RegexDetector,
+    new_line_count: usize,
+    at_first_on_line: bool,
+    eof: bool,
+    errored: bool,
+}
+
+impl<'a> KindScanner<'a> {
+    /// Create a new `KindScanner` by providing the
+    /// JS text
+    pub fn new(text: &'a str) -> Self {
+        let mut stream = Tokenizer::new(text);
+        let (new_line_count, _) = stream.skip_whitespace();
+        Self {
+            original: text,
+            stream,
+            regex_detector: RegexDetector::new(),
+            new_line_count,
+            at_first_on_line: true,
+            eof: false,
+            errored: false,
+        }
+    }
+
+    fn next_kind(&mut self) -> Res<(TokenKind, Span)> {
+        let mut next = match self.stream.next(self.at_first_on_line) {
+            Ok(next) => next,
+            Err(e) => {
+                self.errored = true;
+                return Err(self.error(e));
+            }
+        };
+        if next.ty.is_div_punct() && self.regex_detector.is_regex_start() {
+            next = match self.stream.next_regex(next.end - next.start) {
+                Ok(next) => next,
+                Err(e) => {
+                    self.errored = true;
+                    return Err(self.error(e));
+                }
+            };
+        }
+        let span = Span::new(next.start, next.end);
+        let (kind, new_lines) = match next.ty {
+            RawToken::Boolean(b) => (TokenKind::Boolean(b), 0),
+            RawToken::EoF => {
+                self.eof = true;
+                return Ok((TokenKind::EoF, span));
+            }
+            RawToken::Ident => (TokenKind::Ident, 0),
+            RawToken::Keyword(k) => (TokenKind::Keyword(k.with_str(())), 0),
+            RawToken::Null => (TokenKind::Null, 0),
+            RawToken::Number(n) => (TokenKind::Number(n), 0),
+            RawToken::Punct(p) => (TokenKind::Punct(p), 0),
+            RawToken::RegEx(_) => (TokenKind::RegEx, 0),
+            RawToken::String {
+                kind,
+                new_line_count,
+                ..
+            } => {
+                let quote = match kind {
+                    StringKind::Single => Quote::Single,
+                    StringKind::Double => Quote::Double,
+                };
+                (TokenKind::String(quote), new_line_count)
+            }
+            RawToken::Template {
+                kind,
+                new_line_count,
+                ..
+            } => {
+                let part = match kind {
+                    TemplateKind::NoSub => TemplatePart::NoSub,
+                    TemplateKind::Head => TemplatePart::Head,
+                    TemplateKind::Body => TemplatePart::Middle,
+                    TemplateKind::Tail => TemplatePart::Tail,
+                };
+                (TokenKind::Template(part), new_line_count)
+            }
+            RawToken::Comment {
+                kind,
+                new_line_count,
+                ..
+            } => {
+                if kind == CommentKind::Html {
+                    let comment = &self.original[next.start..next.end];
+                    let first_on_line = crate::first_on_line(self.original, next.start);
+                    if let Some(e) =
+                        ManualScanner::html_comment_error(comment, next.start, first_on_line)
+                    {
+                        self.errored = true;
+                        return Err(self.error(e));
+                    }
+                }
+                (TokenKind::Comment(kind), new_line_count)
+            }
+        };
+        self.at_first_on_line =
+            self.at_first_on_line && kind == TokenKind::Comment(CommentKind::Multi);
+        self.bump_lines(new_lines);
+        let (new_lines, _) = self.stream.skip_whitespace();
+        self.bump_lines(new_lines);
+        if !kind.is_comment() {
+            let line = self.new_line_count as u32;
+            let text = &self.original[next.start..next.end];
+            let meta = MetaToken::from_raw(&next.ty, text, line);
+            if let Err(delimiter) =
+                self.regex_detector
+                    .keep_books(meta, next.start, Position::new(0, 0), line)
+            {
+                self.errored = true;
+                return Err(self.delimiter_error(delimiter, span));
+            }
+        }
+        Ok((kind, span))
+    }
+
+    #[inline]
+    fn bump_lines(&mut self, ct: usize) {
+        if ct > 0 {
+            self.new_line_count += ct;
+            self.at_first_on_line = true;
+        }
+    }
+
+    fn error(&self, e: RawError) -> Error {
+        let position = LineIndex::new(self.original).position(e.idx);
+        Error {
+            line: position.line,
+            column: position.column,
+            msg: e.msg,
+            idx: e.idx,
+            kind: ErrorKind::Syntax,
+        }
+    }
+
+    fn delimiter_error(&self, delimiter: crate::Delimiter, span: Span) -> Error {
+        let lines = LineIndex::new(self.original);
+        let open = self
+            .regex_detector
+            .innermost_open()
+            .map(|(delimiter, start, _)| {
+                DelimiterLocation::single(delimiter, start, lines.position(start))
+            });
+        let close = DelimiterLocation::single(delimiter, span.start, lines.position(span.start));
+        Error::unmatched_close(close, open)
+    }
+}
+
+impl<'a> Iterator for KindScanner<'a> {
+    type Item = Res<(TokenKind, Span)>;
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.eof || self.errored {
+            return None;
+        }
+        Some(self.next_kind())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::Scanner;
+
+    fn compare(js: &str) {
+        let kinds: Vec<_> = KindScanner::new(js).map(|r| r.unwrap()).collect();
+        let expected: Vec<_> = Scanner::new(js)
+            .map(|i| i.unwrap())
+            .map(|i| (i.token.kind(), i.span))
+            .collect();
+        assert_eq!(kinds, expected);
+    }
+
+    #[test]
+    fn matches_scanner() {
+        compare(
+            "#!/usr/bin/env node
+function thing(a, b) {
+    let x = 'single' + \"double\" / 2;
+    var re = /[a-z]+\\//gi; // single
+    if (a) /b/.test(x);
+    /* multi */ return `head${x / 2}middle${{a: /b/}}tail`;
+}
+<!-- html comment
+x = 0xff + 0b1 + 0o7 + 1n + 1.5e2 + true + null;
+function f() {} /a/g
+let g = {} / 2
+return
+{} /a/",
+        );
+    }
+
+    #[test]
+    fn errors_have_positions() {
+        let mut scanner = KindScanner::new("{\n  a)\nb");
+        let _open = scanner.next().unwrap().unwrap();
+        let _a = scanner.next().unwrap().unwrap();
+        let err = scanner.next().unwrap().unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "unmatched close paren (innermost open delimiter is { at 1:1) at 2:4"
+        );
+        assert!(scanner.next().is_none());
+        let err = KindScanner::new("\n 'a").next().unwrap().unwrap_err();
+        assert_eq!((err.line, err.column), (2, 3));
+    }
+}
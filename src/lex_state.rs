@@ -0,0 +1,528 @@
+use alloc::vec::Vec;
+
+use crate::{
+    error::Error,
+    look_behind::{Brace, LookBehind, MetaToken, Paren},
+    tokenizer::RawKeyword,
+    tokens::Punct,
+    LineIndex, OpenCurlyKind, Position, Scanner,
+};
+
+type Res<T> = Result<T, Error>;
+
+/// The line number the first line of a resumed text is
+/// counted as, the lines of tokens before it count back
+/// from here so they never match the 0 of a token with no line
+pub(crate) const FIRST_LINE: u32 = u32::MAX / 2;
+
+/// The lexical state of a `Scanner` between two tokens,
+/// enough to pick up scanning a later piece of the same
+/// text with `Scanner::resume`
+///
+/// This covers any multi-line comment, template literal or
+/// string continued on the next line, the nesting of
+/// template substitutions and blocks, and the tokens needed
+/// to tell a regular expression from division. Line numbers
+/// are kept relative to the end of the state, so two states
+/// are equal whenever scanning from them would be the same,
+/// which makes it useful for caching the state at each line
+/// in a highlighter.
+///
+/// Delimiters opened before a state was captured have no
+/// location, any error that would point to one will point
+/// to the start of the text instead.
+///
+/// ```
+/// use ress::{LexState, Scanner, tokens::Token};
+/// let mut scanner = Scanner::resume("let a = `one\n", &LexState::new());
+/// let template = scanner.by_ref().nth(3).unwrap().unwrap();
+/// assert!(template.token.is_template());
+/// let state = scanner.lex_state();
+/// assert!(state.in_template());
+/// let tail = Scanner::resume("two` / 2", &state).next().unwrap().unwrap();
+/// assert!(tail.token.is_template_tail());
+/// assert_eq!(tail.span.end, 4);
+/// ```
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct LexState {
+    open: Option<OpenToken>,
+    /// If this is anywhere but the start of the source text,
+    /// where a hashbang comment can appear
+    mid_source: bool,
+    curly_stack: Vec<OpenCurlyKind>,
+    /// The last three tokens, oldest first
+    last_three: [Option<LexToken>; 3],
+    paren_stack: Vec<LexParen>,
+    brace_stack: Vec<LexBrace>,
+    class_heading: Option<(bool, usize)>,
+}
+
+/// A token cut off by the end of a text
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub(crate) enum OpenToken {
+    Comment,
+    /// `head` is true if the template started
+    /// with a back tick rather than a `}`
+    Template {
+        head: bool,
+    },
+    String {
+        double: bool,
+    },
+}
+
+/// A `MetaToken` without its byte index or position, with
+/// the line numbers ranked by how many lines back they are
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+enum LexToken {
+    Keyword(RawKeyword, u32),
+    Punct(Punct),
+    OpenParen(LexParen),
+    CloseParen(LexParen),
+    OpenBrace(LexBrace, u32),
+    CloseBrace(LexBrace),
+    OpenTemplate,
+    Ident,
+    Of,
+    Async,
+    Other,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+struct LexParen {
+    func_expr: bool,
+    conditional: bool,
+    for_head: bool,
+    function: bool,
+    generator: bool,
+    is_async: bool,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+struct LexBrace {
+    is_block: bool,
+    paren: Option<LexParen>,
+    yield_kw: bool,
+    await_kw: bool,
+}
+
+impl LexState {
+    /// The state at the start of the source text
+    pub fn new() -> Self {
+        Self::default()
+    }
+    /// If this state is inside of a multi-line comment
+    pub fn in_comment(&self) -> bool {
+        self.open == Some(OpenToken::Comment)
+    }
+    /// If this state is inside of the text of a template literal
+    pub fn in_template(&self) -> bool {
+        matches!(self.open, Some(OpenToken::Template { .. }))
+    }
+    /// If this state is inside of a string continued
+    /// with an escaped new line
+    pub fn in_string(&self) -> bool {
+        matches!(self.open, Some(OpenToken::String { .. }))
+    }
+    /// The number of template substitutions and blocks open
+    pub fn depth(&self) -> usize {
+        self.curly_stack.len()
+    }
+
+    pub(crate) fn open(&self) -> Option<OpenToken> {
+        self.open
+    }
+    pub(crate) fn mid_source(&self) -> bool {
+        self.mid_source
+    }
+    pub(crate) fn capture(scanner: &Scanner) -> Self {
+        let stream = &scanner.manual_scanner.stream;
+        let detector = &scanner.regex_detector;
+        let last_three = &detector.last_three;
+        let last_three = [*last_three.three(), *last_three.two(), *last_three.one()];
+        // how many lines back each token's line is, the
+        // actual distance only matters when comparing them
+        let current = scanner.current_line();
+        let mut distances: Vec<u32> = last_three
+            .iter()
+            .flatten()
+            .filter(|t| matches!(t, MetaToken::Keyword(..) | MetaToken::OpenBrace(..)))
+            .map(|t| current.wrapping_sub(t.line_number()))
+            .filter(|d| *d > 0)
+            .collect();
+        distances.sort_unstable();
+        distances.dedup();
+        let rank = |line: u32| {
+            let distance = current.wrapping_sub(line);
+            distances
+                .iter()
+                .position(|d| *d == distance)
+                .map(|i| i as u32 + 1)
+                .unwrap_or_default()
+        };
+        Self {
+            open: stream.open,
+            mid_source: !stream.at_source_start || stream.stream.idx > 0,
+            curly_stack: stream.curly_stack.clone(),
+            last_three: [
+                last_three[0].map(|t| LexToken::from_meta(t, rank)),
+                last_three[1].map(|t| LexToken::from_meta(t, rank)),
+                last_three[2].map(|t| LexToken::from_meta(t, rank)),
+            ],
+            paren_stack: detector.paren_stack.iter().map(LexParen::from).collect(),
+            brace_stack: detector.brace_stack.iter().map(LexBrace::from).collect(),
+            class_heading: detector.class_heading,
+        }
+    }
+
+    pub(crate) fn restore(&self, scanner: &mut Scanner) {
+        let mut last_three = LookBehind::new();
+        for token in self.last_three.iter().flatten() {
+            last_three.push(token.to_meta());
+        }
+        let detector = &mut scanner.regex_detector;
+        detector.last_three = last_three;
+        detector.paren_stack = self.paren_stack.iter().map(|p| p.to_paren()).collect();
+        detector.brace_stack = self.brace_stack.iter().map(|b| b.to_brace()).collect();
+        detector.class_heading = self.class_heading;
+        scanner.manual_scanner.stream.curly_stack = self.curly_stack.clone();
+    }
+}
+
+impl LexToken {
+    fn from_meta(token: MetaToken, rank: impl Fn(u32) -> u32) -> Self {
+        match token {
+            MetaToken::Keyword(k, line) => LexToken::Keyword(k, rank(line)),
+            MetaToken::Punct(p) => LexToken::Punct(p),
+            MetaToken::OpenParen(p) => LexToken::OpenParen((&p).into()),
+            MetaToken::CloseParen(p) => LexToken::CloseParen((&p).into()),
+            MetaToken::OpenBrace(b, line) => LexToken::OpenBrace((&b).into(), rank(line)),
+            MetaToken::CloseBrace(b) => LexToken::CloseBrace((&b).into()),
+            MetaToken::OpenTemplate => LexToken::OpenTemplate,
+            MetaToken::Ident => LexToken::Ident,
+            MetaToken::Of => LexToken::Of,
+            MetaToken::Async => LexToken::Async,
+            MetaToken::Other => LexToken::Other,
+        }
+    }
+    /// Lines before the start of the new text
+    /// count back from `FIRST_LINE`
+    fn to_meta(self) -> MetaToken {
+        match self {
+            LexToken::Keyword(k, rank) => MetaToken::Keyword(k, FIRST_LINE - rank),
+            LexToken::Punct(p) => MetaToken::Punct(p),
+            LexToken::OpenParen(p) => MetaToken::OpenParen(p.to_paren()),
+            LexToken::CloseParen(p) => MetaToken::CloseParen(p.to_paren()),
+            LexToken::OpenBrace(b, rank) => MetaToken::OpenBrace(b.to_brace(), FIRST_LINE - rank),
+            LexToken::CloseBrace(b) => MetaToken::CloseBrace(b.to_brace()),
+            LexToken::OpenTemplate => MetaToken::OpenTemplate,
+            LexToken::Ident => MetaToken::Ident,
+            LexToken::Of => MetaToken::Of,
+            LexToken::Async => MetaToken::Async,
+            LexToken::Other => MetaToken::Other,
+        }
+    }
+}
+
+impl From<&Paren> for LexParen {
+    fn from(paren: &Paren) -> Self {
+        Self {
+            func_expr: paren.func_expr,
+            conditional: paren.conditional,
+            for_head: paren.for_head,
+            function: paren.function,
+            generator: paren.generator,
+            is_async: paren.is_async,
+        }
+    }
+}
+
+impl LexParen {
+    fn to_paren(self) -> Paren {
+        Paren {
+            func_expr: self.func_expr,
+            conditional: self.conditional,
+            for_head: self.for_head,
+            function: self.function,
+            generator: self.generator,
+            is_async: self.is_async,
+            start: 0,
+            position: Position::new(1, 1),
+        }
+    }
+}
+
+impl From<&Brace> for LexBrace {
+    fn from(brace: &Brace) -> Self {
+        Self {
+            is_block: brace.is_block,
+            paren: brace.paren.as_ref().map(LexParen::from),
+            yield_kw: brace.yield_kw,
+            await_kw: brace.await_kw,
+        }
+    }
+}
+
+impl LexBrace {
+    fn to_brace(self) -> Brace {
+        Brace {
+            is_block: self.is_block,
+            paren: self.paren.map(LexParen::to_paren),
+            yield_kw: self.yield_kw,
+            await_kw: self.await_kw,
+            start: 0,
+            position: Position::new(1, 1),
+        }
+    }
+}
+
+/// The `LexState` at the end of each line of a text, found
+/// by scanning each line on its own from the state at the
+/// end of the line before it
+///
+/// Any error ends the iteration, with its `line` and `idx`
+/// relative to the whole text.
+///
+/// ```
+/// use ress::LineStates;
+/// let states: Vec<_> = LineStates::new("/* a\nb */ x = `\n${y}`")
+///     .map(|s| s.unwrap())
+///     .collect();
+/// assert!(states[0].in_comment());
+/// assert!(states[1].in_template());
+/// assert_eq!(states[2].depth(), 0);
+/// ```
+pub struct LineStates<'a> {
+    text: &'a str,
+    lines: LineIndex,
+    line: usize,
+    state: LexState,
+    done: bool,
+}
+
+impl<'a> LineStates<'a> {
+    /// Create a new `LineStates` for every line in `text`
+    pub fn new(text: &'a str) -> Self {
+        Self::resume(text, LexState::new())
+    }
+    /// Create a new `LineStates` for every line in `text`,
+    /// starting from `state`
+    pub fn resume(text: &'a str, state: LexState) -> Self {
+        Self {
+            text,
+            lines: LineIndex::new(text),
+            line: 1,
+            state,
+            done: false,
+        }
+    }
+}
+
+impl<'a> Iterator for LineStates<'a> {
+    type Item = Res<LexState>;
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+        let start = self.lines.line_start(self.line)?;
+        let end = self
+            .lines
+            .line_start(self.line + 1)
+            .unwrap_or(self.text.len());
+        let mut scanner = Scanner::resume(&self.text[start..end], &self.state);
+        for item in scanner.by_ref() {
+            if let Err(mut e) = item {
+                self.done = true;
+                e.line += self.line - 1;
+                e.idx += start;
+                return Some(Err(e));
+            }
+        }
+        self.state = scanner.lex_state();
+        self.line += 1;
+        Some(Ok(self.state.clone()))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::tokens::Token;
+
+    /// The source text of each token, scanning one line at a time
+    fn scan_lines(js: &str) -> Vec<&str> {
+        let lines = LineIndex::new(js);
+        let mut state = LexState::new();
+        let mut items = Vec::new();
+        for line in 1..=lines.line_count() {
+            let start = lines.line_start(line).unwrap();
+            let end = lines.line_start(line + 1).unwrap_or(js.len());
+            let line = &js[start..end];
+            let mut scanner = Scanner::resume(line, &state);
+            for item in scanner.by_ref() {
+                let item = item.unwrap();
+                if !item.token.is_eof() {
+                    items.push(&line[item.span.start..item.span.end]);
+                }
+            }
+            state = scanner.lex_state();
+        }
+        items
+    }
+
+    #[test]
+    fn resume_each_line() {
+        let js = "function f(a) {
+    /* multi
+       line */ return `head${a / 2}
+middle${ {b: /re/g} }
+tail` / 2 + 'con\\
+tinued';
+}
+if (a) /x/.test(b);
+return
+/y/";
+        assert_eq!(
+            scan_lines(js),
+            vec![
+                "function",
+                "f",
+                "(",
+                "a",
+                ")",
+                "{",
+                "/* multi\n",
+                "       line */",
+                "return",
+                "`head${",
+                "a",
+                "/",
+                "2",
+                "}\n",
+                "middle${",
+                "{",
+                "b",
+                ":",
+                "/re/g",
+                "}",
+                "}\n",
+                "tail`",
+                "/",
+                "2",
+                "+",
+                "'con\\\n",
+                "tinued'",
+                ";",
+                "}",
+                "if",
+                "(",
+                "a",
+                ")",
+                "/x/",
+                ".",
+                "test",
+                "(",
+                "b",
+                ")",
+                ";",
+                "return",
+                "/y/",
+            ]
+        );
+    }
+
+    #[test]
+    fn contents() {
+        let mut scanner = Scanner::resume("/* a\n", &LexState::new());
+        match scanner.next().unwrap().unwrap().token {
+            Token::Comment(c) => assert_eq!(c.content, " a\n"),
+            t => panic!("expected comment found {:?}", t),
+        }
+        let _eof = scanner.next();
+        let state = scanner.lex_state();
+        match Scanner::resume(" b */", &state)
+            .next()
+            .unwrap()
+            .unwrap()
+            .token
+        {
+            Token::Comment(c) => assert_eq!(c.content, " b "),
+            t => panic!("expected comment found {:?}", t),
+        }
+        let state = LineStates::new("x = \"a\\\n").last().unwrap().unwrap();
+        assert!(state.in_string());
+        match Scanner::resume("b\"", &state)
+            .next()
+            .unwrap()
+            .unwrap()
+            .token
+        {
+            Token::String(s) => assert_eq!(s.to_string(), "\"b\""),
+            t => panic!("expected string found {:?}", t),
+        }
+    }
+
+    #[test]
+    fn matches_scanner() {
+        let js = "function f() {
+    if (x)
+        /a/.test(y);
+    let z = y
+        / 2 / x;
+    return
+    {}
+    /b/g;
+}
+x = class
+{} / 2 / y;
+function* g() {
+    yield
+    /c/;
+}";
+        let expected: Vec<_> = Scanner::new(js)
+            .map(|i| i.unwrap())
+            .filter(|i| !i.token.is_eof())
+            .map(|i| &js[i.span.start..i.span.end])
+            .collect();
+        assert_eq!(scan_lines(js), expected);
+    }
+
+    #[test]
+    fn equal_states() {
+        let states: Vec<_> = LineStates::new("a = {\n  b: 1,\n  c: 2,\n}\n")
+            .map(|s| s.unwrap())
+            .collect();
+        assert_eq!(states.len(), 5);
+        assert_ne!(states[0], states[1]);
+        assert_eq!(states[1], states[2]);
+        assert_eq!(states[3], states[4]);
+        assert_ne!(states[3], LexState::new());
+        assert!(LineStates::new("#!/usr/bin/env node\n#!x")
+            .nth(1)
+            .unwrap()
+            .is_ok());
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn serde() {
+        for state in LineStates::new("if (a) {\n  return /* b\n */ `${c}\nd`\n}") {
+            let state = state.unwrap();
+            let json = serde_json::to_string(&state).unwrap();
+            assert_eq!(serde_json::from_str::<LexState>(&json).unwrap(), state);
+        }
+    }
+
+    #[test]
+    fn errors() {
+        let err = LineStates::new("a\nb\n  'c").find_map(|s| s.err()).unwrap();
+        assert_eq!((err.line, err.idx), (3, 7));
+        let err = LineStates::new("x = `a\n").last().unwrap();
+        assert!(err.is_ok());
+    }
+}
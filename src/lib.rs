@@ -18,14 +18,40 @@
 //! [tokenize]: ../fn.tokenize
 //! [location]: ./struct.SourceLocation
 
+#![cfg_attr(not(any(feature = "std", test)), no_std)]
+
+#[macro_use]
+extern crate alloc;
 #[macro_use]
 extern crate log;
 
+mod bracket_index;
+mod comment_attacher;
 pub mod error;
+mod es_version;
+#[cfg(feature = "serde")]
+pub mod esprima;
+mod interner;
+mod kind_scanner;
+mod lex_state;
+mod limits;
+mod line_index;
 mod manual_scanner;
+mod options;
+mod owned_scanner;
+mod regex_trace;
+mod resume;
+mod source_text;
+#[cfg(feature = "std")]
+mod stream_scanner;
+mod token_buffer;
+mod token_cache;
+mod token_tree;
 mod tokenizer;
 pub mod tokens;
-pub use crate::tokenizer::{JSBuffer, Tokenizer};
+mod verify;
+pub use crate::token_tree::{Delimiter, Group, TokenTree, TokenTreeError};
+pub use crate::tokenizer::{JSBuffer, Tokenizer, UNICODE_VERSION};
 
 pub mod prelude {
     pub use super::{
@@ -33,14 +59,33 @@ pub mod prelude {
         SourceLocation,
     };
 }
-use crate::{tokenizer::RawKeyword, tokens::prelude::*};
-use error::{Error, RawError};
+pub use crate::bracket_index::{BracketIndex, FoldingKind, FoldingRange};
+pub use crate::comment_attacher::{AttachedItem, CommentAttacher};
+pub use crate::es_version::{EsVersion, Feature, Unsupported};
+pub use crate::interner::{Interned, Interner, InterningScanner, Symbol};
+pub use crate::kind_scanner::KindScanner;
+pub use crate::lex_state::{LexState, LineStates};
+pub use crate::limits::Limits;
+pub use crate::line_index::LineIndex;
+pub use crate::look_behind::{Brace, Paren};
+pub use crate::options::{Comments, ScannerOptions};
+pub use crate::owned_scanner::OwnedScanner;
+pub use crate::regex_trace::{RegexDecision, RegexRule};
+pub use crate::source_text::{Encoding, SourceText};
+#[cfg(feature = "std")]
+pub use crate::stream_scanner::StreamScanner;
+pub use crate::token_buffer::{Items as TokenBufferItems, TokenBuffer};
+pub use crate::token_cache::TokenCache;
+use crate::tokens::prelude::*;
+pub use crate::verify::{verify, Violation, ViolationKind};
+use alloc::{boxed::Box, string::String, vec::Vec};
+use error::{DelimiterLocation, Error, ErrorKind};
 pub use manual_scanner::{ManualScanner, ScannerState as ManualState};
 
 type Res<T> = Result<T, Error>;
 mod look_behind;
 
-use look_behind::{Brace, LookBehind, MetaToken, Paren};
+use look_behind::{LookBehind, RegexDetector};
 
 /// a convince function for collecting a scanner into
 /// a `Vec<Token>`
@@ -54,6 +99,7 @@ pub fn tokenize(text: &str) -> Res<Vec<Token<&str>>> {
 }
 
 #[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 /// The start and end position of a token
 /// including the line/column number
 pub struct SourceLocation {
@@ -69,6 +115,7 @@ impl SourceLocation {
 }
 
 #[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 /// A single character position in the
 /// file including the line/column number
 pub struct Position {
@@ -76,19 +123,19 @@ pub struct Position {
     pub column: usize,
 }
 
-impl ::std::fmt::Display for Position {
-    fn fmt(&self, f: &mut ::std::fmt::Formatter) -> ::std::fmt::Result {
+impl ::core::fmt::Display for Position {
+    fn fmt(&self, f: &mut ::core::fmt::Formatter) -> ::core::fmt::Result {
         write!(f, "{}:{}", self.line, self.column)
     }
 }
-impl ::std::cmp::PartialOrd for Position {
-    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+impl ::core::cmp::PartialOrd for Position {
+    fn partial_cmp(&self, other: &Self) -> Option<core::cmp::Ordering> {
         Some(self.cmp(other))
     }
 }
-impl std::cmp::Ord for Position {
-    fn cmp(&self, other: &Self) -> std::cmp::Ordering {
-        use std::cmp::Ordering::*;
+impl core::cmp::Ord for Position {
+    fn cmp(&self, other: &Self) -> core::cmp::Ordering {
+        use core::cmp::Ordering::*;
         match self.line.cmp(&other.line) {
             Less => Less,
             Greater => Greater,
@@ -105,6 +152,7 @@ impl Position {
 }
 
 #[derive(Debug, PartialEq, Eq, Clone, Copy)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 /// The start and end of a token as the byte
 /// index in the original text
 pub struct Span {
@@ -126,6 +174,7 @@ impl Span {
 }
 
 #[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 /// A single token with additional metadata
 pub struct Item<T> {
     pub token: Token<T>,
@@ -141,22 +190,12 @@ impl<T> Item<T> {
             location,
         }
     }
-    fn new_(
-        token: Token<T>,
-        span_start: usize,
-        span_end: usize,
-        loc_start_line: usize,
-        loc_start_col: usize,
-        loc_end_line: usize,
-        loc_end_col: usize,
-    ) -> Self {
-        Self {
-            token,
-            span: Span::new(span_start, span_end),
-            location: SourceLocation::new(
-                Position::new(loc_start_line, loc_start_col),
-                Position::new(loc_end_line, loc_end_col),
-            ),
+    /// Convert the text held by this item's token with `f`
+    pub fn map<U>(self, f: impl FnMut(T) -> U) -> Item<U> {
+        Item {
+            token: self.token.map(f),
+            span: self.span,
+            location: self.location,
         }
     }
     pub fn is_string(&self) -> bool {
@@ -172,30 +211,117 @@ impl<T> Item<T> {
     }
 }
 
+impl<T> Item<T>
+where
+    T: AsRef<str>,
+{
+    /// Copy the text held by this item's token into an
+    /// owned type like `String`, `Rc<str>` or `Arc<str>`
+    pub fn into_owned<U>(self) -> Item<U>
+    where
+        U: for<'s> From<&'s str>,
+    {
+        self.map(|s| U::from(s.as_ref()))
+    }
+}
+
 /// The primary interface of this crate used
 /// to tokenize any JS text into a stream of
 /// `Item`s.
 pub struct Scanner<'a> {
     manual_scanner: ManualScanner<'a>,
-    original: &'a str,
     errored: bool,
-    last_three: LookBehind,
-    brace_stack: Vec<Brace>,
-    paren_stack: Vec<Paren>,
+    regex_detector: RegexDetector,
+    /// The line number `regex_detector` counts the
+    /// first line of this text as
+    first_line: u32,
+    regex_trace: Option<Box<dyn FnMut(RegexDecision) + Send>>,
+    recover: bool,
+    target: EsVersion,
+    on_unsupported: Option<Box<dyn FnMut(Unsupported) + Send>>,
 }
 
 impl<'a> Scanner<'a> {
     /// Create a new `Scanner` by providing the
     /// JS text
     pub fn new(text: &'a str) -> Self {
-        Self {
-            manual_scanner: ManualScanner::new(text),
-            original: text,
+        Self::with_options(text, ScannerOptions::new())
+    }
+    /// Create a new `Scanner` with the `options` provided
+    pub fn with_options(text: &'a str, mut options: ScannerOptions) -> Self {
+        let limits = options.limits.take();
+        let regex_trace = options.regex_trace.take();
+        let on_unsupported = options.on_unsupported.take();
+        let resume = options.resume.clone();
+        let mut ret = Self {
+            recover: options.recover,
+            target: options.target,
+            manual_scanner: ManualScanner::with_options(text, options),
             errored: false,
-            last_three: LookBehind::new(),
-            paren_stack: Vec::new(),
-            brace_stack: Vec::new(),
+            regex_detector: RegexDetector::new(),
+            first_line: if resume.is_some() {
+                lex_state::FIRST_LINE
+            } else {
+                0
+            },
+            regex_trace,
+            on_unsupported,
+        };
+        ret.manual_scanner.stream.limits = limits;
+        if let Some(state) = resume {
+            state.restore(&mut ret);
         }
+        ret
+    }
+    /// Create a new `Scanner` over text decoded from bytes,
+    /// `source.original_span` maps each `Item`'s span back
+    /// to the bytes
+    pub fn from_bytes(source: &'a SourceText) -> Self {
+        Self::new(source.text())
+    }
+    /// Create a new `Scanner` that skips tracking line/column
+    /// positions, every `Item` will have a zeroed `location`.
+    ///
+    /// Positions can be looked up with a `LineIndex` as needed.
+    /// ```
+    /// use ress::{LineIndex, Position, Scanner};
+    /// let js = "let a;\nlet b;";
+    /// let b = Scanner::without_locations(js).nth(4).unwrap().unwrap();
+    /// assert_eq!(b.location.start, Position::new(0, 0));
+    /// let lines = LineIndex::new(js);
+    /// assert_eq!(lines.position(b.span.start), Position::new(2, 5));
+    /// ```
+    pub fn without_locations(text: &'a str) -> Self {
+        Self::with_options(text, ScannerOptions::new().locations(false))
+    }
+    /// Create a new `Scanner` for a piece of a larger text,
+    /// starting from the `LexState` at the end of the text
+    /// before it
+    ///
+    /// A multi-line comment, template literal or string cut off
+    /// by the end of `text` is returned as a token without its
+    /// closing delimiter instead of an error, see `lex_state`
+    /// to continue it in the next piece.
+    pub fn resume(text: &'a str, state: &LexState) -> Self {
+        Self::with_options(text, ScannerOptions::new().resume(state))
+    }
+    /// The `LexState` after the last token scanned, for
+    /// scanning the rest of the text with `Scanner::resume`
+    pub fn lex_state(&self) -> LexState {
+        LexState::capture(self)
+    }
+    /// Create a new `Scanner` that calls `trace` with the
+    /// `RegexDecision` made for every `/` and `/=` it scans
+    pub fn with_regex_trace(
+        text: &'a str,
+        trace: impl FnMut(RegexDecision) + Send + 'static,
+    ) -> Self {
+        Self::with_options(text, ScannerOptions::new().regex_trace(trace))
+    }
+    /// Create a new `Scanner` that will stop with an
+    /// error once any of the `limits` are exceeded
+    pub fn with_limits(text: &'a str, limits: Limits) -> Self {
+        Self::with_options(text, ScannerOptions::new().limits(limits))
     }
 }
 
@@ -226,8 +352,10 @@ impl<'b> Scanner<'b> {
     pub fn get_state(&self) -> ScannerState {
         ScannerState {
             manual_state: self.manual_scanner.get_state(),
-            last_three: self.last_three.clone(),
-            paren_stack: self.paren_stack.clone(),
+            last_three: self.regex_detector.last_three.clone(),
+            paren_stack: self.regex_detector.paren_stack.clone(),
+            brace_stack: self.regex_detector.brace_stack.clone(),
+            class_heading: self.regex_detector.class_heading,
         }
     }
     /// Set the scanner's current state to the state provided
@@ -237,9 +365,13 @@ impl<'b> Scanner<'b> {
             manual_state,
             last_three,
             paren_stack,
+            brace_stack,
+            class_heading,
         } = state;
-        self.last_three = last_three;
-        self.paren_stack = paren_stack;
+        self.regex_detector.last_three = last_three;
+        self.regex_detector.paren_stack = paren_stack;
+        self.regex_detector.brace_stack = brace_stack;
+        self.regex_detector.class_heading = class_heading;
         self.manual_scanner.set_state(manual_state);
     }
     #[inline]
@@ -258,12 +390,22 @@ impl<'b> Scanner<'b> {
         let next = match self.manual_scanner.next_token()? {
             Ok(n) => n,
             Err(e) => {
-                self.errored = true;
+                // a recovering `manual_scanner` has already
+                // moved past the error
+                self.errored = !self.recover || e.kind.is_limit();
                 return Some(Err(e));
             }
         };
+        let next = if self.target == EsVersion::Es3 {
+            es_version::es3_reserved(next)
+        } else {
+            next
+        };
 
-        let ret = if next.token.is_div_punct() && self.is_regex_start() {
+        if advance_cursor && next.token.is_div_punct() {
+            self.trace_regex(&next);
+        }
+        let ret = if next.token.is_div_punct() && self.regex_detector.is_regex_start() {
             self.manual_scanner.next_regex(next.span.len())?
         } else {
             Ok(next)
@@ -271,6 +413,15 @@ impl<'b> Scanner<'b> {
         if advance_cursor {
             if let Ok(i) = &ret {
                 if let Err(e) = self.keep_books(i) {
+                    self.errored = !self.recover;
+                    return Some(Err(e));
+                }
+                if let Err(e) = self.check_limits(i) {
+                    self.errored = true;
+                    return Some(Err(e));
+                }
+                if let Err(e) = self.check_target(i) {
+                    self.errored = true;
                     return Some(Err(e));
                 }
             }
@@ -284,246 +435,137 @@ impl<'b> Scanner<'b> {
     /// start and handle updating the
     /// `self.last_three`, `self.paren_stack` and `self.brace_stack`
     fn keep_books(&mut self, item: &Item<&'b str>) -> Res<()> {
-        if let Token::Punct(ref p) = &item.token {
-            match p {
-                Punct::OpenParen => self.handle_open_paren_books(),
-                Punct::OpenBrace => self.handle_open_brace_books(),
-                Punct::CloseParen => self.handle_close_paren_books(item.span.start)?,
-                Punct::CloseBrace => self.handle_close_brace_books(item.span.start)?,
-                _ => self
-                    .last_three
-                    .push((&item.token, self.manual_scanner.new_line_count as u32).into()),
-            }
-        } else if !item.token.is_comment() {
-            self.last_three
-                .push((&item.token, self.manual_scanner.new_line_count as u32).into());
+        // the end of a text resumed with `Scanner::resume`
+        // is not the end of the source
+        if item.token.is_comment() || item.token.is_eof() {
+            return Ok(());
+        }
+        let line = self.current_line();
+        let meta = (&item.token, line).into();
+        if let Err(delimiter) =
+            self.regex_detector
+                .keep_books(meta, item.span.start, item.location.start, line)
+        {
+            return self.delimiter_error(delimiter, item);
         }
         Ok(())
     }
-    #[inline]
-    /// Handle the book keeping when we find
-    /// an `(`
-    fn handle_open_paren_books(&mut self) {
-        let func_expr = if let Some(MetaToken::Keyword(RawKeyword::Function, _)) =
-            self.last_three.one()
-        {
-            if let Some(tok) = self.last_three.two() {
-                !Self::check_for_expression(*tok)
-            } else {
-                false
-            }
-        } else if let Some(MetaToken::Keyword(RawKeyword::Function, _)) = self.last_three.two() {
-            if let Some(tok) = self.last_three.three() {
-                Self::check_for_expression(*tok)
-            } else {
-                false
-            }
-        } else {
-            false
-        };
-        let conditional = if let Some(tok) = self.last_three.one() {
-            Self::check_token_for_conditional(*tok)
-        } else {
-            false
-        };
-        let paren = Paren {
-            func_expr,
-            conditional,
-        };
-        let meta = MetaToken::OpenParen(paren);
-        self.paren_stack.push(paren);
-        self.last_three.push(meta);
+    /// The line number `regex_detector` is on
+    fn current_line(&self) -> u32 {
+        (self.manual_scanner.new_line_count as u32).wrapping_add(self.first_line)
     }
-    #[inline]
-    /// Handle the book keeping when we find
-    /// and `{`
-    fn handle_open_brace_books(&mut self) {
-        let is_block = if let Some(last) = self.last_three.one() {
-            match last {
-                MetaToken::Punct(Punct::OpenParen)
-                | MetaToken::Punct(Punct::OpenBracket)
-                | MetaToken::OpenParen(_)
-                | MetaToken::OpenBrace(_, _) => false,
-                MetaToken::Punct(Punct::Colon) => {
-                    if let Some(parent) = self.brace_stack.last() {
-                        parent.is_block
-                    } else {
-                        false
-                    }
-                }
-                MetaToken::Punct(_) => !Self::is_op(*last),
-                MetaToken::Keyword(RawKeyword::Return, line)
-                | MetaToken::Keyword(RawKeyword::Yield, line) => {
-                    if let Some(last) = self.last_three.two() {
-                        last.line_number() != *line
-                    } else {
-                        false
-                    }
-                }
-                MetaToken::Keyword(RawKeyword::Case, _) => false,
-                MetaToken::Keyword(_, _) => !Self::is_op(*last),
-                _ => true,
-            }
-        } else {
-            true
+    /// Report the decision made for the `/` or `/=` in
+    /// `item` if this scanner has a `regex_trace`
+    fn trace_regex(&mut self, item: &Item<&'b str>) {
+        let trace = match &mut self.regex_trace {
+            Some(trace) => trace,
+            None => return,
         };
-        let paren = if let Some(MetaToken::CloseParen(open)) = self.last_three.one() {
-            Some(*open)
-        } else {
-            None
+        let punct = match item.token {
+            Token::Punct(p) => p,
+            _ => return,
         };
-        let brace = look_behind::Brace { is_block, paren };
-        self.brace_stack.push(brace);
-        self.last_three.push(MetaToken::OpenBrace(
-            brace,
-            self.manual_scanner.new_line_count as u32,
-        ));
-    }
-    #[inline]
-    /// Handle the book keeping when we find a `(`
-    fn handle_close_paren_books(&mut self, start: usize) -> Res<()> {
-        let paren = if let Some(paren) = self.paren_stack.pop() {
-            paren
+        let position = if self.manual_scanner.tracks_locations() {
+            item.location.start
         } else {
-            self.errored = true;
-            return self.error(RawError {
-                idx: start,
-                msg: "Unmatched open close paren".to_string(),
-            });
+            self.manual_scanner.line_index().position(item.span.start)
         };
-        self.last_three.push(MetaToken::CloseParen(paren));
-        Ok(())
-    }
-    #[inline]
-    /// Handle the book keeping when we find a `{`
-    fn handle_close_brace_books(&mut self, start: usize) -> Res<()> {
-        if let Some(open) = self.brace_stack.pop() {
-            let close = MetaToken::CloseBrace(open);
-            self.last_three.push(close);
-            Ok(())
-        } else {
-            self.error(RawError {
-                idx: start,
-                msg: "unmatched close brace".to_string(),
-            })
-        }
-    }
-    /// Detect if the `/` is the beginning of
-    /// a regex or is division
-    ///
-    /// [see this for more details](https://github.com/sweet-js/sweet-core/wiki/design)
-    fn is_regex_start(&self) -> bool {
-        if let Some(ref last_token) = self.last_three.one() {
-            match last_token {
-                MetaToken::Keyword(k, _) => !matches!(k, RawKeyword::This),
-                MetaToken::Punct(p) => !matches!(p, Punct::CloseBracket),
-                MetaToken::CloseParen(open) => open.conditional,
-                MetaToken::CloseBrace(close) => {
-                    if close.is_block {
-                        if let Some(open) = &close.paren {
-                            !open.func_expr
-                        } else {
-                            true
-                        }
-                    } else {
-                        false
-                    }
-                }
-                MetaToken::OpenParen(_) | MetaToken::OpenBrace(_, _) => true,
-                _ => false,
+        let rule = self.regex_detector.rule();
+        trace(RegexDecision {
+            punct,
+            idx: item.span.start,
+            position,
+            is_regex: rule.is_regex(),
+            rule,
+        });
+    }
+    /// Count `item` against any `Limits` this scanner has
+    fn check_limits(&mut self, item: &Item<&'b str>) -> Res<()> {
+        let stream = &mut self.manual_scanner.stream;
+        let limits = match &mut stream.limits {
+            Some(limits) => limits,
+            None => return Ok(()),
+        };
+        match item.token {
+            Token::Punct(Punct::OpenBracket) | Token::Punct(Punct::HashOpenBracket) => {
+                limits.open_brackets += 1
             }
-        } else {
-            true
+            Token::Punct(Punct::CloseBracket) => {
+                limits.open_brackets = limits.open_brackets.saturating_sub(1)
+            }
+            _ => {}
         }
-    }
-    /// Check a token for the conditional keywords
-    ///
-    /// > used in determining if we are at a regex or not
-    fn check_token_for_conditional(tok: MetaToken) -> bool {
-        if let MetaToken::Keyword(k, _) = tok {
-            matches!(
-                k,
-                RawKeyword::If | RawKeyword::For | RawKeyword::While | RawKeyword::With
-            )
-        } else {
-            false
+        let depth =
+            self.regex_detector.paren_stack.len() + stream.curly_stack.len() + limits.open_brackets;
+        if let Err((kind, msg)) = limits.check(item.span.len(), depth) {
+            let position = if self.manual_scanner.tracks_locations() {
+                item.location.start
+            } else {
+                self.manual_scanner.line_index().position(item.span.start)
+            };
+            return Err(Error {
+                line: position.line,
+                column: position.column,
+                msg,
+                idx: item.span.start,
+                kind,
+            });
         }
+        Ok(())
     }
-    /// Check if a token indicates beginning of a
-    /// function expression
-    ///
-    /// > used in determining if we are at a regex or not
-    fn check_for_expression(token: MetaToken) -> bool {
-        if Self::is_op(token) {
-            true
-        } else {
-            matches!(
-                token,
-                MetaToken::Keyword(RawKeyword::Return, _) | MetaToken::Keyword(RawKeyword::Case, _)
-            )
+    /// Report or error on any `Feature` in `item` newer
+    /// than this scanner's `target`
+    fn check_target(&mut self, item: &Item<&'b str>) -> Res<()> {
+        let mut found = None;
+        let text = self.manual_scanner.text();
+        es_version::unsupported(item, text, self.target, |feature| {
+            found.get_or_insert(feature);
+            if let Some(report) = &mut self.on_unsupported {
+                report(Unsupported {
+                    feature,
+                    span: item.span,
+                    location: item.location,
+                });
+            }
+        });
+        match found {
+            Some(feature) if self.on_unsupported.is_none() => {
+                let position = if self.manual_scanner.tracks_locations() {
+                    item.location.start
+                } else {
+                    self.manual_scanner.line_index().position(item.span.start)
+                };
+                Err(Error {
+                    line: position.line,
+                    column: position.column,
+                    msg: format!("{} requires {}", feature, feature.since()),
+                    idx: item.span.start,
+                    kind: ErrorKind::Unsupported(feature),
+                })
+            }
+            _ => Ok(()),
         }
     }
-    /// Determine if a token is a punctuation or keyword
-    /// that indicates an operation
-    ///
-    /// > used in determining if we are at a regex or not
-    fn is_op(tok: MetaToken) -> bool {
-        match tok {
-            MetaToken::Punct(ref p) => matches!(
-                p,
-                Punct::Equal
-                    | Punct::PlusEqual
-                    | Punct::DashEqual
-                    | Punct::AsteriskEqual
-                    | Punct::ForwardSlashEqual
-                    | Punct::PercentEqual
-                    | Punct::DoubleLessThanEqual
-                    | Punct::DoubleGreaterThanEqual
-                    | Punct::TripleGreaterThanEqual
-                    | Punct::AmpersandEqual
-                    | Punct::PipeEqual
-                    | Punct::CaretEqual
-                    | Punct::Comma
-                    | Punct::Plus
-                    | Punct::Dash
-                    | Punct::Asterisk
-                    | Punct::ForwardSlash
-                    | Punct::Percent
-                    | Punct::DoubleLessThan
-                    | Punct::DoubleGreaterThan
-                    | Punct::TripleGreaterThan
-                    | Punct::Ampersand
-                    | Punct::Pipe
-                    | Punct::Caret
-                    | Punct::DoubleAmpersand
-                    | Punct::DoublePipe
-                    | Punct::QuestionMark
-                    | Punct::Colon
-                    | Punct::TripleEqual
-                    | Punct::DoubleEqual
-                    | Punct::GreaterThanEqual
-                    | Punct::LessThanEqual
-                    | Punct::LessThan
-                    | Punct::GreaterThan
-                    | Punct::BangEqual
-                    | Punct::BangDoubleEqual
-                    | Punct::DoublePlus
-                    | Punct::DoubleDash
-                    | Punct::Tilde
-                    | Punct::Bang
-            ),
-            MetaToken::Keyword(k, _) => matches!(
-                k,
-                RawKeyword::InstanceOf
-                    | RawKeyword::In
-                    | RawKeyword::Delete
-                    | RawKeyword::Void
-                    | RawKeyword::TypeOf
-                    | RawKeyword::Throw
-                    | RawKeyword::New
-            ),
-            _ => false,
+    /// Build the error for a `)` or `}` with nothing to
+    /// close, including the innermost delimiter still open
+    fn delimiter_error<T>(&self, delimiter: Delimiter, item: &Item<&'b str>) -> Res<T> {
+        let open = self.regex_detector.innermost_open();
+        if !self.manual_scanner.tracks_locations() {
+            let lines = self.manual_scanner.line_index();
+            let start = item.span.start;
+            return Err(Error::unmatched_close(
+                DelimiterLocation::single(delimiter, start, lines.position(start)),
+                open.map(|(delimiter, start, _)| {
+                    DelimiterLocation::single(delimiter, start, lines.position(start))
+                }),
+            ));
         }
+        Err(Error::unmatched_close(
+            token_tree::delimiter_location(delimiter, item),
+            open.map(|(delimiter, start, position)| {
+                DelimiterLocation::single(delimiter, start, position)
+            }),
+        ))
     }
     /// Get a string for any given span
     pub fn string_for(&self, span: &Span) -> Option<String> {
@@ -535,52 +577,22 @@ impl<'b> Scanner<'b> {
     }
     /// Get the line/column pair for any given byte index
     pub fn position_for(&self, idx: usize) -> (usize, usize) {
-        let mut line_ct = 1;
-        // This is the byte position, not the character
-        // position to account for multi byte chars
-        let mut byte_position = 0;
-        // loop over the characters
-        for (i, c) in self.original.chars().enumerate() {
-            if i >= idx {
-                return (line_ct, byte_position);
-            }
-            match c {
-                '\r' => {
-                    // look ahead 1 char to see if it is a newline pair
-                    // if so, don't include it, it will get included in the next
-                    // iteration
-                    if let Some(next) = self.original.get(byte_position..byte_position + 2) {
-                        if next != "\r\n" {
-                            line_ct += 1;
-                            byte_position = 0;
-                        }
-                    }
-                }
-                '\n' | '\u{2028}' | '\u{2029}' => {
-                    line_ct += 1;
-                    byte_position = 0;
-                }
-                _ => byte_position += c.len_utf8(),
-            };
-        }
-        (line_ct, byte_position)
+        self.manual_scanner.position_for(idx)
     }
 
     pub fn has_pending_new_line(&self) -> bool {
         self.manual_scanner.pending_new_line
     }
+}
 
-    /// Helper to handle the error cases
-    fn error<T>(&self, raw_error: RawError) -> Res<T> {
-        let RawError { idx, msg } = &raw_error;
-        let (line, column) = self.position_for(*idx);
-        Err(Error {
-            line,
-            column,
-            msg: msg.clone(),
-            idx: *idx,
-        })
-    }
+/// Check that only whitespace comes before `idx`
+/// on its line
+fn first_on_line(text: &str, idx: usize) -> bool {
+    text[..idx]
+        .chars()
+        .rev()
+        .take_while(|&c| !is_line_term(c))
+        .all(char::is_whitespace)
 }
 
 #[inline]
@@ -588,7 +600,8 @@ fn is_line_term(c: char) -> bool {
     c == '\n' || c == '\r' || c == '\u{2028}' || c == '\u{2029}'
 }
 
-#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 /// For keeping track of the nested-ness of
 /// templates and blocks
 pub enum OpenCurlyKind {
@@ -604,11 +617,15 @@ pub struct ScannerState {
     pub manual_state: ManualState,
     pub last_three: LookBehind,
     pub paren_stack: Vec<Paren>,
+    pub brace_stack: Vec<Brace>,
+    /// After a `class` keyword, if it is a class expression
+    /// and how many delimiters were open before it
+    pub class_heading: Option<(bool, usize)>,
 }
 
 #[cfg(test)]
 mod test {
-    use super::{tokens::*, *};
+    use super::{error::ErrorKind, tokens::*, *};
     #[test]
     fn tokenizer() {
         let js = "#!/usr/bin/env node
@@ -786,7 +803,7 @@ this.y = 0;
                 Ok(_) => (),
                 Err(e) => {
                     assert_eq!(e.line, 3);
-                    assert_eq!(e.column, 17);
+                    assert_eq!(e.column, 18);
                 }
             }
         }
@@ -812,14 +829,14 @@ f`;
             SourceLocation::new(Position::new(2, 5), Position::new(2, 8)), // 5 let
             SourceLocation::new(Position::new(2, 9), Position::new(2, 10)), // 6 x
             SourceLocation::new(Position::new(2, 11), Position::new(2, 12)), // 7 =
-            SourceLocation::new(Position::new(2, 13), Position::new(3, 1)), // 8 'asdf'
-            SourceLocation::new(Position::new(3, 1), Position::new(3, 2)), // 9 ;
+            SourceLocation::new(Position::new(2, 13), Position::new(3, 2)), // 8 'asdf'
+            SourceLocation::new(Position::new(3, 2), Position::new(3, 3)), // 9 ;
             SourceLocation::new(Position::new(4, 5), Position::new(4, 8)), // 10 let
             SourceLocation::new(Position::new(4, 9), Position::new(4, 10)), // 11 y
             SourceLocation::new(Position::new(4, 11), Position::new(4, 12)), // 12 =
-            SourceLocation::new(Position::new(4, 13), Position::new(5, 2)), // 13 `asdf`
-            SourceLocation::new(Position::new(5, 2), Position::new(5, 3)), // 14 ;
-            SourceLocation::new(Position::new(6, 5), Position::new(8, 6)), // 15 comment
+            SourceLocation::new(Position::new(4, 13), Position::new(5, 3)), // 13 `asdf`
+            SourceLocation::new(Position::new(5, 3), Position::new(5, 4)), // 14 ;
+            SourceLocation::new(Position::new(6, 5), Position::new(8, 7)), // 15 comment
             SourceLocation::new(Position::new(9, 1), Position::new(9, 2)), // 16 }
             SourceLocation::new(Position::new(9, 2), Position::new(9, 3)), // 17 )
             SourceLocation::new(Position::new(9, 3), Position::new(9, 4)), // 18 (
@@ -906,7 +923,7 @@ ley z = 9;";
     }
 
     #[test]
-    #[should_panic = "Unmatched open close paren"]
+    #[should_panic = "unmatched close paren"]
     fn unmatched_close_paren_error() {
         Scanner::new(")").next().unwrap().unwrap();
     }
@@ -916,6 +933,45 @@ ley z = 9;";
         Scanner::new("}").next().unwrap().unwrap();
     }
     #[test]
+    fn without_locations() {
+        for js in &[
+            "function f() {\n    return /a/g;\n}\n--> html",
+            "x\nelse /*\n*/ return {}/a/g\nelse `\n` return {}/b/g\n/* \r\n */--> c",
+        ] {
+            let expected: Vec<_> = Scanner::new(js).map(|i| i.unwrap()).collect();
+            let items: Vec<_> = Scanner::without_locations(js).map(|i| i.unwrap()).collect();
+            assert_eq!(items.len(), expected.len());
+            for (lhs, rhs) in items.iter().zip(expected.iter()) {
+                assert_eq!(lhs.token, rhs.token);
+                assert_eq!(lhs.span, rhs.span);
+                assert_eq!(lhs.location.start, Position::new(0, 0));
+            }
+        }
+        let err = Scanner::without_locations("a;\n  )")
+            .find_map(|i| i.err())
+            .unwrap();
+        assert_eq!(err.to_string(), "unmatched close paren at 2:3");
+    }
+    #[test]
+    fn unmatched_close_includes_open() {
+        let mut s = Scanner::new("{\n  a)\n  b");
+        let _open = s.next().unwrap().unwrap();
+        let _a = s.next().unwrap().unwrap();
+        let err = s.next().unwrap().unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "unmatched close paren (innermost open delimiter is { at 1:1) at 2:4"
+        );
+        match err.kind {
+            ErrorKind::Delimiter(e) => {
+                assert_eq!(e.open.unwrap().span, Span::new(0, 1));
+                assert_eq!(e.close.unwrap().span, Span::new(5, 6));
+            }
+            _ => panic!("expected a delimiter error: {:?}", err),
+        }
+        assert!(s.next().is_none());
+    }
+    #[test]
     fn this_over_number() {
         let mut s = Scanner::new("this / 100");
         let _this = s.next().unwrap().unwrap();
@@ -933,4 +989,20 @@ ley z = 9;";
         let re = s.next().unwrap().unwrap();
         assert!(re.token.is_regex(), "regex was not a regex: {:?}", re);
     }
+
+    #[test]
+    fn set_state_regex() {
+        for (js, skip) in &[("x = class {} / 2 / 1", 3), ("x = { a: 1 } / 2 / 1", 5)] {
+            let mut s = Scanner::new(js);
+            for _ in 0..*skip {
+                s.next().unwrap().unwrap();
+            }
+            let state = s.get_state();
+            let first: Vec<_> = s.by_ref().take(6).map(|i| i.unwrap().token).collect();
+            s.set_state(state);
+            let second: Vec<_> = s.take(6).map(|i| i.unwrap().token).collect();
+            assert!(!first.iter().any(|t| t.is_regex()), "{}", js);
+            assert_eq!(first, second, "{}", js);
+        }
+    }
 }
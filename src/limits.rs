@@ -0,0 +1,250 @@
+use alloc::{
+    boxed::Box,
+    string::{String, ToString},
+};
+
+use crate::error::ErrorKind;
+
+/// Bounds on the work a `Scanner` will do, for
+/// scanning input that can't be trusted
+///
+/// Each limit is off unless set, and exceeding one stops
+/// the `Scanner` with an error of its own `ErrorKind`.
+///
+/// ```
+/// use ress::{error::ErrorKind, Limits, Scanner};
+/// let limits = Limits::new().max_depth(2);
+/// let err = Scanner::with_limits("f(a, [{b: (1)}])", limits)
+///     .find_map(|i| i.err())
+///     .unwrap();
+/// assert_eq!(err.kind, ErrorKind::NestingTooDeep);
+/// ```
+#[derive(Default)]
+pub struct Limits {
+    max_depth: Option<usize>,
+    max_token_len: Option<usize>,
+    max_tokens: Option<usize>,
+    step_budget: Option<StepBudget>,
+    tokens: usize,
+    /// The number of `[` still open, parens and braces
+    /// are already counted by the `Scanner`
+    pub(crate) open_brackets: usize,
+}
+
+struct StepBudget {
+    steps: usize,
+    left: usize,
+    keep_going: Box<dyn FnMut() -> bool + Send>,
+}
+
+impl Limits {
+    /// Create a new `Limits` with nothing limited
+    pub fn new() -> Self {
+        Self::default()
+    }
+    /// The most parens, braces, brackets and template
+    /// substitutions that can be open at once
+    pub fn max_depth(mut self, depth: usize) -> Self {
+        self.max_depth = Some(depth);
+        self
+    }
+    /// The most bytes a single token can span
+    ///
+    /// Strings, comments, templates and regexes are checked
+    /// while they are scanned, so an unterminated one stops the
+    /// scan as soon as it is too long instead of at the end of
+    /// the text. Tokens are never copied so this bounds the time
+    /// spent on a token rather than the memory.
+    pub fn max_token_len(mut self, len: usize) -> Self {
+        self.max_token_len = Some(len);
+        self
+    }
+    /// The most tokens, including comments, that
+    /// will be scanned
+    pub fn max_tokens(mut self, count: usize) -> Self {
+        self.max_tokens = Some(count);
+        self
+    }
+    /// Call `keep_going` after every `steps` steps, stopping
+    /// the scan when it returns `false`
+    ///
+    /// Each token is a step, as is each byte scanned inside a
+    /// string, comment, template or regex.
+    ///
+    /// ```
+    /// use ress::{error::ErrorKind, Limits, Scanner};
+    /// use std::time::{Duration, Instant};
+    /// let deadline = Instant::now() + Duration::from_secs(1);
+    /// let limits = Limits::new().step_budget(1024, move || Instant::now() < deadline);
+    /// assert!(Scanner::with_limits("let a = 1;", limits).all(|i| i.is_ok()));
+    /// let limits = Limits::new().step_budget(2, || false);
+    /// let err = Scanner::with_limits("let a = 1;", limits).nth(1).unwrap().unwrap_err();
+    /// assert_eq!(err.kind, ErrorKind::Cancelled);
+    /// ```
+    pub fn step_budget(
+        mut self,
+        steps: usize,
+        keep_going: impl FnMut() -> bool + Send + 'static,
+    ) -> Self {
+        self.step_budget = Some(StepBudget {
+            steps,
+            left: steps,
+            keep_going: Box::new(keep_going),
+        });
+        self
+    }
+
+    /// Count one more token of `len` bytes, with `depth`
+    /// delimiters open after it
+    pub(crate) fn check(&mut self, len: usize, depth: usize) -> Result<(), (ErrorKind, String)> {
+        self.tokens += 1;
+        if let Some(max) = self.max_tokens {
+            if self.tokens > max {
+                return Err((
+                    ErrorKind::TooManyTokens,
+                    format!("more than {} tokens", max),
+                ));
+            }
+        }
+        self.check_len(len)?;
+        if let Some(max) = self.max_depth {
+            if depth > max {
+                return Err((
+                    ErrorKind::NestingTooDeep,
+                    format!("more than {} delimiters open", max),
+                ));
+            }
+        }
+        self.step(1)
+    }
+    /// Count `scanned` more bytes of a token that is
+    /// `len` bytes long so far
+    pub(crate) fn check_partial(
+        &mut self,
+        len: usize,
+        scanned: usize,
+    ) -> Result<(), (ErrorKind, String)> {
+        self.check_len(len)?;
+        self.step(scanned)
+    }
+
+    fn check_len(&self, len: usize) -> Result<(), (ErrorKind, String)> {
+        if let Some(max) = self.max_token_len {
+            if len > max {
+                return Err((
+                    ErrorKind::TokenTooLong,
+                    format!("token of {} bytes is longer than {}", len, max),
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    fn step(&mut self, steps: usize) -> Result<(), (ErrorKind, String)> {
+        if let Some(budget) = &mut self.step_budget {
+            budget.left = budget.left.saturating_sub(steps);
+            if budget.left == 0 {
+                if !(budget.keep_going)() {
+                    return Err((ErrorKind::Cancelled, "scan cancelled".to_string()));
+                }
+                budget.left = budget.steps;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::Scanner;
+
+    fn error_kind(js: &str, limits: Limits) -> Option<(ErrorKind, usize)> {
+        Scanner::with_limits(js, limits)
+            .find_map(|i| i.err())
+            .map(|e| (e.kind, e.idx))
+    }
+
+    #[test]
+    fn limits() {
+        let js = "function f(a) { return `${(a)}` + '0123456789'; }";
+        assert_eq!(error_kind(js, Limits::new()), None);
+        assert_eq!(
+            error_kind(js, Limits::new().max_depth(2)),
+            Some((ErrorKind::NestingTooDeep, 26))
+        );
+        assert_eq!(error_kind(js, Limits::new().max_depth(3)), None);
+        assert_eq!(
+            error_kind(js, Limits::new().max_token_len(11)),
+            Some((ErrorKind::TokenTooLong, 34))
+        );
+        assert_eq!(error_kind(js, Limits::new().max_token_len(12)), None);
+        assert_eq!(
+            error_kind(js, Limits::new().max_tokens(3)),
+            Some((ErrorKind::TooManyTokens, 11))
+        );
+        assert_eq!(
+            error_kind(js, Limits::new().step_budget(5, || false)),
+            Some((ErrorKind::Cancelled, 12))
+        );
+        assert_eq!(
+            error_kind(&"[".repeat(100), Limits::new().max_depth(10)),
+            Some((ErrorKind::NestingTooDeep, 10))
+        );
+        assert_eq!(error_kind("[[[]][]]", Limits::new().max_depth(3)), None);
+    }
+
+    #[test]
+    fn inside_tokens() {
+        // none of these are ever closed, so without checking
+        // inside the token each would only be a syntax error
+        // found at the end of the text
+        let tail = "x".repeat(10_000);
+        for (start, idx) in &[
+            ("a = '", 4),
+            ("a = `", 4),
+            ("a = `${b}", 8),
+            ("a /*", 2),
+            ("a //é", 2),
+            ("a\n<!--", 2),
+            ("a = /", 4),
+        ] {
+            let js = format!("{}{}", start, tail);
+            assert_eq!(
+                error_kind(&js, Limits::new().max_token_len(100)),
+                Some((ErrorKind::TokenTooLong, *idx)),
+                "{:?}",
+                start
+            );
+            let mut calls = 0;
+            let err = error_kind(
+                &js,
+                Limits::new().step_budget(1000, move || {
+                    calls += 1;
+                    calls < 3
+                }),
+            );
+            assert_eq!(err, Some((ErrorKind::Cancelled, *idx)), "{:?}", start);
+        }
+    }
+
+    #[test]
+    fn stops() {
+        let mut calls = 0;
+        let mut scanner = Scanner::with_limits(
+            "a b c d e f",
+            Limits::new().max_tokens(10).step_budget(2, move || {
+                calls += 1;
+                calls < 2
+            }),
+        );
+        assert!(scanner.next().unwrap().is_ok());
+        assert!(scanner.next().unwrap().is_ok());
+        assert!(scanner.next().unwrap().is_ok());
+        assert_eq!(
+            scanner.next().unwrap().unwrap_err().kind,
+            ErrorKind::Cancelled
+        );
+        assert!(scanner.next().is_none());
+    }
+}
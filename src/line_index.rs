@@ -0,0 +1,100 @@
+use crate::{Position, SourceLocation, Span};
+use alloc::vec::Vec;
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+/// The byte index of the start of each line in some
+/// JS text, for converting byte indexes into
+/// line/column `Position`s on request
+///
+/// Lines are 1 indexed and end at any of `\n`, `\r\n`,
+/// `\r`, `\u{2028}` or `\u{2029}`, columns are the 1 indexed
+/// byte offset from the start of the line.
+///
+/// ```
+/// use ress::{LineIndex, Position};
+/// let index = LineIndex::new("let a;\nlet b;");
+/// assert_eq!(index.position(11), Position::new(2, 5));
+/// ```
+pub struct LineIndex {
+    line_starts: Vec<usize>,
+    len: usize,
+}
+
+impl LineIndex {
+    pub fn new(text: &str) -> Self {
+        let mut line_starts = vec![0];
+        let bytes = text.as_bytes();
+        let mut i = 0;
+        while i < bytes.len() {
+            match bytes[i] {
+                b'\n' => line_starts.push(i + 1),
+                b'\r' if bytes.get(i + 1) != Some(&b'\n') => line_starts.push(i + 1),
+                // \u{2028} and \u{2029} are encoded as E2 80 A8/A9
+                0xE2 if bytes.get(i + 1) == Some(&0x80)
+                    && matches!(bytes.get(i + 2), Some(0xA8) | Some(0xA9)) =>
+                {
+                    i += 2;
+                    line_starts.push(i + 1);
+                }
+                _ => (),
+            }
+            i += 1;
+        }
+        Self {
+            line_starts,
+            len: text.len(),
+        }
+    }
+    /// The number of lines in the text
+    pub fn line_count(&self) -> usize {
+        self.line_starts.len()
+    }
+    /// The byte index the 1 indexed `line` starts at
+    pub fn line_start(&self, line: usize) -> Option<usize> {
+        self.line_starts.get(line.checked_sub(1)?).copied()
+    }
+    /// The line/column for a byte index, any index past
+    /// the end of the text is treated as the end
+    pub fn position(&self, idx: usize) -> Position {
+        let idx = idx.min(self.len);
+        let line = match self.line_starts.binary_search(&idx) {
+            Ok(line) => line,
+            Err(line) => line - 1,
+        };
+        Position::new(line + 1, idx - self.line_starts[line] + 1)
+    }
+    /// The start and end line/column for a span
+    pub fn location(&self, span: Span) -> SourceLocation {
+        SourceLocation::new(self.position(span.start), self.position(span.end))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn line_terminators() {
+        let index = LineIndex::new("a\nb\r\nc\rd\u{2028}e\u{2029}f");
+        assert_eq!(index.line_count(), 6);
+        assert_eq!(index.position(0), Position::new(1, 1));
+        assert_eq!(index.position(2), Position::new(2, 1));
+        assert_eq!(index.position(3), Position::new(2, 2));
+        assert_eq!(index.position(4), Position::new(2, 3));
+        assert_eq!(index.position(5), Position::new(3, 1));
+        assert_eq!(index.position(7), Position::new(4, 1));
+        assert_eq!(index.line_start(5), Some(11));
+        assert_eq!(index.position(15), Position::new(6, 1));
+        assert_eq!(index.position(100), Position::new(6, 2));
+        assert_eq!(index.line_start(7), None);
+    }
+
+    #[test]
+    fn location() {
+        let index = LineIndex::new("/*\n*/ a");
+        assert_eq!(
+            index.location(Span::new(0, 5)),
+            SourceLocation::new(Position::new(1, 1), Position::new(2, 3))
+        );
+    }
+}
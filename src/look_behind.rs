@@ -1,6 +1,11 @@
-use crate::tokenizer::RawKeyword;
+use crate::regex_trace::RegexRule;
+use crate::token_tree::Delimiter;
+use crate::tokenizer::{RawKeyword, RawToken, TemplateKind};
 use crate::tokens::Punct;
-use std::rc::Rc;
+use crate::tokens::Template;
+use crate::Position;
+use alloc::rc::Rc;
+use alloc::vec::Vec;
 
 /// A 2 element buffer of
 /// MetaTokens, this will use a
@@ -74,18 +79,54 @@ pub enum MetaToken {
     CloseParen(Paren),
     OpenBrace(Brace, u32),
     CloseBrace(Brace),
+    /// A template head or middle, which opens a substitution
+    OpenTemplate,
     Ident,
+    /// The identifier `of`, a keyword in the head of a `for`
+    Of,
+    /// The identifier `async`
+    Async,
     Other,
 }
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq)]
+/// What is known about a `(` when deciding if
+/// a `/` starts a regex
 pub struct Paren {
+    /// If this could be the parameters of a function expression
     pub func_expr: bool,
+    /// If this follows `if`, `for`, `while` or `with`
     pub conditional: bool,
+    /// If this is the head of a `for` loop
+    pub for_head: bool,
+    /// If this could be the parameters of a function or method
+    pub function: bool,
+    /// If this could be the parameters of a generator
+    pub generator: bool,
+    /// If this could be the parameters of an async
+    /// function or arrow
+    pub is_async: bool,
+    /// The byte index of the `(`
+    pub start: usize,
+    /// The line/column of the `(`
+    pub position: Position,
 }
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq)]
+/// What is known about a `{` when deciding if
+/// a `/` starts a regex
 pub struct Brace {
+    /// If this is a block rather than an expression,
+    /// like an object literal
     pub is_block: bool,
+    /// The `)` right before this, if there was one
     pub paren: Option<Paren>,
+    /// If `yield` is a keyword inside of this brace
+    pub yield_kw: bool,
+    /// If `await` is a keyword inside of this brace
+    pub await_kw: bool,
+    /// The byte index of the `{`
+    pub start: usize,
+    /// The line/column of the `{`
+    pub position: Position,
 }
 
 impl MetaToken {
@@ -102,23 +143,497 @@ impl PartialEq for MetaToken {
         match (self, other) {
             (MetaToken::Keyword(lhs, _), MetaToken::Keyword(rhs, _)) => lhs == rhs,
             (MetaToken::Punct(lhs), MetaToken::Punct(rhs)) => lhs == rhs,
-            (MetaToken::Ident, MetaToken::Ident) | (MetaToken::Other, MetaToken::Other) => true,
+            (MetaToken::Ident, MetaToken::Ident)
+            | (MetaToken::Of, MetaToken::Of)
+            | (MetaToken::Async, MetaToken::Async)
+            | (MetaToken::OpenTemplate, MetaToken::OpenTemplate)
+            | (MetaToken::Other, MetaToken::Other) => true,
             _ => false,
         }
     }
 }
 
-impl<T> From<(&crate::Token<T>, u32)> for MetaToken {
+impl MetaToken {
+    /// The `MetaToken` for an identifier, with the text `ident`
+    pub fn ident(ident: &str) -> Self {
+        match ident {
+            "of" => MetaToken::Of,
+            "async" => MetaToken::Async,
+            _ => MetaToken::Ident,
+        }
+    }
+    /// The `MetaToken` for a `RawToken`, `text` is the text
+    /// of the token
+    pub fn from_raw(token: &RawToken, text: &str, line: u32) -> Self {
+        match token {
+            RawToken::Keyword(k) => MetaToken::Keyword(*k, line),
+            RawToken::Punct(p) => MetaToken::Punct(*p),
+            RawToken::Ident => MetaToken::ident(text),
+            RawToken::Template {
+                kind: TemplateKind::Head,
+                ..
+            }
+            | RawToken::Template {
+                kind: TemplateKind::Body,
+                ..
+            } => MetaToken::OpenTemplate,
+            _ => MetaToken::Other,
+        }
+    }
+}
+
+impl<T: AsRef<str>> From<(&crate::Token<T>, u32)> for MetaToken {
     fn from((other, line): (&crate::Token<T>, u32)) -> Self {
         match other {
             crate::Token::Keyword(k) => MetaToken::Keyword(k.into(), line),
             crate::Token::Punct(p) => MetaToken::Punct(*p),
-            crate::Token::Ident(_) => MetaToken::Ident,
+            crate::Token::Ident(i) => MetaToken::ident(i.as_ref()),
+            crate::Token::Template(Template::Head(_))
+            | crate::Token::Template(Template::Middle(_)) => MetaToken::OpenTemplate,
             _ => MetaToken::Other,
         }
     }
 }
 
+/// The book keeping needed to decide if a `/`
+/// is the start of a regex or division
+#[derive(Debug, Clone)]
+pub struct RegexDetector {
+    pub last_three: LookBehind,
+    pub paren_stack: Vec<Paren>,
+    pub brace_stack: Vec<Brace>,
+    /// After a `class` keyword, if it is a class expression
+    /// and how many delimiters were open, the `{` with as
+    /// many open will start the class body
+    pub class_heading: Option<(bool, usize)>,
+}
+
+impl RegexDetector {
+    pub const fn new() -> Self {
+        Self {
+            last_three: LookBehind::new(),
+            paren_stack: Vec::new(),
+            brace_stack: Vec::new(),
+            class_heading: None,
+        }
+    }
+    #[inline]
+    /// Update the book keeping for a single non-comment token,
+    /// `start` and `position` are where the token starts and `line`
+    /// is the line number the scanner is currently on. If the token is a
+    /// `)` or `}` with nothing to close, the delimiter is returned as the error
+    pub fn keep_books(
+        &mut self,
+        token: MetaToken,
+        start: usize,
+        position: Position,
+        line: u32,
+    ) -> Result<(), Delimiter> {
+        match token {
+            MetaToken::Punct(Punct::OpenParen) => self.handle_open_paren_books(start, position),
+            MetaToken::Punct(Punct::OpenBrace) => {
+                self.handle_open_brace_books(start, position, line, false)
+            }
+            // a record is always an expression
+            MetaToken::Punct(Punct::HashOpenBrace) => {
+                self.handle_open_brace_books(start, position, line, true)
+            }
+            MetaToken::Punct(Punct::CloseParen) => self.handle_close_paren_books()?,
+            MetaToken::Punct(Punct::CloseBrace) => self.handle_close_brace_books()?,
+            MetaToken::Keyword(RawKeyword::Class, _) => {
+                let expr = self
+                    .last_three
+                    .one()
+                    .map(Self::check_for_expression)
+                    .unwrap_or(false);
+                self.class_heading = Some((expr, self.depth()));
+                self.last_three.push(token)
+            }
+            _ => self.last_three.push(token),
+        }
+        Ok(())
+    }
+    /// The number of `(` and `{` still open
+    fn depth(&self) -> usize {
+        self.paren_stack.len() + self.brace_stack.len()
+    }
+    /// The innermost `(` or `{` that is still open
+    pub fn innermost_open(&self) -> Option<(Delimiter, usize, Position)> {
+        let paren = self
+            .paren_stack
+            .last()
+            .map(|p| (Delimiter::Paren, p.start, p.position));
+        let brace = self
+            .brace_stack
+            .last()
+            .map(|b| (Delimiter::Brace, b.start, b.position));
+        match (paren, brace) {
+            (Some(paren), Some(brace)) => Some(if paren.1 > brace.1 { paren } else { brace }),
+            (paren, brace) => paren.or(brace),
+        }
+    }
+    #[inline]
+    /// Handle the book keeping when we find
+    /// an `(`
+    fn handle_open_paren_books(&mut self, start: usize, position: Position) {
+        let func_expr = if let Some(MetaToken::Keyword(RawKeyword::Function, _)) =
+            self.last_three.one()
+        {
+            if let Some(tok) = self.last_three.two() {
+                Self::check_for_expression(*tok)
+            } else {
+                false
+            }
+        } else if let Some(MetaToken::Keyword(RawKeyword::Function, _)) = self.last_three.two() {
+            if let Some(tok) = self.last_three.three() {
+                Self::check_for_expression(*tok)
+            } else {
+                false
+            }
+        } else {
+            false
+        };
+        let one = *self.last_three.one();
+        let two = *self.last_three.two();
+        let three = *self.last_three.three();
+        let for_head = match (one, two) {
+            (Some(MetaToken::Keyword(RawKeyword::For, _)), _) => true,
+            // for await (
+            (
+                Some(MetaToken::Keyword(RawKeyword::Await, _)),
+                Some(MetaToken::Keyword(RawKeyword::For, _)),
+            ) => true,
+            _ => false,
+        };
+        let conditional = for_head || one.map(Self::check_token_for_conditional).unwrap_or(false);
+        let function = matches!(
+            one,
+            Some(MetaToken::Keyword(RawKeyword::Function, _))
+                | Some(MetaToken::Ident)
+                | Some(MetaToken::Of)
+                | Some(MetaToken::Async)
+        ) || matches!(two, Some(MetaToken::Keyword(RawKeyword::Function, _)));
+        // function* ( or function* name ( or *name (
+        let generator = matches!(one, Some(MetaToken::Punct(Punct::Asterisk)))
+            || matches!(two, Some(MetaToken::Punct(Punct::Asterisk)));
+        // async ( or async name ( or async function ( or async function name (
+        let is_async = matches!(one, Some(MetaToken::Async))
+            || matches!(two, Some(MetaToken::Async))
+            || (matches!(three, Some(MetaToken::Async))
+                && matches!(two, Some(MetaToken::Keyword(RawKeyword::Function, _))));
+        let paren = Paren {
+            func_expr,
+            conditional,
+            for_head,
+            function,
+            generator,
+            is_async,
+            start,
+            position,
+        };
+        let meta = MetaToken::OpenParen(paren);
+        self.paren_stack.push(paren);
+        self.last_three.push(meta);
+    }
+    #[inline]
+    /// Handle the book keeping when we find
+    /// and `{`
+    fn handle_open_brace_books(
+        &mut self,
+        start: usize,
+        position: Position,
+        line: u32,
+        record: bool,
+    ) {
+        let class_body = match self.class_heading {
+            Some((expr, depth)) if depth == self.depth() => {
+                self.class_heading = None;
+                Some(expr)
+            }
+            _ => None,
+        };
+        let is_block = if record {
+            false
+        } else if let Some(expr) = class_body {
+            // a class expression's body ends an expression like
+            // an object literal does
+            !expr
+        } else if let Some(last) = self.last_three.one() {
+            match last {
+                MetaToken::Punct(Punct::OpenParen)
+                | MetaToken::Punct(Punct::OpenBracket)
+                | MetaToken::Punct(Punct::HashOpenBracket)
+                | MetaToken::OpenParen(_)
+                | MetaToken::OpenBrace(_, _)
+                | MetaToken::OpenTemplate
+                | MetaToken::Of => false,
+                MetaToken::Punct(Punct::Colon) => {
+                    if let Some(parent) = self.brace_stack.last() {
+                        parent.is_block
+                    } else {
+                        false
+                    }
+                }
+                MetaToken::Punct(_) => !Self::is_op(*last),
+                MetaToken::Keyword(RawKeyword::Return, line)
+                | MetaToken::Keyword(RawKeyword::Yield, line) => {
+                    if let Some(last) = self.last_three.two() {
+                        last.line_number() != *line
+                    } else {
+                        false
+                    }
+                }
+                MetaToken::Keyword(RawKeyword::Case, _) => false,
+                MetaToken::Keyword(_, _) => !Self::is_op(*last),
+                _ => true,
+            }
+        } else {
+            true
+        };
+        let paren = if let Some(MetaToken::CloseParen(open)) = self.last_three.one() {
+            Some(*open)
+        } else {
+            None
+        };
+        let (yield_kw, await_kw) = match (self.last_three.one(), self.last_three.two()) {
+            (Some(MetaToken::CloseParen(open)), _) if open.function => {
+                (open.generator, open.is_async)
+            }
+            // an arrow body, `yield` is never a keyword here
+            (Some(MetaToken::Punct(Punct::EqualGreaterThan)), two) => {
+                let is_async = match two {
+                    Some(MetaToken::CloseParen(open)) => open.is_async,
+                    Some(MetaToken::Ident) => {
+                        matches!(self.last_three.three(), Some(MetaToken::Async))
+                    }
+                    _ => false,
+                };
+                (false, is_async)
+            }
+            _ => self
+                .brace_stack
+                .last()
+                .map(|parent| (parent.yield_kw, parent.await_kw))
+                .unwrap_or((false, true)),
+        };
+        let brace = Brace {
+            is_block,
+            paren,
+            yield_kw,
+            await_kw,
+            start,
+            position,
+        };
+        self.brace_stack.push(brace);
+        self.last_three.push(MetaToken::OpenBrace(brace, line));
+    }
+    #[inline]
+    /// Handle the book keeping when we find a `)`
+    fn handle_close_paren_books(&mut self) -> Result<(), Delimiter> {
+        let paren = self.paren_stack.pop().ok_or(Delimiter::Paren)?;
+        self.last_three.push(MetaToken::CloseParen(paren));
+        Ok(())
+    }
+    #[inline]
+    /// Handle the book keeping when we find a `}`
+    fn handle_close_brace_books(&mut self) -> Result<(), Delimiter> {
+        let open = self.brace_stack.pop().ok_or(Delimiter::Brace)?;
+        self.last_three.push(MetaToken::CloseBrace(open));
+        Ok(())
+    }
+    /// Detect if the `/` is the beginning of
+    /// a regex or is division
+    ///
+    /// [see this for more details](https://github.com/sweet-js/sweet-core/wiki/design)
+    pub fn is_regex_start(&self) -> bool {
+        self.rule().is_regex()
+    }
+    /// The rule that decides if a `/` here is the
+    /// beginning of a regex, based on the token before it
+    pub fn rule(&self) -> RegexRule {
+        let last_token = match self.last_three.one() {
+            Some(token) => *token,
+            None => return RegexRule::StartOfInput,
+        };
+        match last_token {
+            MetaToken::Keyword(RawKeyword::Yield, _) => RegexRule::Yield {
+                is_keyword: self.yield_is_keyword(),
+            },
+            MetaToken::Keyword(RawKeyword::Await, _) => RegexRule::Await {
+                is_keyword: self.await_is_keyword(),
+            },
+            MetaToken::Keyword(k, _) => RegexRule::Keyword(k.with_str(())),
+            // the topic reference is an operand
+            MetaToken::Punct(Punct::DoubleAtMark) => RegexRule::Operand,
+            MetaToken::Punct(p) => RegexRule::Punct(p),
+            MetaToken::OpenParen(open) => RegexRule::OpenParen(open),
+            MetaToken::CloseParen(open) => RegexRule::CloseParen(open),
+            MetaToken::OpenBrace(open, _) => RegexRule::OpenBrace(open),
+            MetaToken::CloseBrace(open) => RegexRule::CloseBrace(open),
+            MetaToken::OpenTemplate => RegexRule::OpenTemplate,
+            MetaToken::Of => RegexRule::Of {
+                is_keyword: self.of_is_keyword(),
+            },
+            MetaToken::Ident | MetaToken::Async | MetaToken::Other => RegexRule::Operand,
+        }
+    }
+    /// If `yield` is a keyword here, it is only inside
+    /// of a generator's body
+    fn yield_is_keyword(&self) -> bool {
+        self.brace_stack
+            .last()
+            .map(|brace| brace.yield_kw)
+            .unwrap_or(false)
+    }
+    /// If `await` is a keyword here, it is at the top level
+    /// of a module, inside of an async function's body and
+    /// at the start of an async arrow's expression body
+    fn await_is_keyword(&self) -> bool {
+        matches!(
+            self.last_three.two(),
+            Some(MetaToken::Punct(Punct::EqualGreaterThan))
+        ) || self
+            .brace_stack
+            .last()
+            .map(|brace| brace.await_kw)
+            .unwrap_or(true)
+    }
+    /// If `of` is a keyword here, only directly inside the head
+    /// of a `for` right after the binding, so not `for (of of x)`,
+    /// `for (a.of;;)` or `for (let x = of / 2;;)`
+    fn of_is_keyword(&self) -> bool {
+        let in_head = match self.innermost_open() {
+            Some((Delimiter::Paren, _, _)) => self
+                .paren_stack
+                .last()
+                .map(|paren| paren.for_head)
+                .unwrap_or(false),
+            _ => false,
+        };
+        if !in_head {
+            return false;
+        }
+        match self.last_three.two() {
+            // `for (of of x)` or `for (var of of x)`, the first `of`
+            // was the binding so this one is the keyword
+            Some(MetaToken::Of) => matches!(
+                self.last_three.three(),
+                Some(MetaToken::OpenParen(_))
+                    | Some(MetaToken::Keyword(RawKeyword::Var, _))
+                    | Some(MetaToken::Keyword(RawKeyword::Let, _))
+                    | Some(MetaToken::Keyword(RawKeyword::Const, _))
+            ),
+            Some(MetaToken::Ident)
+            | Some(MetaToken::Async)
+            | Some(MetaToken::CloseBrace(_))
+            | Some(MetaToken::Punct(Punct::CloseBracket)) => true,
+            _ => false,
+        }
+    }
+    /// Check a token for the conditional keywords
+    ///
+    /// > used in determining if we are at a regex or not
+    fn check_token_for_conditional(tok: MetaToken) -> bool {
+        if let MetaToken::Keyword(k, _) = tok {
+            matches!(
+                k,
+                RawKeyword::If | RawKeyword::For | RawKeyword::While | RawKeyword::With
+            )
+        } else {
+            false
+        }
+    }
+    /// Check if a token indicates beginning of a
+    /// function expression
+    ///
+    /// > used in determining if we are at a regex or not
+    fn check_for_expression(token: MetaToken) -> bool {
+        if Self::is_op(token) {
+            true
+        } else {
+            matches!(
+                token,
+                MetaToken::Keyword(RawKeyword::Return, _)
+                    | MetaToken::Keyword(RawKeyword::Case, _)
+                    | MetaToken::Keyword(RawKeyword::Extends, _)
+                    | MetaToken::Keyword(RawKeyword::Yield, _)
+                    | MetaToken::Keyword(RawKeyword::Await, _)
+                    | MetaToken::Punct(Punct::OpenBracket)
+                    | MetaToken::Punct(Punct::HashOpenBracket)
+                    | MetaToken::Punct(Punct::EqualGreaterThan)
+                    | MetaToken::Punct(Punct::Ellipsis)
+                    | MetaToken::OpenParen(_)
+                    | MetaToken::OpenTemplate
+                    | MetaToken::Of
+            )
+        }
+    }
+    /// Determine if a token is a punctuation or keyword
+    /// that indicates an operation
+    ///
+    /// > used in determining if we are at a regex or not
+    fn is_op(tok: MetaToken) -> bool {
+        match tok {
+            MetaToken::Punct(ref p) => matches!(
+                p,
+                Punct::Equal
+                    | Punct::PlusEqual
+                    | Punct::DashEqual
+                    | Punct::AsteriskEqual
+                    | Punct::ForwardSlashEqual
+                    | Punct::PercentEqual
+                    | Punct::DoubleLessThanEqual
+                    | Punct::DoubleGreaterThanEqual
+                    | Punct::TripleGreaterThanEqual
+                    | Punct::AmpersandEqual
+                    | Punct::PipeEqual
+                    | Punct::CaretEqual
+                    | Punct::Comma
+                    | Punct::Plus
+                    | Punct::Dash
+                    | Punct::Asterisk
+                    | Punct::ForwardSlash
+                    | Punct::Percent
+                    | Punct::DoubleLessThan
+                    | Punct::DoubleGreaterThan
+                    | Punct::TripleGreaterThan
+                    | Punct::Ampersand
+                    | Punct::Pipe
+                    | Punct::Caret
+                    | Punct::DoubleAmpersand
+                    | Punct::DoublePipe
+                    | Punct::QuestionMark
+                    | Punct::Colon
+                    | Punct::TripleEqual
+                    | Punct::DoubleEqual
+                    | Punct::GreaterThanEqual
+                    | Punct::LessThanEqual
+                    | Punct::LessThan
+                    | Punct::GreaterThan
+                    | Punct::BangEqual
+                    | Punct::BangDoubleEqual
+                    | Punct::DoublePlus
+                    | Punct::DoubleDash
+                    | Punct::Tilde
+                    | Punct::Bang
+                    | Punct::PipeGreaterThan
+                    | Punct::DoubleColon
+            ),
+            MetaToken::Keyword(k, _) => matches!(
+                k,
+                RawKeyword::InstanceOf
+                    | RawKeyword::In
+                    | RawKeyword::Delete
+                    | RawKeyword::Void
+                    | RawKeyword::TypeOf
+                    | RawKeyword::Throw
+                    | RawKeyword::New
+            ),
+            _ => false,
+        }
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct OpenBrace {
     pub look_behind: LookBehind,
@@ -135,7 +650,7 @@ pub struct CloseParen {
     pub open: LookBehind,
 }
 
-impl std::ops::Deref for OpenBrace {
+impl core::ops::Deref for OpenBrace {
     type Target = LookBehind;
     fn deref(&self) -> &Self::Target {
         &self.look_behind
@@ -1,13 +1,19 @@
 use crate::{
-    error::{Error, RawError},
+    error::{Error, ErrorKind, RawError},
+    lex_state::OpenToken,
+    options::{Comments, ScannerOptions},
     tokenizer::{self, RawToken, Tokenizer},
     tokens::{self, prelude::*},
-    Item, Span,
+    Item, LineIndex, Position, SourceLocation, Span,
 };
+use alloc::string::{String, ToString};
+use core::cell::OnceCell;
 
 type Res<T> = Result<T, Error>;
 type Ret<'a> = Option<Res<Item<&'a str>>>;
 
+const NO_LOCATION: SourceLocation = SourceLocation::new(Position::new(0, 0), Position::new(0, 0));
+
 pub struct ManualScanner<'a> {
     pub stream: Tokenizer<'a>,
     pub eof: bool,
@@ -17,24 +23,92 @@ pub struct ManualScanner<'a> {
     errored: bool,
     pub new_line_count: usize,
     line_cursor: usize,
+    /// The byte index `new_line_count` and `line_cursor` describe
+    cursor_idx: usize,
     at_first_on_line: bool,
+    track_locations: bool,
+    skip_comments: bool,
+    recover: bool,
+    /// Built the first time a position is looked
+    /// up instead of tracked
+    lines: OnceCell<LineIndex>,
 }
 
 impl<'b> ManualScanner<'b> {
     pub fn new(text: &'b str) -> Self {
         let mut stream = Tokenizer::new(text);
-        let (new_line_count, line_cursor) = stream.skip_whitespace();
-        Self {
+        let (_, leading_whitespace) = stream.skip_whitespace();
+        let mut ret = Self {
             stream,
             eof: false,
             pending_new_line: false,
             original: text,
             errored: false,
-            new_line_count,
-            line_cursor: usize::max(line_cursor, 1),
+            new_line_count: 0,
+            line_cursor: 1,
+            cursor_idx: 0,
             at_first_on_line: true,
-            last_skipped_whitespace: line_cursor,
-        }
+            last_skipped_whitespace: leading_whitespace,
+            track_locations: true,
+            skip_comments: false,
+            recover: false,
+            lines: OnceCell::new(),
+        };
+        ret.seek(ret.stream.stream.idx);
+        ret
+    }
+    /// Create a new `ManualScanner` with the `options` provided,
+    /// `ScannerOptions` that only apply to a `Scanner` are ignored
+    pub fn with_options(text: &'b str, options: ScannerOptions) -> Self {
+        let mut ret = match &options.resume {
+            Some(state) => Self::resume(text, state.open(), !state.mid_source()),
+            None => Self::new(text),
+        };
+        ret.configure(&options);
+        ret
+    }
+    /// Apply the `options` that change how tokens are scanned
+    pub(crate) fn configure(&mut self, options: &ScannerOptions) {
+        self.stream.html_comments = options.html_comments;
+        self.stream.proposals = options.proposals;
+        self.stream.at_source_start = self.stream.at_source_start && options.hashbang;
+        self.track_locations = options.locations;
+        self.skip_comments = options.comments == Comments::Skip;
+        self.recover = options.recover;
+    }
+    /// Create a new `ManualScanner` for a piece of a larger
+    /// text starting in the middle of `open`, returning any
+    /// token cut off by the end of `text` instead of an error
+    pub(crate) fn resume(text: &'b str, open: Option<OpenToken>, at_source_start: bool) -> Self {
+        let mut ret = if open.is_some() {
+            // leading whitespace is part of the open token
+            Self {
+                stream: Tokenizer::new(text),
+                last_skipped_whitespace: 0,
+                new_line_count: 0,
+                line_cursor: 1,
+                cursor_idx: 0,
+                ..Self::new("")
+            }
+        } else {
+            Self::new(text)
+        };
+        ret.original = text;
+        ret.stream.partial = true;
+        ret.stream.resume_in = open;
+        ret.stream.at_source_start = at_source_start;
+        ret
+    }
+    /// Create a new `ManualScanner` that skips tracking
+    /// line/column positions, every `Item` will have a
+    /// zeroed `location`. Positions can be looked up
+    /// with a `LineIndex` as needed.
+    pub fn without_locations(text: &'b str) -> Self {
+        Self::with_options(text, ScannerOptions::new().locations(false))
+    }
+    /// If this scanner is tracking line/column positions
+    pub fn tracks_locations(&self) -> bool {
+        self.track_locations
     }
 
     /// Skip any upcoming comments to get the
@@ -65,107 +139,133 @@ impl<'b> ManualScanner<'b> {
     #[inline]
     pub fn set_state(&mut self, state: ScannerState) {
         self.stream.stream.idx = state.cursor;
+        self.cursor_idx = state.cursor;
         self.new_line_count = state.new_line_count;
         self.line_cursor = state.line_cursor;
         self.at_first_on_line = state.at_first_on_line;
     }
 
     pub fn next_token(&mut self) -> Ret<'b> {
-        if self.eof {
-            debug!("end of iterator, returning None");
-            return None;
+        loop {
+            if self.eof {
+                debug!("end of iterator, returning None");
+                return None;
+            };
+            let state = self.get_state();
+            match self.next_item() {
+                Ok(item) if self.skip_comments && item.token.is_comment() => continue,
+                Err(e) if self.recover && !e.kind.is_limit() => {
+                    self.skip_char(state);
+                    return Some(Err(e));
+                }
+                ret => return Some(ret),
+            }
+        }
+    }
+    /// Move past the character a token that failed
+    /// to scan started at, or all of a string that failed
+    fn skip_char(&mut self, state: ScannerState) {
+        let cursor = state.cursor;
+        let rest = &self.original[cursor..];
+        let len = match rest.chars().next() {
+            Some(quote) if quote == '\'' || quote == '"' => string_len(rest, quote),
+            Some(c) => c.len_utf8(),
+            None => 0,
         };
-        Some(self.next_item())
+        self.skip_to(state, cursor + len);
+    }
+    /// Move past a regex that failed to scan, to the next line
+    /// terminator since a regex can't contain one
+    fn skip_regex(&mut self, state: ScannerState) {
+        if self.pending_new_line {
+            // the line already ended between the `/` and `state`
+            return self.set_state(state);
+        }
+        let cursor = state.cursor;
+        let len = self.original[cursor..]
+            .find(crate::is_line_term)
+            .unwrap_or(self.original.len() - cursor);
+        self.skip_to(state, cursor + len);
+    }
+    /// Move from `state` to the byte index `idx` and
+    /// on to the start of the next token
+    fn skip_to(&mut self, state: ScannerState, idx: usize) {
+        let cursor = state.cursor;
+        self.set_state(state);
+        let (skipped_lines, _) = line_breaks(self.original, cursor, idx, 0);
+        self.stream.stream.idx = idx;
+        self.at_first_on_line = false;
+        let (new_line_count, leading_whitespace) = self.stream.skip_whitespace();
+        self.advance(self.stream.stream.idx, skipped_lines + new_line_count);
+        self.pending_new_line = new_line_count > 0;
+        self.last_skipped_whitespace = leading_whitespace;
     }
 
     fn next_item(&mut self) -> Res<Item<&'b str>> {
-        let (_, prev_lines, prev_line_cursor) = self.capture_cursors();
         let next = match self.stream.next(self.at_first_on_line) {
             Ok(n) => n,
-            Err(e) => {
-                self.errored = true;
-                return self.error(e);
-            }
+            Err(e) => return self.tokenizer_error(e),
         };
 
-        let mut len = next.end - next.start;
+        // tokens split across texts by `Scanner::resume` may be
+        // missing their opening or closing delimiters
+        let continued = self.stream.continued;
+        let open = self.stream.open.is_some();
+        let token_lines = match next.ty {
+            RawToken::Comment { new_line_count, .. }
+            | RawToken::String { new_line_count, .. }
+            | RawToken::Template { new_line_count, .. } => new_line_count,
+            _ => 0,
+        };
         let ret = {
-            let mut new_lines = 0;
             let s = self.slice_original(next.start, next.end)?;
             let token = match next.ty {
                 RawToken::Boolean(b) => Token::Boolean(b.into()),
                 RawToken::Comment {
-                    kind,
-                    new_line_count,
-                    last_len,
-                    end_index,
-                } => {
-                    len = last_len;
-                    new_lines = new_line_count;
-                    match kind {
-                        tokens::CommentKind::Multi => {
-                            let (tail_content, tail_start) =
-                                if let Some(tail_start) = s[end_index..].find("-->") {
-                                    let actual_start = end_index + tail_start;
-                                    (Some(&s[actual_start + 3..]), actual_start)
-                                } else {
-                                    (None, s.len())
-                                };
-                            let content = s[..tail_start]
-                                .trim_start_matches("/*")
-                                .trim_end_matches("*/");
-                            Token::Comment(Comment {
-                                kind: tokens::CommentKind::Multi,
-                                content,
-                                tail_content,
-                            })
-                        }
-                        tokens::CommentKind::Single => {
-                            Token::Comment(Comment::new_single_line(s.trim_start_matches("//")))
-                        }
-                        tokens::CommentKind::Html => {
-                            let start_idx = if s.starts_with("<!--") { 4 } else { 0 };
-                            let (content, tail) = if let Some(idx) = s.rfind("-->") {
-                                let actual_end = idx.saturating_add(3);
-                                if actual_end < next.end {
-                                    let tail = &s[actual_end..];
-                                    let tail = if tail.is_empty() { None } else { Some(tail) };
-                                    (&s[start_idx..idx], tail)
-                                } else {
-                                    (&s[start_idx..], None)
-                                }
+                    kind, end_index, ..
+                } => match kind {
+                    tokens::CommentKind::Multi => {
+                        let (tail_content, tail_start) =
+                            if let Some(tail_start) = s[end_index..].find("-->") {
+                                let actual_start = end_index + tail_start;
+                                (Some(&s[actual_start + 3..]), actual_start)
                             } else {
-                                (&s[start_idx..], None)
+                                (None, s.len())
                             };
-                            if start_idx == 0 && !self.at_first_on_line(next.start)? {
-                                self.errored = true;
-                                return Err(Error {
-                                    line: self.new_line_count,
-                                    column: self.line_cursor,
-                                    msg: "--> comments must either be a part of a full HTML \
-                                          comment or the first item on a new line"
-                                        .to_string(),
-                                    idx: start_idx,
-                                });
-                            }
-                            Token::Comment(Comment::new_html(content, tail))
-                        }
-                        tokens::CommentKind::Hashbang => {
-                            Token::Comment(Comment::new_hashbang(&s[2..]))
+                        let content = if continued || open {
+                            let end = if open { s.len() } else { end_index - 2 };
+                            &s[if continued { 0 } else { 2 }..end]
+                        } else {
+                            s[..tail_start]
+                                .trim_start_matches("/*")
+                                .trim_end_matches("*/")
+                        };
+                        Token::Comment(Comment {
+                            kind: tokens::CommentKind::Multi,
+                            content,
+                            tail_content,
+                        })
+                    }
+                    tokens::CommentKind::Single => {
+                        Token::Comment(Comment::new_single_line(s.trim_start_matches("//")))
+                    }
+                    tokens::CommentKind::Html => {
+                        let (content, tail) = Comment::split_html(s);
+                        let first_on_line = self.at_first_on_line(next.start)?;
+                        if let Some(e) = Self::html_comment_error(s, next.start, first_on_line) {
+                            self.errored = true;
+                            return self.error(e);
                         }
+                        Token::Comment(Comment::new_html(content, tail))
                     }
-                }
+                    tokens::CommentKind::Hashbang => Token::Comment(Comment::new_hashbang(&s[2..])),
+                },
                 RawToken::EoF => {
                     self.eof = true;
-                    return Ok(Item::new_(
-                        Token::EoF,
-                        self.original.len(),
-                        self.original.len(),
-                        prev_lines.saturating_add(1),
-                        prev_line_cursor,
-                        self.new_line_count.saturating_add(1),
-                        self.line_cursor,
-                    ));
+                    let end = self.original.len();
+                    self.advance(end, 0);
+                    let position = self.position();
+                    return Ok(self.new_item(Token::EoF, end, end, position, position));
                 }
                 RawToken::Ident => Token::Ident(Ident::from(s)),
                 RawToken::Keyword(k) => Token::Keyword(k.with_str(s)),
@@ -175,13 +275,10 @@ impl<'b> ManualScanner<'b> {
                 RawToken::RegEx(_) => unreachable!("Regex from next"),
                 RawToken::String {
                     kind,
-                    new_line_count,
-                    last_len,
                     found_octal_escape,
+                    ..
                 } => {
-                    len = last_len;
-                    new_lines = new_line_count;
-                    let s = &s[1..s.len() - 1];
+                    let s = trim_delimiters(s, continued, open, 1);
                     match kind {
                         tokenizer::StringKind::Double => {
                             Token::String(StringLit::double(s, found_octal_escape))
@@ -193,129 +290,110 @@ impl<'b> ManualScanner<'b> {
                 }
                 RawToken::Template {
                     kind,
-                    new_line_count,
-                    last_len,
                     has_octal_escape,
                     found_invalid_unicode_escape,
                     found_invalid_hex_escape,
-                } => {
-                    len = last_len;
-                    new_lines = new_line_count;
-                    match kind {
-                        tokenizer::TemplateKind::Head => {
-                            let s = &s[1..s.len() - 2];
-                            Token::Template(Template::template_head(
-                                s,
-                                has_octal_escape,
-                                found_invalid_unicode_escape,
-                                found_invalid_hex_escape,
-                            ))
-                        }
-                        tokenizer::TemplateKind::Body => {
-                            let s = &s[1..s.len() - 2];
-                            Token::Template(Template::template_middle(
-                                s,
-                                has_octal_escape,
-                                found_invalid_unicode_escape,
-                                found_invalid_hex_escape,
-                            ))
-                        }
-                        tokenizer::TemplateKind::Tail => {
-                            let s = &s[1..s.len() - 1];
-                            Token::Template(Template::template_tail(
-                                s,
-                                has_octal_escape,
-                                found_invalid_unicode_escape,
-                                found_invalid_hex_escape,
-                            ))
-                        }
-                        tokenizer::TemplateKind::NoSub => {
-                            let s = &s[1..s.len() - 1];
-                            Token::Template(Template::no_sub_template(
-                                s,
-                                has_octal_escape,
-                                found_invalid_unicode_escape,
-                                found_invalid_hex_escape,
-                            ))
-                        }
+                    ..
+                } => match kind {
+                    tokenizer::TemplateKind::Head => {
+                        let s = trim_delimiters(s, continued, open, 2);
+                        Token::Template(Template::template_head(
+                            s,
+                            has_octal_escape,
+                            found_invalid_unicode_escape,
+                            found_invalid_hex_escape,
+                        ))
                     }
-                }
+                    tokenizer::TemplateKind::Body => {
+                        let s = trim_delimiters(s, continued, open, 2);
+                        Token::Template(Template::template_middle(
+                            s,
+                            has_octal_escape,
+                            found_invalid_unicode_escape,
+                            found_invalid_hex_escape,
+                        ))
+                    }
+                    tokenizer::TemplateKind::Tail => {
+                        let s = trim_delimiters(s, continued, open, 1);
+                        Token::Template(Template::template_tail(
+                            s,
+                            has_octal_escape,
+                            found_invalid_unicode_escape,
+                            found_invalid_hex_escape,
+                        ))
+                    }
+                    tokenizer::TemplateKind::NoSub => {
+                        let s = trim_delimiters(s, continued, open, 1);
+                        Token::Template(Template::no_sub_template(
+                            s,
+                            has_octal_escape,
+                            found_invalid_unicode_escape,
+                            found_invalid_hex_escape,
+                        ))
+                    }
+                },
             };
             self.at_first_on_line = self.at_first_on_line && token.is_multi_line_comment();
-            self.bump_line_cursors(new_lines, len);
-            Item::new_(
-                token,
-                next.start,
-                next.end,
-                prev_lines.saturating_add(1),
-                prev_line_cursor,
-                self.new_line_count.saturating_add(1),
-                self.line_cursor,
-            )
+            self.advance(next.start, 0);
+            let start = self.position();
+            self.advance(next.end, token_lines);
+            self.new_item(token, next.start, next.end, start, self.position())
         };
         let (new_line_count, leading_whitespace) = self.stream.skip_whitespace();
-        self.bump_line_cursors(new_line_count, leading_whitespace);
+        self.advance(self.stream.stream.idx, new_line_count);
         self.pending_new_line = new_line_count > 0;
         self.last_skipped_whitespace = leading_whitespace;
         Ok(ret)
     }
     /// Get the next token as a regular expression. The previous token
     /// should have been `/` or `/=`,
+    ///
+    /// When recovering from errors, a regular expression that
+    /// fails to scan is skipped up to the end of its line.
     pub fn next_regex(&mut self, prev_len: usize) -> Option<Res<Item<&'b str>>> {
-        Some(self.next_regex_item(prev_len))
+        let state = self.get_state();
+        let ret = self.next_regex_item(prev_len);
+        if matches!(&ret, Err(e) if self.recover && !e.kind.is_limit()) {
+            self.skip_regex(state);
+        }
+        Some(ret)
     }
 
     fn next_regex_item(&mut self, prev_len: usize) -> Res<Item<&'b str>> {
         self.stream.stream.skip_back(self.last_skipped_whitespace);
-        self.line_cursor = self
-            .line_cursor
-            .saturating_sub(self.last_skipped_whitespace);
-        let (_, prev_lines, prev_line_cursor) = self.capture_cursors();
         let next = match self.stream.next_regex(prev_len) {
             Ok(n) => n,
-            Err(e) => {
-                self.errored = true;
-                return self.error(e);
-            }
+            Err(e) => return self.tokenizer_error(e),
         };
         let ret = match next.ty {
             RawToken::RegEx(body_end) => {
-                self.line_cursor = self.line_cursor.saturating_sub(prev_len);
-                self.line_cursor = self.line_cursor.saturating_add(next.end - next.start);
                 let flags = if next.end > body_end {
                     Some(self.slice_original(body_end, next.end)?)
                 } else {
                     None
                 };
                 let body = self.slice_original(next.start + 1, body_end - 1)?;
-                Item::new_(
+                self.advance(next.start, 0);
+                let start = self.position();
+                self.advance(next.end, 0);
+                self.new_item(
                     Token::RegEx(RegEx { body, flags }),
                     next.start,
                     next.end,
-                    prev_lines + 1,
-                    prev_line_cursor.saturating_sub(prev_len),
-                    prev_lines + 1,
-                    self.line_cursor,
+                    start,
+                    self.position(),
                 )
             }
             _ => {
                 unreachable!();
             }
         };
-        let (new_line_count, leading_whitespace) = self.stream.skip_whitespace();
-        self.bump_line_cursors(new_line_count, leading_whitespace);
+        let (new_line_count, _) = self.stream.skip_whitespace();
+        self.advance(self.stream.stream.idx, new_line_count);
         self.pending_new_line = new_line_count > 0;
         Ok(ret)
     }
 
-    fn capture_cursors(&self) -> (usize, usize, usize) {
-        (
-            self.stream.stream.idx,
-            self.new_line_count,
-            self.line_cursor,
-        )
-    }
-
     /// Get a string for any given span
     pub fn string_for(&self, span: &Span) -> Option<String> {
         Some(self.str_for(span)?.to_string())
@@ -326,50 +404,89 @@ impl<'b> ManualScanner<'b> {
     }
     /// Get the line/column pair for any given byte index
     pub fn position_for(&self, idx: usize) -> (usize, usize) {
-        let mut line_ct = 1;
-        // This is the byte position, not the character
-        // position to account for multi byte chars
-        let mut byte_position = 0;
-        // loop over the characters
-        for (i, c) in self.original.chars().enumerate() {
-            if i >= idx {
-                return (line_ct, byte_position);
+        let position = self.line_index().position(idx);
+        (position.line, position.column)
+    }
+    /// The text being scanned
+    pub(crate) fn text(&self) -> &'b str {
+        self.original
+    }
+    /// The `LineIndex` for the text, built on first use
+    pub(crate) fn line_index(&self) -> &LineIndex {
+        self.lines.get_or_init(|| LineIndex::new(self.original))
+    }
+    /// The line/column of `cursor_idx`
+    fn position(&self) -> Position {
+        Position::new(self.new_line_count + 1, self.line_cursor)
+    }
+    /// Move the cursor forward to the byte index `idx`, with
+    /// `new_lines` line breaks between the cursor and `idx`
+    ///
+    /// Without locations only `new_line_count`, which regex
+    /// detection needs, is kept up to date and the text
+    /// in between isn't looked at again
+    fn advance(&mut self, idx: usize, new_lines: usize) {
+        if self.track_locations {
+            return self.seek(idx);
+        }
+        self.cursor_idx = idx;
+        if new_lines > 0 {
+            self.new_line_count += new_lines;
+            self.at_first_on_line = true;
+        }
+    }
+    /// Move `new_line_count` and `line_cursor` to
+    /// describe the byte index `idx`
+    fn seek(&mut self, idx: usize) {
+        let from = self.cursor_idx;
+        self.cursor_idx = idx;
+        if idx >= from {
+            // the line may have started before the text a resumed
+            // scanner was given, so this can wrap below 0
+            let line_start = (from + 1).wrapping_sub(self.line_cursor);
+            let (count, line_start) = line_breaks(self.original, from, idx, line_start);
+            if count > 0 {
+                self.new_line_count += count;
+                self.at_first_on_line = true;
             }
-            match c {
-                '\r' => {
-                    // look ahead 1 char to see if it is a newline pair
-                    // if so, don't include it, it will get included in the next
-                    // iteration
-                    if let Some(next) = self.original.get(byte_position..byte_position + 2) {
-                        if next != "\r\n" {
-                            line_ct += 1;
-                            byte_position = 0;
-                        }
-                    }
-                }
-                '\n' | '\u{2028}' | '\u{2029}' => {
-                    line_ct += 1;
-                    byte_position = 0;
-                }
-                _ => byte_position += c.len_utf8(),
-            };
+            self.line_cursor = idx.wrapping_sub(line_start) + 1;
+            return;
+        }
+        let (count, _) = line_breaks(self.original, idx, from, idx);
+        if count == 0 {
+            self.line_cursor -= from - idx;
+            return;
         }
-        (line_ct, byte_position)
+        self.new_line_count -= count;
+        let line_start = self.original[..idx]
+            .rfind(crate::is_line_term)
+            .map_or(0, |i| {
+                i + self.original[i..].chars().next().map_or(1, char::len_utf8)
+            });
+        self.line_cursor = idx - line_start + 1;
     }
     #[inline]
-    /// Helper to handle new lines
-    fn bump_line_cursors(&mut self, ct: usize, len: usize) {
-        if ct != 0 {
-            self.line_cursor = len;
-            self.new_line_count += ct;
-            self.at_first_on_line = true;
+    fn new_item(
+        &self,
+        token: Token<&'b str>,
+        span_start: usize,
+        span_end: usize,
+        start: Position,
+        end: Position,
+    ) -> Item<&'b str> {
+        let location = if self.track_locations {
+            SourceLocation::new(start, end)
         } else {
-            self.line_cursor += len;
-        }
+            NO_LOCATION
+        };
+        Item::new(token, Span::new(span_start, span_end), location)
     }
     #[inline]
     fn at_first_on_line(&self, token_start: usize) -> Res<bool> {
         trace!("at_first_on_line");
+        if !self.track_locations {
+            return Ok(crate::first_on_line(self.original, token_start));
+        }
         if self.line_cursor <= 1 {
             return Ok(true);
         }
@@ -378,6 +495,23 @@ impl<'b> ManualScanner<'b> {
         trace!("prefix: {:?}", prefix);
         Ok(prefix.chars().all(|c| c.is_whitespace()))
     }
+    /// The error for the HTML comment `comment` starting at `start`,
+    /// a `-->` comment has to be the first item on its line
+    pub(crate) fn html_comment_error(
+        comment: &str,
+        start: usize,
+        first_on_line: bool,
+    ) -> Option<RawError> {
+        if comment.starts_with("<!--") || first_on_line {
+            return None;
+        }
+        Some(RawError {
+            idx: start,
+            msg: "--> comments must either be a part of a full HTML \
+                  comment or the first item on a new line"
+                .to_string(),
+        })
+    }
     /// Helper to handle the error cases
     fn error<T>(&self, raw_error: RawError) -> Res<T> {
         let RawError { idx, msg } = &raw_error;
@@ -387,6 +521,20 @@ impl<'b> ManualScanner<'b> {
             column,
             msg: msg.clone(),
             idx: *idx,
+            kind: ErrorKind::Syntax,
+        })
+    }
+
+    /// Handle an error from `stream`, which may have
+    /// come from one of its `limits`
+    fn tokenizer_error<T>(&mut self, raw_error: RawError) -> Res<T> {
+        self.errored = true;
+        let kind = self.stream.limit_hit.take();
+        self.error(raw_error).map_err(|mut err| {
+            if let Some(kind) = kind {
+                err.kind = kind;
+            }
+            err
         })
     }
 
@@ -445,6 +593,71 @@ pub struct ScannerState {
     pub at_first_on_line: bool,
 }
 
+/// Count the line breaks between the byte indexes `start`
+/// and `end` of `text`, returning the count and the index
+/// the last line starts at, `line_start` if there were none
+///
+/// A `\r\n` pair is a single line break, like in `LineIndex`
+fn line_breaks(text: &str, start: usize, end: usize, mut line_start: usize) -> (usize, usize) {
+    let bytes = text.as_bytes();
+    let mut count = 0;
+    let mut i = start;
+    while i < end {
+        match bytes[i] {
+            b'\n' => {
+                count += 1;
+                line_start = i + 1;
+            }
+            b'\r' if bytes.get(i + 1) != Some(&b'\n') => {
+                count += 1;
+                line_start = i + 1;
+            }
+            // \u{2028} and \u{2029} are encoded as E2 80 A8/A9
+            0xE2 if bytes.get(i + 1) == Some(&0x80)
+                && matches!(bytes.get(i + 2), Some(0xA8) | Some(0xA9)) =>
+            {
+                count += 1;
+                i += 2;
+                line_start = i + 1;
+            }
+            _ => (),
+        }
+        i += 1;
+    }
+    (count, line_start)
+}
+
+/// The length of the string literal at the start of `rest`,
+/// up to and including its closing `quote` or up to the line
+/// terminator that ends it early
+fn string_len(rest: &str, quote: char) -> usize {
+    let mut chars = rest.char_indices().skip(1);
+    while let Some((i, c)) = chars.next() {
+        if c == '\\' {
+            // a `\r\n` continues the string as one line break
+            if let Some((_, '\r')) = chars.next() {
+                if rest[i + 2..].starts_with('\n') {
+                    chars.next();
+                }
+            }
+        } else if c == quote {
+            return i + 1;
+        } else if crate::is_line_term(c) {
+            return i;
+        }
+    }
+    rest.len()
+}
+
+/// Remove a string or template's 1 byte opening delimiter
+/// and `close_len` byte closing delimiter, unless this token
+/// is `continued` from or left `open` for another text
+fn trim_delimiters(s: &str, continued: bool, open: bool, close_len: usize) -> &str {
+    let start = if continued { 0 } else { 1 };
+    let end = if open { s.len() } else { s.len() - close_len };
+    &s[start..end]
+}
+
 #[cfg(test)]
 mod test {
     use crate::{Position, SourceLocation};
@@ -527,7 +740,7 @@ mod test {
             },
         ];
         let mut s = ManualScanner::new(js);
-        let iter = std::iter::from_fn(move || s.next_token());
+        let iter = core::iter::from_fn(move || s.next_token());
         for (i, (item, expected)) in iter.zip(expected.iter()).enumerate() {
             let item = item.as_ref().unwrap();
             assert_eq!(item, expected, "{}", i)
@@ -540,7 +753,7 @@ mod test {
             Item {
                 location: SourceLocation {
                     start: Position { line: 1, column: 1 },
-                    end: Position { line: 2, column: 2 },
+                    end: Position { line: 2, column: 3 },
                 },
                 token: Token::Template(Template::Head(TemplateLiteral {
                     content: "asdf\n",
@@ -554,14 +767,14 @@ mod test {
                 token: Token::Number("0".into()),
                 span: Span { start: 8, end: 9 },
                 location: SourceLocation {
-                    start: Position { line: 2, column: 2 },
-                    end: Position { line: 2, column: 3 },
+                    start: Position { line: 2, column: 3 },
+                    end: Position { line: 2, column: 4 },
                 },
             },
             Item {
                 location: SourceLocation {
-                    start: Position { line: 2, column: 3 },
-                    end: Position { line: 2, column: 5 },
+                    start: Position { line: 2, column: 4 },
+                    end: Position { line: 2, column: 6 },
                 },
                 token: Token::Template(Template::Tail(TemplateLiteral {
                     content: "",
@@ -573,7 +786,7 @@ mod test {
             },
         ];
         let mut s = ManualScanner::new(js);
-        let iter = std::iter::from_fn(move || s.next_token());
+        let iter = core::iter::from_fn(move || s.next_token());
         for (i, (item, expected)) in iter.zip(expected.iter()).enumerate() {
             let item = item.as_ref().unwrap();
             assert_eq!(item, expected, "{}", i)
@@ -594,7 +807,7 @@ mod test {
             span: Span { start: 0, end: 8 },
         }];
         let mut s = ManualScanner::new(js);
-        let iter = std::iter::from_fn(move || {
+        let iter = core::iter::from_fn(move || {
             let _ = s.next_token().unwrap();
             s.next_regex(1)
         });
@@ -603,4 +816,17 @@ mod test {
             assert_eq!(item, expected, "{}", i)
         }
     }
+
+    #[test]
+    fn line_index_on_demand() {
+        let mut scanner = ManualScanner::without_locations("a\n b /c/;\n'");
+        while let Some(Ok(_)) = scanner.next_token() {}
+        // the unterminated string built the index once
+        let index = scanner.lines.get().unwrap() as *const LineIndex;
+        assert_eq!(scanner.position_for(3), (2, 2));
+        assert_eq!(scanner.line_index() as *const LineIndex, index);
+        let mut scanner = ManualScanner::new("a\n b");
+        while scanner.next_token().is_some() {}
+        assert!(scanner.lines.get().is_none());
+    }
 }
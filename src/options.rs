@@ -0,0 +1,495 @@
+use alloc::boxed::Box;
+use core::fmt;
+
+use crate::{CommentAttacher, EsVersion, LexState, Limits, RegexDecision, Scanner, Unsupported};
+
+/// What a scanner does with the comments it finds
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Comments {
+    /// Return each comment as a `Token::Comment`
+    #[default]
+    Emit,
+    /// Scan comments without returning them
+    Skip,
+}
+
+/// The TC39 proposals whose syntax gets its own `Punct`
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub(crate) struct Proposals {
+    pub(crate) pipeline: bool,
+    pub(crate) bind: bool,
+    pub(crate) records_and_tuples: bool,
+    pub(crate) topic_token: bool,
+}
+
+/// Every option for building a `Scanner` or `ManualScanner`,
+/// each defaulting to how `Scanner::new` behaves
+///
+/// ```
+/// use ress::{prelude::*, Comments, ScannerOptions};
+/// let js = "#!/usr/bin/env node\nlet a; // a";
+/// let options = ScannerOptions::new().comments(Comments::Skip).locations(false);
+/// let tokens: Vec<_> = Scanner::with_options(js, options)
+///     .map(|i| i.unwrap().token)
+///     .collect();
+/// assert_eq!(
+///     tokens,
+///     [
+///         Token::Keyword(Keyword::Let("let")),
+///         Token::Ident("a".into()),
+///         Token::Punct(Punct::SemiColon),
+///         Token::EoF,
+///     ]
+/// );
+/// ```
+pub struct ScannerOptions {
+    pub(crate) comments: Comments,
+    pub(crate) html_comments: bool,
+    pub(crate) hashbang: bool,
+    pub(crate) locations: bool,
+    pub(crate) recover: bool,
+    pub(crate) limits: Option<Limits>,
+    pub(crate) regex_trace: Option<Box<dyn FnMut(RegexDecision) + Send>>,
+    pub(crate) resume: Option<LexState>,
+    pub(crate) target: EsVersion,
+    pub(crate) on_unsupported: Option<Box<dyn FnMut(Unsupported) + Send>>,
+    pub(crate) proposals: Proposals,
+}
+
+impl Default for ScannerOptions {
+    fn default() -> Self {
+        Self {
+            comments: Comments::Emit,
+            html_comments: true,
+            hashbang: true,
+            locations: true,
+            recover: false,
+            limits: None,
+            regex_trace: None,
+            resume: None,
+            target: EsVersion::Latest,
+            on_unsupported: None,
+            proposals: Proposals::default(),
+        }
+    }
+}
+
+// `limits`, `regex_trace` and `on_unsupported` hold callbacks
+// that can't be printed
+impl fmt::Debug for ScannerOptions {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("ScannerOptions")
+            .field("comments", &self.comments)
+            .field("html_comments", &self.html_comments)
+            .field("hashbang", &self.hashbang)
+            .field("locations", &self.locations)
+            .field("recover", &self.recover)
+            .field("resume", &self.resume)
+            .field("target", &self.target)
+            .field("proposals", &self.proposals)
+            .finish_non_exhaustive()
+    }
+}
+
+impl ScannerOptions {
+    /// Create a new `ScannerOptions` with every option
+    /// set to its default
+    pub fn new() -> Self {
+        Self::default()
+    }
+    /// What to do with comments, `Comments::Emit` by default
+    ///
+    /// To attach comments to the tokens around them,
+    /// see `attach_comments`.
+    pub fn comments(mut self, comments: Comments) -> Self {
+        self.comments = comments;
+        self
+    }
+    /// Create a `CommentAttacher` over `text` with these
+    /// options, comments are always emitted for it to attach
+    ///
+    /// ```
+    /// use ress::{prelude::*, ScannerOptions};
+    /// let js = "let a; // a\n`b`";
+    /// let items: Vec<_> = ScannerOptions::new()
+    ///     .locations(false)
+    ///     .attach_comments(js)
+    ///     .map(|i| i.unwrap())
+    ///     .collect();
+    /// assert_eq!(items[2].item.token, Token::Punct(Punct::SemiColon));
+    /// assert_eq!(items[2].trailing_comments.len(), 1);
+    /// assert!(items[3].item.token.is_template());
+    /// ```
+    pub fn attach_comments(mut self, text: &str) -> CommentAttacher<'_> {
+        self.comments = Comments::Emit;
+        Scanner::with_options(text, self).into()
+    }
+    /// If `<!--` and `-->` start comments, on by default
+    ///
+    /// These comments are only allowed in scripts, turn
+    /// this off for modules.
+    pub fn html_comments(mut self, allow: bool) -> Self {
+        self.html_comments = allow;
+        self
+    }
+    /// If a `#!` at the very start of the text is a comment,
+    /// on by default
+    pub fn hashbang(mut self, allow: bool) -> Self {
+        self.hashbang = allow;
+        self
+    }
+    /// If each `Item` should have its line/column `location`,
+    /// on by default
+    ///
+    /// See `Scanner::without_locations`
+    pub fn locations(mut self, track: bool) -> Self {
+        self.locations = track;
+        self
+    }
+    /// If scanning should continue past a syntax error,
+    /// off by default
+    ///
+    /// The character the error was found at is skipped and
+    /// scanning picks back up after it, except for a broken
+    /// string or regex which is skipped up to the end of its
+    /// line, or to a string's closing quote. Errors for
+    /// exceeding `Limits` always stop a `Scanner`.
+    ///
+    /// ```
+    /// use ress::{ScannerOptions, Scanner};
+    /// let js = "let a = 1;\nlet \\b = 2;\nlet c = 3;";
+    /// let options = ScannerOptions::new().recover(true);
+    /// let results: Vec<_> = Scanner::with_options(js, options).collect();
+    /// assert_eq!(results.iter().filter(|i| i.is_err()).count(), 1);
+    /// assert!(results.last().unwrap().as_ref().unwrap().is_eof());
+    /// ```
+    pub fn recover(mut self, recover: bool) -> Self {
+        self.recover = recover;
+        self
+    }
+    /// Stop a `Scanner` with an error once any of the `limits`
+    /// are exceeded, see `Scanner::with_limits`
+    pub fn limits(mut self, limits: Limits) -> Self {
+        self.limits = Some(limits);
+        self
+    }
+    /// Call `trace` with the `RegexDecision` a `Scanner` makes
+    /// for every `/` and `/=`, see `Scanner::with_regex_trace`
+    pub fn regex_trace(mut self, trace: impl FnMut(RegexDecision) + Send + 'static) -> Self {
+        self.regex_trace = Some(Box::new(trace));
+        self
+    }
+    /// Start a `Scanner` from the `LexState` at the end of the
+    /// text before it, see `Scanner::resume`
+    pub fn resume(mut self, state: &LexState) -> Self {
+        self.resume = Some(state.clone());
+        self
+    }
+    /// The `EsVersion` the text should be limited to,
+    /// `EsVersion::Latest` by default
+    ///
+    /// A `Scanner` stops with an `ErrorKind::Unsupported` error at
+    /// the first token using a newer `Feature`, unless
+    /// `on_unsupported` is set. Targeting `EsVersion::Es3` also
+    /// scans the words only ES3 reserves, like `int` and `goto`,
+    /// as `Keyword::Es3Reserved`.
+    ///
+    /// ```
+    /// use ress::{error::ErrorKind, EsVersion, Feature, Scanner, ScannerOptions};
+    /// let options = ScannerOptions::new().target(EsVersion::Es5);
+    /// let err = Scanner::with_options("var s = `a`;", options)
+    ///     .find_map(|i| i.err())
+    ///     .unwrap();
+    /// assert_eq!(err.kind, ErrorKind::Unsupported(Feature::Template));
+    /// assert_eq!(err.to_string(), "template literal requires ES2015 at 1:9");
+    /// ```
+    pub fn target(mut self, version: EsVersion) -> Self {
+        self.target = version;
+        self
+    }
+    /// Call `report` with each token newer than the `target`
+    /// instead of stopping with an error
+    pub fn on_unsupported(mut self, report: impl FnMut(Unsupported) + Send + 'static) -> Self {
+        self.on_unsupported = Some(Box::new(report));
+        self
+    }
+    /// If `|>` from the pipeline proposal is scanned as
+    /// `Punct::PipeGreaterThan`, off by default
+    ///
+    /// ```
+    /// use ress::{prelude::*, ScannerOptions};
+    /// let options = ScannerOptions::new().pipeline(true).topic_token(true);
+    /// let tokens: Vec<_> = Scanner::with_options("a |> f(@@) / 2", options)
+    ///     .map(|i| i.unwrap().token)
+    ///     .collect();
+    /// assert_eq!(tokens[1], Token::Punct(Punct::PipeGreaterThan));
+    /// assert_eq!(tokens[4], Token::Punct(Punct::DoubleAtMark));
+    /// assert_eq!(tokens[6], Token::Punct(Punct::ForwardSlash));
+    /// ```
+    pub fn pipeline(mut self, allow: bool) -> Self {
+        self.proposals.pipeline = allow;
+        self
+    }
+    /// If `::` from the bind proposal is scanned as
+    /// `Punct::DoubleColon`, off by default
+    pub fn bind(mut self, allow: bool) -> Self {
+        self.proposals.bind = allow;
+        self
+    }
+    /// If `#{` and `#[` from the records and tuples proposal are
+    /// scanned as `Punct::HashOpenBrace` and `Punct::HashOpenBracket`,
+    /// off by default
+    ///
+    /// These are closed by a plain `}` or `]`.
+    pub fn records_and_tuples(mut self, allow: bool) -> Self {
+        self.proposals.records_and_tuples = allow;
+        self
+    }
+    /// If the `@@` topic reference for pipelines is scanned
+    /// as `Punct::DoubleAtMark`, off by default
+    pub fn topic_token(mut self, allow: bool) -> Self {
+        self.proposals.topic_token = allow;
+        self
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{prelude::*, LineIndex, ManualScanner};
+
+    fn tokens(js: &str, options: ScannerOptions) -> Vec<Token<&str>> {
+        Scanner::with_options(js, options)
+            .map(|i| i.unwrap().token)
+            .collect()
+    }
+
+    #[test]
+    fn defaults_match_new() {
+        let js = "#!node\n<!-- a\n/* b */ --> c\nlet x = /d/; // e";
+        let expected: Vec<_> = Scanner::new(js).collect();
+        let found: Vec<_> = Scanner::with_options(js, ScannerOptions::new()).collect();
+        assert_eq!(found, expected);
+        let mut manual = ManualScanner::with_options(js, ScannerOptions::new());
+        let mut expected = ManualScanner::new(js);
+        while let Some(item) = expected.next_token() {
+            assert_eq!(manual.next_token(), Some(item));
+        }
+        assert_eq!(manual.next_token(), None);
+    }
+
+    #[test]
+    fn debug() {
+        let options = ScannerOptions::new()
+            .target(EsVersion::Es5)
+            .on_unsupported(drop);
+        let printed = format!("{:?}", options);
+        assert!(printed.starts_with("ScannerOptions { comments: Emit,"));
+        assert!(printed.contains("target: Es5"), "{}", printed);
+        assert!(printed.ends_with(", .. }"), "{}", printed);
+    }
+
+    #[test]
+    fn skip_comments() {
+        let js = "/* a */ b = // c\n/d/";
+        let options = ScannerOptions::new().comments(Comments::Skip);
+        assert_eq!(
+            tokens(js, options),
+            [
+                Token::Ident("b".into()),
+                Token::Punct(Punct::Equal),
+                Token::RegEx(RegEx::from_parts("d", None)),
+                Token::EoF
+            ]
+        );
+        let mut manual =
+            ManualScanner::with_options(js, ScannerOptions::new().comments(Comments::Skip));
+        let b = manual.next_token().unwrap().unwrap();
+        assert_eq!(b.token, Token::Ident("b".into()));
+    }
+
+    #[test]
+    fn html_comments() {
+        let js = "a <!-- b\n--> c";
+        let options = ScannerOptions::new().html_comments(false);
+        assert_eq!(
+            tokens(js, options),
+            [
+                Token::Ident("a".into()),
+                Token::Punct(Punct::LessThan),
+                Token::Punct(Punct::Bang),
+                Token::Punct(Punct::DoubleDash),
+                Token::Ident("b".into()),
+                Token::Punct(Punct::DoubleDash),
+                Token::Punct(Punct::GreaterThan),
+                Token::Ident("c".into()),
+                Token::EoF
+            ]
+        );
+        let js = "/*\n*/-->a";
+        let options = ScannerOptions::new().html_comments(false);
+        assert_eq!(tokens(js, options).len(), 5);
+    }
+
+    #[test]
+    fn hashbang() {
+        let options = ScannerOptions::new().hashbang(false);
+        assert_eq!(
+            tokens("#!a", options),
+            [
+                Token::Punct(Punct::Hash),
+                Token::Punct(Punct::Bang),
+                Token::Ident("a".into()),
+                Token::EoF
+            ]
+        );
+    }
+
+    #[test]
+    fn recover() {
+        let js = "a \\ b\n'c\nd";
+        let options = ScannerOptions::new().recover(true);
+        let items: Vec<_> = Scanner::with_options(js, options).collect();
+        let errors: Vec<_> = items
+            .iter()
+            .filter_map(|i| i.as_ref().err())
+            .map(|e| e.idx)
+            .collect();
+        assert_eq!(errors.len(), 2);
+        let found: Vec<_> = items
+            .into_iter()
+            .filter_map(Result::ok)
+            .map(|i| (i.token, i.location.start))
+            .collect();
+        assert_eq!(
+            found,
+            [
+                (Token::Ident("a".into()), Position::new(1, 1)),
+                (Token::Ident("b".into()), Position::new(1, 5)),
+                (Token::Ident("d".into()), Position::new(3, 1)),
+                (Token::EoF, Position::new(3, 2)),
+            ]
+        );
+        // without it the first error is the last item
+        let items: Vec<_> = Scanner::new(js).collect();
+        assert!(items.last().unwrap().is_err());
+    }
+
+    #[test]
+    fn recover_delimiters() {
+        let js = "a ) b } c";
+        let options = ScannerOptions::new().recover(true);
+        let items: Vec<_> = Scanner::with_options(js, options)
+            .map(|i| i.map(|i| i.token))
+            .collect();
+        assert_eq!(items.len(), 6);
+        assert!(items[1].is_err() && items[3].is_err());
+        assert_eq!(items[4], Ok(Token::Ident("c".into())));
+        let items: Vec<_> = Scanner::new(js).collect();
+        assert_eq!(items.len(), 2);
+        assert!(items[1].is_err());
+    }
+
+    #[test]
+    fn recover_regex() {
+        let options = ScannerOptions::new().recover(true);
+        let items: Vec<_> = Scanner::with_options("x = /a\ny", options).collect();
+        assert!(items[2].is_err());
+        let rest: Vec<_> = items[3..]
+            .iter()
+            .map(|i| i.as_ref().unwrap().token.clone())
+            .collect();
+        assert_eq!(rest, [Token::Ident("y".into()), Token::EoF]);
+    }
+
+    #[test]
+    fn recover_literals() {
+        // the punctuation inside each broken literal
+        // should not be scanned as tokens
+        let js = "a = 'b) {c\nx = \"\\u{zz} (\" + d\ny = /[e}/ (\nz = '\\\r\n) f\ng";
+        for locations in [true, false] {
+            let options = ScannerOptions::new().recover(true).locations(locations);
+            let items: Vec<_> = Scanner::with_options(js, options).collect();
+            let errors = items.iter().filter(|i| i.is_err()).count();
+            assert_eq!(errors, 4, "{}", js);
+            let found: Vec<_> = items
+                .into_iter()
+                .filter_map(Result::ok)
+                .map(|i| i.token.to_string())
+                .collect();
+            assert_eq!(
+                found,
+                ["a", "=", "x", "=", "+", "d", "y", "=", "z", "=", "g", ""],
+                "{}",
+                locations
+            );
+        }
+        let lines = LineIndex::new(js);
+        let options = ScannerOptions::new().recover(true);
+        for item in Scanner::with_options(js, options).filter_map(Result::ok) {
+            assert_eq!(item.location.start, lines.position(item.span.start));
+        }
+    }
+
+    #[test]
+    fn proposals() {
+        let js = "a |> b::c @@ #{ d: 1 } #[ 2 ]";
+        let all = ScannerOptions::new()
+            .pipeline(true)
+            .bind(true)
+            .records_and_tuples(true)
+            .topic_token(true);
+        let puncts: Vec<_> = tokens(js, all)
+            .into_iter()
+            .filter_map(|t| match t {
+                Token::Punct(p) => Some(p),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(
+            puncts,
+            [
+                Punct::PipeGreaterThan,
+                Punct::DoubleColon,
+                Punct::DoubleAtMark,
+                Punct::HashOpenBrace,
+                Punct::Colon,
+                Punct::CloseBrace,
+                Punct::HashOpenBracket,
+                Punct::CloseBracket,
+            ]
+        );
+        // off by default
+        let puncts = tokens(js, ScannerOptions::new())
+            .into_iter()
+            .filter(|t| t.is_punct())
+            .count();
+        assert_eq!(puncts, 13);
+    }
+
+    #[test]
+    fn proposal_regexes() {
+        let options = || {
+            ScannerOptions::new()
+                .pipeline(true)
+                .bind(true)
+                .records_and_tuples(true)
+                .topic_token(true)
+        };
+        let regexes = |js| {
+            tokens(js, options())
+                .into_iter()
+                .filter(|t| t.is_regex())
+                .count()
+        };
+        assert_eq!(regexes("a |> /re/.test(@@)"), 1);
+        assert_eq!(regexes("a |> @@ / 2 / 1"), 0);
+        assert_eq!(regexes("a::/re/"), 1);
+        assert_eq!(regexes("x = #{ a: /re/ } / 2 / 1"), 1);
+        assert_eq!(regexes("#{ a: 1 } / 2 / 1"), 0);
+        assert_eq!(regexes("#[ /re/ ] / 2 / 1"), 1);
+        assert_eq!(regexes("#[ { a: 1 } / 2 ]"), 0);
+        let trees = crate::TokenTree::build("#{ a: #[1] }");
+        assert!(trees.is_ok());
+    }
+}
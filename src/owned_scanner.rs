@@ -0,0 +1,107 @@
+use alloc::collections::VecDeque;
+use alloc::string::String;
+
+use crate::{error::Error, resume::Resume, Item, Scanner};
+
+type Res<T> = Result<T, Error>;
+
+/// How many items to scan each time the
+/// `Scanner` is picked back up
+const BATCH_SIZE: usize = 256;
+
+/// A scanner that owns its JS text, yielding `Item`s that
+/// own their text as well
+///
+/// The items are exactly those of a `Scanner` over the same
+/// text, but can outlive it or be sent to another thread.
+///
+/// ```
+/// use ress::OwnedScanner;
+/// let scanner = OwnedScanner::new(String::from("let x = 1;"));
+/// let handle = std::thread::spawn(move || scanner.map(|i| i.unwrap()).count());
+/// assert_eq!(handle.join().unwrap(), 6);
+/// ```
+pub struct OwnedScanner {
+    text: String,
+    /// Where the last batch left off
+    resume: Option<Resume>,
+    ready: VecDeque<Res<Item<String>>>,
+    done: bool,
+}
+
+impl OwnedScanner {
+    /// Create a new `OwnedScanner` taking
+    /// ownership of the JS text
+    pub fn new(text: impl Into<String>) -> Self {
+        Self {
+            text: text.into(),
+            resume: None,
+            ready: VecDeque::new(),
+            done: false,
+        }
+    }
+    /// The JS text being scanned
+    pub fn text(&self) -> &str {
+        &self.text
+    }
+
+    fn scan(&mut self) {
+        let mut scanner = Scanner::new(&self.text);
+        if let Some(resume) = self.resume.take() {
+            resume.restore(&mut scanner);
+        }
+        for _ in 0..BATCH_SIZE {
+            match scanner.next() {
+                Some(item) => self.ready.push_back(item.map(Item::into_owned)),
+                None => break,
+            }
+        }
+        self.done = scanner.errored || scanner.manual_scanner.eof;
+        self.resume = Some(Resume::capture(&scanner));
+    }
+}
+
+impl Iterator for OwnedScanner {
+    type Item = Res<Item<String>>;
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.ready.is_empty() && !self.done {
+            self.scan();
+        }
+        self.ready.pop_front()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::{rc::Rc, sync::Arc};
+
+    #[test]
+    fn matches_scanner() {
+        let js = "function thing(a) {\n    return `a${a / 2}b` + /re/g;\n}\n".repeat(100);
+        let expected: Vec<_> = Scanner::new(&js).map(|i| i.map(Item::into_owned)).collect();
+        let items: Vec<_> = OwnedScanner::new(js.clone()).collect();
+        assert!(expected.len() > BATCH_SIZE * 2);
+        assert_eq!(items, expected);
+    }
+
+    #[test]
+    fn stops_after_error() {
+        let js = format!("{}'unterminated\nx", "a ".repeat(BATCH_SIZE - 1));
+        let items: Vec<_> = OwnedScanner::new(js).collect();
+        assert_eq!(items.len(), BATCH_SIZE);
+        assert!(items.last().unwrap().is_err());
+    }
+
+    #[test]
+    fn into_owned() {
+        let mut scanner = Scanner::new("'a' /* b */ `c${");
+        let string: Item<Rc<str>> = scanner.next().unwrap().unwrap().into_owned();
+        assert_eq!(string.token.to_string(), "'a'");
+        let comment: Item<Arc<str>> = scanner.next().unwrap().unwrap().into_owned();
+        assert_eq!(comment.token.to_string(), "/* b */");
+        let template: Item<String> = scanner.next().unwrap().unwrap().into_owned();
+        assert!(template.token.is_template_head());
+        assert_eq!(template.span, crate::Span::new(12, 16));
+    }
+}
@@ -0,0 +1,226 @@
+use alloc::string::ToString;
+use core::fmt;
+
+use crate::{
+    look_behind::{Brace, Paren},
+    tokens::{Keyword, Punct},
+    Position,
+};
+
+/// The choice a `Scanner` made between a regular expression
+/// and division for a single `/` or `/=`
+///
+/// ```
+/// use ress::Scanner;
+/// use std::sync::mpsc::channel;
+/// let (tx, rx) = channel();
+/// let js = "if (a) /b/.test(c)";
+/// Scanner::with_regex_trace(js, move |d| tx.send(d.to_string()).unwrap()).for_each(drop);
+/// assert_eq!(
+///     rx.try_iter().collect::<Vec<_>>(),
+///     ["`/` at 1:8 is a regex: the previous token `)` closes a conditional paren opened at 1:4"],
+/// );
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct RegexDecision {
+    /// The `/` or `/=` being decided on
+    pub punct: Punct,
+    /// The byte index of the `/`
+    pub idx: usize,
+    /// The line/column of the `/`
+    pub position: Position,
+    /// If the `/` was the start of a regular expression
+    pub is_regex: bool,
+    /// Why the decision was made
+    pub rule: RegexRule,
+}
+
+/// The rule deciding if a `/` starts a regular expression,
+/// named for the token before it
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum RegexRule {
+    /// There is no token before the `/`
+    StartOfInput,
+    /// A keyword other than `yield` or `await`
+    Keyword(Keyword<()>),
+    /// `yield`, which is only a keyword in a generator
+    Yield { is_keyword: bool },
+    /// `await`, which is not a keyword in a function
+    /// that isn't async
+    Await { is_keyword: bool },
+    /// `of`, which is only a keyword in the head of a `for`
+    Of { is_keyword: bool },
+    /// Any punctuation other than a delimiter
+    Punct(Punct),
+    /// A `(`
+    OpenParen(Paren),
+    /// A `)` closing this `Paren`
+    CloseParen(Paren),
+    /// A `{`
+    OpenBrace(Brace),
+    /// A `}` closing this `Brace`
+    CloseBrace(Brace),
+    /// A template head or middle, opening a substitution
+    OpenTemplate,
+    /// An identifier or literal
+    Operand,
+}
+
+impl RegexRule {
+    /// If a `/` after this rule's token starts a regular expression
+    pub fn is_regex(&self) -> bool {
+        match self {
+            RegexRule::StartOfInput
+            | RegexRule::OpenParen(_)
+            | RegexRule::OpenBrace(_)
+            | RegexRule::OpenTemplate => true,
+            RegexRule::Keyword(k) => !matches!(k, Keyword::This(_) | Keyword::Super(_)),
+            RegexRule::Yield { is_keyword }
+            | RegexRule::Await { is_keyword }
+            | RegexRule::Of { is_keyword } => *is_keyword,
+            RegexRule::Punct(p) => *p != Punct::CloseBracket,
+            RegexRule::CloseParen(open) => open.conditional,
+            RegexRule::CloseBrace(open) => match &open.paren {
+                Some(paren) if open.is_block => !paren.func_expr,
+                _ => open.is_block,
+            },
+            RegexRule::Operand => false,
+        }
+    }
+}
+
+impl fmt::Display for RegexDecision {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let kind = if self.is_regex { "a regex" } else { "division" };
+        write!(
+            f,
+            "`{}` at {} is {}: {}",
+            self.punct.to_string(),
+            self.position,
+            kind,
+            self.rule
+        )
+    }
+}
+
+impl fmt::Display for RegexRule {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let not = |is_keyword: &bool| if *is_keyword { "" } else { "not " };
+        match self {
+            RegexRule::StartOfInput => write!(f, "there is no previous token"),
+            RegexRule::Keyword(k) => {
+                write!(f, "the previous token is the keyword `{}`", k.as_str())
+            }
+            RegexRule::Yield { is_keyword } => write!(
+                f,
+                "the previous token `yield` is {}a keyword in this function",
+                not(is_keyword)
+            ),
+            RegexRule::Await { is_keyword } => write!(
+                f,
+                "the previous token `await` is {}a keyword in this function",
+                not(is_keyword)
+            ),
+            RegexRule::Of { is_keyword } => write!(
+                f,
+                "the previous token `of` is {}a keyword in a `for` head",
+                not(is_keyword)
+            ),
+            RegexRule::Punct(p) => write!(f, "the previous token is `{}`", p.to_string()),
+            RegexRule::OpenParen(_) => write!(f, "the previous token is `(`"),
+            RegexRule::CloseParen(open) => {
+                let kind = if open.conditional {
+                    "a conditional paren"
+                } else if open.func_expr {
+                    "the parameters of a function expression"
+                } else {
+                    "a paren"
+                };
+                write!(
+                    f,
+                    "the previous token `)` closes {} opened at {}",
+                    kind, open.position
+                )
+            }
+            RegexRule::OpenBrace(_) => write!(f, "the previous token is `{{`"),
+            RegexRule::CloseBrace(open) => {
+                let kind = match &open.paren {
+                    Some(paren) if open.is_block && paren.func_expr => {
+                        "the body of a function expression"
+                    }
+                    _ if open.is_block => "a block",
+                    _ => "an expression",
+                };
+                write!(
+                    f,
+                    "the previous token `}}` closes {} opened at {}",
+                    kind, open.position
+                )
+            }
+            RegexRule::OpenTemplate => {
+                write!(f, "the previous token opens a template substitution")
+            }
+            RegexRule::Operand => write!(f, "the previous token is an identifier or literal"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::Scanner;
+    use std::sync::{Arc, Mutex};
+
+    fn trace(js: &str) -> Vec<String> {
+        let decisions = Arc::new(Mutex::new(Vec::new()));
+        let sink = Arc::clone(&decisions);
+        Scanner::with_regex_trace(js, move |d| sink.lock().unwrap().push(d.to_string()))
+            .for_each(drop);
+        let decisions = decisions.lock().unwrap();
+        decisions.clone()
+    }
+
+    #[test]
+    fn decisions() {
+        assert_eq!(
+            trace("a / b /= c"),
+            [
+                "`/` at 1:3 is division: the previous token is an identifier or literal",
+                "`/=` at 1:7 is division: the previous token is an identifier or literal",
+            ]
+        );
+        assert_eq!(
+            trace("x = function() {\n} / 2"),
+            ["`/` at 2:3 is division: the previous token `}` closes the body of a function expression opened at 1:16"]
+        );
+        assert_eq!(
+            trace("{}\n/a/"),
+            ["`/` at 2:1 is a regex: the previous token `}` closes a block opened at 1:1"]
+        );
+        assert_eq!(
+            trace("function f() { var yield; yield /a/ }"),
+            [
+                "`/` at 1:33 is division: the previous token `yield` is not a keyword in this function",
+                "`/` at 1:35 is division: the previous token is an identifier or literal",
+            ]
+        );
+        assert_eq!(
+            trace("/a/; typeof /b/"),
+            [
+                "`/` at 1:1 is a regex: there is no previous token",
+                "`/` at 1:13 is a regex: the previous token is the keyword `typeof`",
+            ]
+        );
+    }
+
+    #[test]
+    fn look_ahead_is_not_traced() {
+        let count = Arc::new(Mutex::new(0));
+        let sink = Arc::clone(&count);
+        let mut scanner = Scanner::with_regex_trace("a / b", move |_| *sink.lock().unwrap() += 1);
+        let _ = scanner.next();
+        let _ = scanner.look_ahead();
+        let _ = scanner.look_ahead();
+        scanner.for_each(drop);
+        assert_eq!(*count.lock().unwrap(), 1);
+    }
+}
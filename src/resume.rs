@@ -0,0 +1,47 @@
+use alloc::vec::Vec;
+
+use crate::{
+    look_behind::RegexDetector, manual_scanner::ScannerState as ManualState, OpenCurlyKind, Scanner,
+};
+
+/// Everything needed to pick a `Scanner` back up
+/// at the same place in a new window of text
+#[derive(Clone)]
+pub(crate) struct Resume {
+    pub(crate) manual_state: ManualState,
+    regex_detector: RegexDetector,
+    curly_stack: Vec<OpenCurlyKind>,
+}
+
+impl Resume {
+    pub(crate) fn capture(scanner: &Scanner) -> Self {
+        Self {
+            manual_state: scanner.manual_scanner.get_state(),
+            regex_detector: scanner.regex_detector.clone(),
+            curly_stack: scanner.manual_scanner.stream.curly_stack.clone(),
+        }
+    }
+
+    pub(crate) fn restore(self, scanner: &mut Scanner) {
+        scanner.manual_scanner.set_state(self.manual_state);
+        scanner.regex_detector = self.regex_detector;
+        scanner.manual_scanner.stream.curly_stack = self.curly_stack;
+    }
+
+    /// Move every byte index back by `consumed`, delimiters opened
+    /// before the new window will wrap, which is undone when
+    /// `StreamScanner::shift_span` adds the window's offset back
+    #[cfg(feature = "std")]
+    pub(crate) fn shift(&mut self, consumed: usize) {
+        self.manual_state.cursor -= consumed;
+        for paren in self.regex_detector.paren_stack.iter_mut() {
+            paren.start = paren.start.wrapping_sub(consumed);
+        }
+        for brace in self.regex_detector.brace_stack.iter_mut() {
+            brace.start = brace.start.wrapping_sub(consumed);
+            if let Some(paren) = &mut brace.paren {
+                paren.start = paren.start.wrapping_sub(consumed);
+            }
+        }
+    }
+}
@@ -0,0 +1,315 @@
+use alloc::{borrow::Cow, string::String, vec::Vec};
+use core::fmt;
+
+use crate::{
+    error::{Error, ErrorKind},
+    Span,
+};
+
+type Res<T> = Result<T, Error>;
+
+const UTF8_BOM: &[u8] = b"\xEF\xBB\xBF";
+const UTF16_LE_BOM: &[u8] = b"\xFF\xFE";
+const UTF16_BE_BOM: &[u8] = b"\xFE\xFF";
+
+/// The encoding `SourceText` found its bytes in
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Encoding {
+    Utf8,
+    Utf16Le,
+    Utf16Be,
+    /// ISO-8859-1, assumed for bytes that aren't
+    /// valid in any other encoding
+    Latin1,
+}
+
+impl Encoding {
+    /// How many bytes an ASCII character takes up
+    fn ascii_width(self) -> usize {
+        match self {
+            Encoding::Utf16Le | Encoding::Utf16Be => 2,
+            Encoding::Utf8 | Encoding::Latin1 => 1,
+        }
+    }
+    /// How many bytes `c` takes up
+    fn width(self, c: char) -> usize {
+        match self {
+            Encoding::Utf8 => c.len_utf8(),
+            Encoding::Utf16Le | Encoding::Utf16Be => c.len_utf16() * 2,
+            Encoding::Latin1 => 1,
+        }
+    }
+}
+
+impl fmt::Display for Encoding {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Encoding::Utf8 => write!(f, "UTF-8"),
+            Encoding::Utf16Le => write!(f, "UTF-16LE"),
+            Encoding::Utf16Be => write!(f, "UTF-16BE"),
+            Encoding::Latin1 => write!(f, "ISO-8859-1"),
+        }
+    }
+}
+
+/// JS text decoded from raw bytes, see `Scanner::from_bytes`
+///
+/// A byte order mark picks the encoding when there is one
+/// and is dropped from the text. Without one, bytes that are
+/// valid UTF-8 are used as is, ASCII text with every other
+/// byte zero is read as UTF-16 and anything else as Latin-1.
+///
+/// ```
+/// use ress::{Encoding, Scanner, SourceText};
+/// let bytes: Vec<u8> = b"\xFF\xFEl\0e\0t\0 \0\xE9\0;\0".to_vec();
+/// let source = SourceText::from_bytes(&bytes).unwrap();
+/// assert_eq!(source.encoding(), Encoding::Utf16Le);
+/// assert!(source.has_bom());
+/// assert_eq!(source.text(), "let é;");
+/// let ident = Scanner::from_bytes(&source).nth(1).unwrap().unwrap();
+/// assert_eq!(&source.text()[ident.span.start..ident.span.end], "é");
+/// let original = source.original_span(ident.span);
+/// assert_eq!(&bytes[original.start..original.end], b"\xE9\0");
+/// ```
+#[derive(Clone, Debug)]
+pub struct SourceText<'a> {
+    text: Cow<'a, str>,
+    encoding: Encoding,
+    bom_len: usize,
+    /// The index in `text` and in the original bytes just
+    /// after each character whose width differs from
+    /// that of an ASCII character
+    offsets: Vec<(usize, usize)>,
+}
+
+impl<'a> SourceText<'a> {
+    /// Detect the encoding of `bytes` and decode them,
+    /// borrowing them when they are UTF-8
+    pub fn from_bytes(bytes: &'a [u8]) -> Res<Self> {
+        let (encoding, bom_len) = if bytes.starts_with(UTF8_BOM) {
+            (Encoding::Utf8, UTF8_BOM.len())
+        } else if bytes.starts_with(UTF16_LE_BOM) {
+            (Encoding::Utf16Le, UTF16_LE_BOM.len())
+        } else if bytes.starts_with(UTF16_BE_BOM) {
+            (Encoding::Utf16Be, UTF16_BE_BOM.len())
+        } else {
+            (detect(bytes), 0)
+        };
+        let body = &bytes[bom_len..];
+        let text = match encoding {
+            Encoding::Utf8 => Cow::Borrowed(core::str::from_utf8(body).map_err(|e| {
+                input_error(
+                    format!("invalid UTF-8 at byte {}", bom_len + e.valid_up_to()),
+                    bom_len + e.valid_up_to(),
+                )
+            })?),
+            Encoding::Utf16Le | Encoding::Utf16Be => {
+                Cow::Owned(decode_utf16(body, encoding, bom_len)?)
+            }
+            Encoding::Latin1 => Cow::Owned(body.iter().map(|&b| b as char).collect()),
+        };
+        let mut offsets = Vec::new();
+        let mut original = bom_len;
+        for (idx, c) in text.char_indices() {
+            let width = encoding.width(c);
+            original += width;
+            if width != c.len_utf8() * encoding.ascii_width() {
+                offsets.push((idx + c.len_utf8(), original));
+            }
+        }
+        Ok(Self {
+            text,
+            encoding,
+            bom_len,
+            offsets,
+        })
+    }
+    /// The decoded text, without any byte order mark
+    pub fn text(&self) -> &str {
+        &self.text
+    }
+    /// The encoding the bytes were decoded from
+    pub fn encoding(&self) -> Encoding {
+        self.encoding
+    }
+    /// If the bytes started with a byte order mark
+    pub fn has_bom(&self) -> bool {
+        self.bom_len > 0
+    }
+    /// The offset in the original bytes of the
+    /// byte index `idx` of `text`
+    pub fn original_offset(&self, idx: usize) -> usize {
+        let width = self.encoding.ascii_width();
+        let (text_idx, original) = match self.offsets.partition_point(|(i, _)| *i <= idx) {
+            0 => (0, self.bom_len),
+            n => self.offsets[n - 1],
+        };
+        original + (idx - text_idx) * width
+    }
+    /// Map a `Span` of `text` back to the original bytes
+    pub fn original_span(&self, span: Span) -> Span {
+        Span::new(
+            self.original_offset(span.start),
+            self.original_offset(span.end),
+        )
+    }
+}
+
+/// Guess the encoding of `bytes` that have no byte order mark
+fn detect(bytes: &[u8]) -> Encoding {
+    if core::str::from_utf8(bytes).is_ok() && !bytes.contains(&0) {
+        return Encoding::Utf8;
+    }
+    match bytes {
+        [a, 0, ..] if *a != 0 && bytes.chunks_exact(2).remainder().is_empty() => Encoding::Utf16Le,
+        [0, b, ..] if *b != 0 && bytes.chunks_exact(2).remainder().is_empty() => Encoding::Utf16Be,
+        _ if core::str::from_utf8(bytes).is_ok() => Encoding::Utf8,
+        _ => Encoding::Latin1,
+    }
+}
+
+fn decode_utf16(bytes: &[u8], encoding: Encoding, bom_len: usize) -> Res<String> {
+    let pairs = bytes.chunks_exact(2);
+    if !pairs.remainder().is_empty() {
+        return Err(input_error(
+            "UTF-16 text with an odd number of bytes".into(),
+            bom_len + bytes.len(),
+        ));
+    }
+    let units = pairs.map(|pair| match encoding {
+        Encoding::Utf16Be => u16::from_be_bytes([pair[0], pair[1]]),
+        _ => u16::from_le_bytes([pair[0], pair[1]]),
+    });
+    let mut ret = String::with_capacity(bytes.len() / 2);
+    let mut idx = bom_len;
+    for c in char::decode_utf16(units) {
+        let c = c.map_err(|e| {
+            input_error(
+                format!(
+                    "unpaired surrogate {:#x} at byte {}",
+                    e.unpaired_surrogate(),
+                    idx
+                ),
+                idx,
+            )
+        })?;
+        idx += c.len_utf16() * 2;
+        ret.push(c);
+    }
+    Ok(ret)
+}
+
+fn input_error(msg: String, idx: usize) -> Error {
+    Error {
+        line: 0,
+        column: 0,
+        msg,
+        idx,
+        kind: ErrorKind::Input,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::Scanner;
+
+    fn utf16(s: &str, big_endian: bool) -> Vec<u8> {
+        s.encode_utf16()
+            .flat_map(|u| {
+                if big_endian {
+                    u.to_be_bytes()
+                } else {
+                    u.to_le_bytes()
+                }
+            })
+            .collect()
+    }
+
+    /// Every span of `bytes` maps back to the same text
+    fn check_spans(bytes: &[u8], expected: Encoding, decode: impl Fn(&[u8]) -> String) {
+        let source = SourceText::from_bytes(bytes).unwrap();
+        assert_eq!(source.encoding(), expected);
+        for item in Scanner::from_bytes(&source) {
+            let item = item.unwrap();
+            let original = source.original_span(item.span);
+            assert_eq!(
+                decode(&bytes[original.start..original.end]),
+                &source.text()[item.span.start..item.span.end]
+            );
+        }
+    }
+
+    const JS: &str = "let café = '日本' + \"𝒜\"; // ü";
+
+    #[test]
+    fn utf8() {
+        let source = SourceText::from_bytes(JS.as_bytes()).unwrap();
+        assert!(matches!(source.text, Cow::Borrowed(_)));
+        assert!(!source.has_bom());
+        let with_bom = [UTF8_BOM, JS.as_bytes()].concat();
+        let source = SourceText::from_bytes(&with_bom).unwrap();
+        assert!(source.has_bom());
+        assert_eq!(source.text(), JS);
+        check_spans(&with_bom, Encoding::Utf8, |b| {
+            String::from_utf8(b.to_vec()).unwrap()
+        });
+    }
+
+    #[test]
+    fn utf16_le_and_be() {
+        let decode_le = |b: &[u8]| {
+            let units: Vec<u16> = b
+                .chunks(2)
+                .map(|p| u16::from_le_bytes([p[0], p[1]]))
+                .collect();
+            String::from_utf16(&units).unwrap()
+        };
+        let decode_be = |b: &[u8]| {
+            let units: Vec<u16> = b
+                .chunks(2)
+                .map(|p| u16::from_be_bytes([p[0], p[1]]))
+                .collect();
+            String::from_utf16(&units).unwrap()
+        };
+        let le = [UTF16_LE_BOM, &utf16(JS, false)].concat();
+        check_spans(&le, Encoding::Utf16Le, decode_le);
+        let be = [UTF16_BE_BOM, &utf16(JS, true)].concat();
+        check_spans(&be, Encoding::Utf16Be, decode_be);
+        // without a byte order mark
+        check_spans(&utf16(JS, false), Encoding::Utf16Le, decode_le);
+        check_spans(&utf16(JS, true), Encoding::Utf16Be, decode_be);
+    }
+
+    #[test]
+    fn latin1() {
+        let bytes = b"var s = '\xE9t\xE9'; // \xA9";
+        let source = SourceText::from_bytes(bytes).unwrap();
+        assert_eq!(source.text(), "var s = 'été'; // ©");
+        check_spans(bytes, Encoding::Latin1, |b| {
+            b.iter().map(|&b| b as char).collect()
+        });
+    }
+
+    #[test]
+    fn invalid() {
+        let err = SourceText::from_bytes(b"\xEF\xBB\xBFa\xFF").unwrap_err();
+        assert_eq!((err.kind, err.idx), (ErrorKind::Input, 4));
+        let err = SourceText::from_bytes(b"\xFF\xFEa\0b").unwrap_err();
+        assert_eq!(err.kind, ErrorKind::Input);
+        let err = SourceText::from_bytes(b"\xFF\xFEa\0\x00\xD8").unwrap_err();
+        assert_eq!((err.kind, err.idx), (ErrorKind::Input, 4));
+    }
+
+    #[test]
+    fn hashbang_after_bom() {
+        let bytes = b"\xEF\xBB\xBF#!/usr/bin/env node\nlet a;";
+        let source = SourceText::from_bytes(bytes).unwrap();
+        let first = Scanner::from_bytes(&source).next().unwrap().unwrap();
+        assert!(first.token.is_comment());
+        let text = core::str::from_utf8(bytes).unwrap();
+        let first = Scanner::new(text).next().unwrap().unwrap();
+        assert!(first.token.is_comment());
+        assert_eq!(first.span, Span::new(3, 22));
+    }
+}
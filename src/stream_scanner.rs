@@ -0,0 +1,347 @@
+use std::{
+    collections::VecDeque,
+    io::{self, Read},
+    mem,
+};
+
+use crate::{
+    error::{Error, ErrorKind},
+    line_index::LineIndex,
+    resume::Resume,
+    Item, Position, Scanner, Span,
+};
+
+type Res<T> = Result<T, Error>;
+
+const DEFAULT_CHUNK_SIZE: usize = 64 * 1024;
+/// How far past the end of a token the tokenizer might look,
+/// also the widest character it might back up over after
+/// running out of text. Any token or error this close to the
+/// end of the buffered text is scanned again once more text
+/// has been read
+const LOOK_AHEAD: usize = 4;
+
+/// A scanner that reads its JS text from an `io::Read`, keeping only
+/// the text needed for the tokens that haven't been scanned yet
+///
+/// The input is read in chunks, a token cut off by the end of a chunk
+/// is scanned again after reading the next one, so peak memory depends
+/// on the size of the largest token rather than the size of the input.
+/// Each `Item` owns its text and has spans relative to the whole input.
+///
+/// ```
+/// use ress::{StreamScanner, tokens::Token};
+/// let js: &[u8] = b"let x = 'a';";
+/// let tokens: Vec<Token<String>> = StreamScanner::with_chunk_size(js, 3)
+///     .map(|i| i.unwrap().token)
+///     .collect();
+/// assert_eq!(tokens[3], Token::String(ress::tokens::StringLit::single("a".to_string(), false)));
+/// ```
+pub struct StreamScanner<R> {
+    reader: R,
+    chunk_size: usize,
+    /// The text that hasn't been scanned yet
+    window: String,
+    /// The end of the last read that is not yet a full UTF-8 sequence
+    partial: Vec<u8>,
+    /// The byte index of `window` in the whole input
+    offset: usize,
+    /// The line/column of `offset`
+    offset_position: Position,
+    reader_done: bool,
+    /// The scanner state at the start of `window`
+    resume: Option<Resume>,
+    ready: VecDeque<Res<Item<String>>>,
+    done: bool,
+}
+
+impl<R: Read> StreamScanner<R> {
+    /// Create a new `StreamScanner` reading from `reader`
+    pub fn new(reader: R) -> Self {
+        Self::with_chunk_size(reader, DEFAULT_CHUNK_SIZE)
+    }
+    /// Create a new `StreamScanner` reading up to `chunk_size`
+    /// bytes from `reader` at a time
+    pub fn with_chunk_size(reader: R, chunk_size: usize) -> Self {
+        Self {
+            reader,
+            chunk_size: chunk_size.max(1),
+            window: String::new(),
+            partial: Vec::new(),
+            offset: 0,
+            offset_position: Position::new(1, 1),
+            reader_done: false,
+            resume: None,
+            ready: VecDeque::new(),
+            done: false,
+        }
+    }
+
+    /// Read the next chunk onto the end of `window`, holding
+    /// back any split UTF-8 sequence for the next read
+    fn fill(&mut self) -> Res<()> {
+        let mut chunk = vec![0; self.chunk_size];
+        let read = loop {
+            match self.reader.read(&mut chunk) {
+                Ok(read) => break read,
+                Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+                Err(e) => return Err(self.input_error(format!("failed to read input: {}", e))),
+            }
+        };
+        if read == 0 {
+            self.reader_done = true;
+            if !self.partial.is_empty() {
+                return Err(self.input_error("input ended inside of a UTF-8 sequence".to_string()));
+            }
+            return Ok(());
+        }
+        self.partial.extend_from_slice(&chunk[..read]);
+        let valid = match std::str::from_utf8(&self.partial) {
+            Ok(s) => s.len(),
+            Err(e) if e.error_len().is_none() => e.valid_up_to(),
+            Err(e) => {
+                let msg = format!("invalid UTF-8 in input: {}", e);
+                return Err(self.input_error(msg));
+            }
+        };
+        let text = std::str::from_utf8(&self.partial[..valid]).expect("validated above");
+        self.window.push_str(text);
+        self.partial.drain(..valid);
+        Ok(())
+    }
+
+    /// Scan as many tokens as can be known to be complete
+    /// from `window`, reading more first if needed
+    fn scan(&mut self) -> Res<()> {
+        // anything left in `window` needs more text to finish
+        if !self.reader_done {
+            self.fill()?;
+        }
+        while !self.reader_done && self.window.len() < self.chunk_size {
+            self.fill()?;
+        }
+        let window = mem::take(&mut self.window);
+        let mut scanner = Scanner::new(&window);
+        if let Some(resume) = &self.resume {
+            resume.clone().restore(&mut scanner);
+        }
+        scanner.manual_scanner.stream.at_source_start = self.offset == 0;
+        let mut before = Resume::capture(&scanner);
+        loop {
+            let next = match scanner.next() {
+                Some(next) => next,
+                None => {
+                    self.done = true;
+                    break;
+                }
+            };
+            let cursor = scanner.manual_scanner.stream.stream.idx;
+            if !self.reader_done && cursor + LOOK_AHEAD >= window.len() {
+                // this might have been cut short, scan it
+                // again with more text
+                break;
+            }
+            match next {
+                Ok(item) => {
+                    let is_eof = item.token.is_eof();
+                    self.ready.push_back(Ok(self.to_owned_item(item)));
+                    if is_eof {
+                        self.done = true;
+                        break;
+                    }
+                }
+                Err(e) => {
+                    self.ready.push_back(Err(self.to_stream_error(e, &window)));
+                    if scanner.errored {
+                        self.done = true;
+                        break;
+                    }
+                }
+            }
+            before = Resume::capture(&scanner);
+        }
+        self.window = window;
+        if !self.done {
+            self.consume(before);
+        }
+        Ok(())
+    }
+
+    /// Drop the text before `resume`'s cursor from `window`
+    fn consume(&mut self, mut resume: Resume) {
+        let mut consumed = resume.manual_state.cursor;
+        // keep a trailing `\r` so a `\n` read next
+        // is still treated as part of the same new line
+        if self.window[..consumed].ends_with('\r') {
+            consumed -= 1;
+        }
+        let end = LineIndex::new(&self.window[..consumed]).position(consumed);
+        self.offset_position = if end.line == 1 {
+            Position::new(
+                self.offset_position.line,
+                self.offset_position.column + end.column - 1,
+            )
+        } else {
+            Position::new(self.offset_position.line + end.line - 1, end.column)
+        };
+        self.window.drain(..consumed);
+        self.offset += consumed;
+        resume.shift(consumed);
+        self.resume = Some(resume);
+    }
+
+    fn to_owned_item(&self, item: Item<&str>) -> Item<String> {
+        let mut item: Item<String> = item.into_owned();
+        item.span = self.shift_span(item.span);
+        item
+    }
+
+    fn shift_span(&self, span: Span) -> Span {
+        // delimiters opened in an earlier window will have
+        // wrapped below 0 when shifted, see `Resume::shift`
+        Span::new(
+            span.start.wrapping_add(self.offset),
+            span.end.wrapping_add(self.offset),
+        )
+    }
+
+    /// Update an error from the `Scanner` to be relative
+    /// to the whole input instead of `window`
+    fn to_stream_error(&self, mut e: Error, window: &str) -> Error {
+        if let ErrorKind::Delimiter(delimiters) = &mut e.kind {
+            for location in delimiters
+                .open
+                .iter_mut()
+                .chain(delimiters.close.iter_mut())
+            {
+                location.span = self.shift_span(location.span);
+            }
+        } else {
+            let position = LineIndex::new(window).position(e.idx);
+            if position.line == 1 {
+                e.line = self.offset_position.line;
+                e.column = self.offset_position.column + position.column - 1;
+            } else {
+                e.line = self.offset_position.line + position.line - 1;
+                e.column = position.column;
+            }
+        }
+        e.idx = e.idx.wrapping_add(self.offset);
+        e
+    }
+
+    fn input_error(&self, msg: String) -> Error {
+        Error {
+            line: 0,
+            column: 0,
+            msg,
+            idx: self.offset + self.window.len(),
+            kind: ErrorKind::Input,
+        }
+    }
+}
+
+impl<R: Read> Iterator for StreamScanner<R> {
+    type Item = Res<Item<String>>;
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some(item) = self.ready.pop_front() {
+                return Some(item);
+            }
+            if self.done {
+                return None;
+            }
+            if let Err(e) = self.scan() {
+                self.done = true;
+                return Some(Err(e));
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn compare(js: &str) {
+        let expected: Vec<_> = Scanner::new(js).map(|i| i.map(Item::into_owned)).collect();
+        for chunk_size in 1..=9 {
+            let items: Vec<_> = StreamScanner::with_chunk_size(js.as_bytes(), chunk_size).collect();
+            assert_eq!(items.len(), expected.len(), "chunk size {}", chunk_size);
+            for (lhs, rhs) in items.iter().zip(&expected) {
+                assert_eq!(lhs, rhs, "chunk size {}", chunk_size);
+            }
+        }
+    }
+
+    #[test]
+    fn matches_scanner() {
+        compare(
+            "#!/usr/bin/env node
+function thing(a, b) {
+    let x = 'single' + \"double\" / 2;\r
+    var re = /[a-z]+\\//gi; // single
+    if (a) /b/.test(x);
+    /* multi
+     line */ return `head${x / 2}middle${{a: /b/}}tail`;
+}
+<!-- html comment
+/*
+*/--> trailing
+x = 0xff + 0b1 + 0o7 + 1n + 1.5e2 + true + null ?. y;
+a ?.5 : 1 === 2 >>>= 3;
+let ünicode = '\\u{2028}🦜' + `🦜${'\\u{2029}'}`;",
+        );
+    }
+
+    #[test]
+    fn bounded_window() {
+        let js = "let a = 'some string' + /re/g;\n".repeat(1000);
+        let mut scanner = StreamScanner::with_chunk_size(js.as_bytes(), 16);
+        let mut count = 0;
+        while let Some(item) = scanner.next() {
+            assert!(item.is_ok());
+            assert!(scanner.window.len() < 64, "{}", scanner.window.len());
+            count += 1;
+        }
+        assert_eq!(count, 7001);
+    }
+
+    #[test]
+    fn hashbang_only_at_start() {
+        let items: Vec<_> = StreamScanner::with_chunk_size(&b"a\n#!b"[..], 2).collect();
+        assert!(items[1].is_ok());
+        assert!(items[1]
+            .as_ref()
+            .unwrap()
+            .token
+            .matches_punct(crate::tokens::Punct::Hash));
+    }
+
+    #[test]
+    fn errors() {
+        let js = "{\n  a)\nb";
+        let items: Vec<_> = StreamScanner::with_chunk_size(js.as_bytes(), 2).collect();
+        let err = items[2].as_ref().unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "unmatched close paren (innermost open delimiter is { at 1:1) at 2:4"
+        );
+        match &err.kind {
+            ErrorKind::Delimiter(d) => {
+                assert_eq!(d.open.unwrap().span, Span::new(0, 1));
+                assert_eq!(d.close.unwrap().span, Span::new(5, 6));
+            }
+            _ => panic!("expected delimiter error"),
+        }
+        assert_eq!(items.len(), 3);
+        let err = StreamScanner::with_chunk_size(&b"a;\n  b; 'c"[..], 3)
+            .find_map(|i| i.err())
+            .unwrap();
+        assert_eq!((err.line, err.column, err.idx), (2, 7, 9));
+        let err = StreamScanner::new(&[b'a', 0xff][..])
+            .find_map(|i| i.err())
+            .unwrap();
+        assert_eq!(err.kind, ErrorKind::Input);
+    }
+}
@@ -0,0 +1,282 @@
+use alloc::vec::Vec;
+use core::convert::TryFrom;
+
+use crate::{
+    error::Error,
+    line_index::LineIndex,
+    tokens::{
+        Comment, CommentKind, Ident, Number, Quote, RegEx, StringLit, Template, TemplatePart,
+        Token, TokenKind,
+    },
+    Item, Scanner, SourceLocation, Span,
+};
+
+type Res<T> = Result<T, Error>;
+
+const OCTAL_ESCAPE: u8 = 1;
+const INVALID_UNICODE_ESCAPE: u8 = 1 << 1;
+const INVALID_HEX_ESCAPE: u8 = 1 << 2;
+
+/// A compact store for all of the tokens in some JS text
+///
+/// Each token is kept as a `TokenKind`, a byte of flags and a
+/// `u32` start and end in parallel arrays, about 11 bytes per token
+/// compared to the 80+ of an `Item<&str>`. Line/column positions
+/// are only computed when asked for.
+///
+/// ```
+/// use ress::{TokenBuffer, tokens::{Punct, TokenKind}};
+/// let buffer = TokenBuffer::new("let x = 1;").unwrap();
+/// assert_eq!(buffer.kind(3), Some(TokenKind::Number(ress::tokens::NumberKind::Dec)));
+/// let items: Vec<_> = buffer.iter().collect();
+/// assert!(items[4].token.matches_punct(Punct::SemiColon));
+/// ```
+pub struct TokenBuffer<'a> {
+    text: &'a str,
+    kinds: Vec<TokenKind>,
+    flags: Vec<u8>,
+    starts: Vec<u32>,
+    ends: Vec<u32>,
+    lines: LineIndex,
+}
+
+impl<'a> TokenBuffer<'a> {
+    /// Scan the provided JS text into a new buffer,
+    /// the text must be shorter than `u32::MAX` bytes
+    pub fn new(text: &'a str) -> Res<Self> {
+        if u32::try_from(text.len()).is_err() {
+            let msg = format!("text is too large for a TokenBuffer: {} bytes", text.len());
+            return Err(Error::new(1, 1, msg, 0));
+        }
+        let mut ret = Self {
+            text,
+            kinds: Vec::new(),
+            flags: Vec::new(),
+            starts: Vec::new(),
+            ends: Vec::new(),
+            lines: LineIndex::new(text),
+        };
+        for item in Scanner::without_locations(text) {
+            ret.push(&item?);
+        }
+        Ok(ret)
+    }
+
+    fn push(&mut self, item: &Item<&'a str>) {
+        let flags = match &item.token {
+            Token::String(StringLit::Single(s)) | Token::String(StringLit::Double(s))
+                if s.contains_octal_escape =>
+            {
+                OCTAL_ESCAPE
+            }
+            Token::Template(Template::NoSub(t))
+            | Token::Template(Template::Head(t))
+            | Token::Template(Template::Middle(t))
+            | Token::Template(Template::Tail(t)) => {
+                let mut flags = 0;
+                if t.contains_octal_escape {
+                    flags |= OCTAL_ESCAPE;
+                }
+                if t.contains_invalid_unicode_escape {
+                    flags |= INVALID_UNICODE_ESCAPE;
+                }
+                if t.contains_invalid_hex_escape {
+                    flags |= INVALID_HEX_ESCAPE;
+                }
+                flags
+            }
+            _ => 0,
+        };
+        self.kinds.push(item.token.kind());
+        self.flags.push(flags);
+        self.starts.push(item.span.start as u32);
+        self.ends.push(item.span.end as u32);
+    }
+    /// The number of tokens, including the `EoF`
+    pub fn len(&self) -> usize {
+        self.kinds.len()
+    }
+    pub fn is_empty(&self) -> bool {
+        self.kinds.is_empty()
+    }
+    /// The original text
+    pub fn text(&self) -> &'a str {
+        self.text
+    }
+    /// The line index used to compute
+    /// line/column positions
+    pub fn line_index(&self) -> &LineIndex {
+        &self.lines
+    }
+    /// All of the token kinds, in order
+    pub fn kinds(&self) -> &[TokenKind] {
+        &self.kinds
+    }
+    /// The kind of the token at `idx`
+    pub fn kind(&self, idx: usize) -> Option<TokenKind> {
+        self.kinds.get(idx).copied()
+    }
+    /// The span of the token at `idx`
+    pub fn span(&self, idx: usize) -> Option<Span> {
+        Some(Span::new(
+            *self.starts.get(idx)? as usize,
+            *self.ends.get(idx)? as usize,
+        ))
+    }
+    /// The line/column range of the token at `idx`
+    pub fn location(&self, idx: usize) -> Option<SourceLocation> {
+        Some(self.lines.location(self.span(idx)?))
+    }
+    /// Build the full `Item` for the token at `idx`
+    pub fn item(&self, idx: usize) -> Option<Item<&'a str>> {
+        let kind = self.kind(idx)?;
+        let span = self.span(idx)?;
+        let token = token_from_parts(kind, self.flags[idx], self.text, span);
+        Some(Item::new(token, span, self.lines.location(span)))
+    }
+    /// An iterator building each `Item` on demand
+    pub fn iter(&self) -> Items<'_, 'a> {
+        Items {
+            buffer: self,
+            idx: 0,
+        }
+    }
+}
+
+/// An iterator over the `Item`s in a `TokenBuffer`
+pub struct Items<'b, 'a> {
+    buffer: &'b TokenBuffer<'a>,
+    idx: usize,
+}
+
+impl<'b, 'a> Iterator for Items<'b, 'a> {
+    type Item = Item<&'a str>;
+    fn next(&mut self) -> Option<Self::Item> {
+        let ret = self.buffer.item(self.idx)?;
+        self.idx += 1;
+        Some(ret)
+    }
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let len = self.buffer.len().saturating_sub(self.idx);
+        (len, Some(len))
+    }
+}
+
+impl<'b, 'a> ExactSizeIterator for Items<'b, 'a> {}
+
+impl<'b, 'a> IntoIterator for &'b TokenBuffer<'a> {
+    type Item = Item<&'a str>;
+    type IntoIter = Items<'b, 'a>;
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+/// Rebuild a `Token` from its kind, flags and span, the
+/// slicing here mirrors `ManualScanner::next_item`
+pub(crate) fn token_from_parts(kind: TokenKind, flags: u8, text: &str, span: Span) -> Token<&str> {
+    let s = &text[span.start..span.end];
+    let oct = flags & OCTAL_ESCAPE != 0;
+    let uni = flags & INVALID_UNICODE_ESCAPE != 0;
+    let hex = flags & INVALID_HEX_ESCAPE != 0;
+    match kind {
+        TokenKind::Boolean(b) => Token::Boolean(b.into()),
+        TokenKind::EoF => Token::EoF,
+        TokenKind::Ident => Token::Ident(Ident::from(s)),
+        TokenKind::Keyword(k) => Token::Keyword(k.with_str(s)),
+        TokenKind::Null => Token::Null,
+        TokenKind::Number(_) => Token::Number(Number::from(s)),
+        TokenKind::Punct(p) => Token::Punct(p),
+        TokenKind::String(Quote::Single) => {
+            Token::String(StringLit::single(&s[1..s.len() - 1], oct))
+        }
+        TokenKind::String(Quote::Double) => {
+            Token::String(StringLit::double(&s[1..s.len() - 1], oct))
+        }
+        TokenKind::RegEx => {
+            let body_end = s.rfind('/').unwrap_or(0);
+            let flags = &s[body_end + 1..];
+            let flags = if flags.is_empty() { None } else { Some(flags) };
+            Token::RegEx(RegEx::from_parts(&s[1..body_end], flags))
+        }
+        TokenKind::Template(TemplatePart::NoSub) => {
+            Token::Template(Template::no_sub_template(&s[1..s.len() - 1], oct, uni, hex))
+        }
+        TokenKind::Template(TemplatePart::Head) => {
+            Token::Template(Template::template_head(&s[1..s.len() - 2], oct, uni, hex))
+        }
+        TokenKind::Template(TemplatePart::Middle) => {
+            Token::Template(Template::template_middle(&s[1..s.len() - 2], oct, uni, hex))
+        }
+        TokenKind::Template(TemplatePart::Tail) => {
+            Token::Template(Template::template_tail(&s[1..s.len() - 1], oct, uni, hex))
+        }
+        TokenKind::Comment(CommentKind::Single) => {
+            Token::Comment(Comment::new_single_line(s.trim_start_matches("//")))
+        }
+        TokenKind::Comment(CommentKind::Multi) => {
+            let end_index = s[2..].find("*/").map(|i| i + 4).unwrap_or(s.len());
+            let (tail_content, tail_start) = if let Some(tail_start) = s[end_index..].find("-->") {
+                let actual_start = end_index + tail_start;
+                (Some(&s[actual_start + 3..]), actual_start)
+            } else {
+                (None, s.len())
+            };
+            let content = s[..tail_start]
+                .trim_start_matches("/*")
+                .trim_end_matches("*/");
+            Token::Comment(Comment::from_parts(
+                content,
+                CommentKind::Multi,
+                tail_content,
+            ))
+        }
+        TokenKind::Comment(CommentKind::Html) => {
+            let (content, tail) = Comment::split_html(s);
+            Token::Comment(Comment::new_html(content, tail))
+        }
+        TokenKind::Comment(CommentKind::Hashbang) => Token::Comment(Comment::new_hashbang(&s[2..])),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn compare(js: &str) {
+        let buffer = TokenBuffer::new(js).unwrap();
+        let expected: Vec<_> = Scanner::new(js).map(|i| i.unwrap()).collect();
+        assert_eq!(buffer.len(), expected.len());
+        for (lhs, rhs) in buffer.iter().zip(expected) {
+            assert_eq!(lhs, rhs);
+        }
+    }
+
+    #[test]
+    fn matches_scanner() {
+        compare(
+            "#!/usr/bin/env node
+function thing(a, b) {
+    let x = 'single' + \"double\\101\";
+    var re = /[a-z]+\\//gi; // single
+    /* multi */ return `head${x}middle${re}tail\\x1`;
+}
+<!-- html comment
+x = 0xff + 0b1 + 0o7 + 1n + 1.5e2 + true + null;",
+        );
+        compare("/* a\n */--> tail\nb");
+        compare("a = 'é\\\r\nb' + `\u{2028}${c}\r\n` /* ü\u{2029} */ / 2;\n  // z");
+    }
+
+    #[test]
+    fn lazy_locations() {
+        let buffer = TokenBuffer::new("a\n  b").unwrap();
+        assert_eq!(buffer.len(), 3);
+        assert_eq!(
+            buffer.location(1).unwrap(),
+            SourceLocation::new(crate::Position::new(2, 3), crate::Position::new(2, 4))
+        );
+        assert!(buffer.kind(2).unwrap().is_eof());
+        assert!(buffer.item(3).is_none());
+    }
+}
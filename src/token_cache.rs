@@ -0,0 +1,528 @@
+use crate::{
+    error::CacheError,
+    tokens::{
+        Comment, CommentKind, Ident, Number, Punct, RegEx, StringLit, Template, Token, KEYWORDS,
+    },
+    Item, Position, SourceLocation, Span,
+};
+use alloc::{string::String, vec::Vec};
+
+/// The first bytes of every token cache
+const MAGIC: &[u8; 8] = b"RESSTOK\0";
+/// The version of the layout below, bumped along with
+/// the crate version any time it changes
+const FORMAT_VERSION: u8 = 1;
+const VERSION: &str = env!("CARGO_PKG_VERSION");
+
+const EOF: u8 = 0;
+const FALSE: u8 = 1;
+const TRUE: u8 = 2;
+const IDENT: u8 = 3;
+const KEYWORD: u8 = 4;
+const NULL: u8 = 5;
+const NUMBER: u8 = 6;
+const PUNCT: u8 = 7;
+const STRING: u8 = 8;
+const TEMPLATE: u8 = 9;
+const REGEX: u8 = 10;
+const COMMENT: u8 = 11;
+
+/// Build `punct_code` and `punct_from_code` from one list so
+/// the byte each `Punct` is stored as doesn't depend on the
+/// order `Punct` is declared in. These bytes are part of the
+/// format, new puncts need new bytes and existing ones must
+/// never change.
+macro_rules! punct_codes {
+    ($($code:literal => $punct:ident,)*) => {
+        fn punct_code(p: Punct) -> u8 {
+            match p {
+                $(Punct::$punct => $code,)*
+            }
+        }
+        fn punct_from_code(code: u8) -> Option<Punct> {
+            Some(match code {
+                $($code => Punct::$punct,)*
+                _ => return None,
+            })
+        }
+    };
+}
+
+punct_codes! {
+    0 => Ampersand,
+    1 => AmpersandEqual,
+    2 => Asterisk,
+    3 => AsteriskEqual,
+    4 => AtMark,
+    5 => Bang,
+    6 => BangDoubleEqual,
+    7 => BangEqual,
+    8 => Caret,
+    9 => CaretEqual,
+    10 => CloseBrace,
+    11 => CloseBracket,
+    12 => CloseParen,
+    13 => Colon,
+    14 => Comma,
+    15 => Dash,
+    16 => DoubleDash,
+    17 => DashEqual,
+    18 => DoubleAmpersand,
+    19 => DoubleAsterisk,
+    20 => DoubleAsteriskEqual,
+    21 => DoubleEqual,
+    22 => DoubleGreaterThan,
+    23 => DoubleGreaterThanEqual,
+    24 => DoubleLessThan,
+    25 => DoubleLessThanEqual,
+    26 => DoublePipe,
+    27 => DoublePlus,
+    28 => Ellipsis,
+    29 => Equal,
+    30 => EqualGreaterThan,
+    31 => ForwardSlash,
+    32 => ForwardSlashEqual,
+    33 => GreaterThan,
+    34 => GreaterThanEqual,
+    35 => Hash,
+    36 => LessThan,
+    37 => LessThanEqual,
+    38 => OpenBrace,
+    39 => OpenBracket,
+    40 => OpenParen,
+    41 => Percent,
+    42 => PercentEqual,
+    43 => Period,
+    44 => Pipe,
+    45 => PipeEqual,
+    46 => Plus,
+    47 => PlusEqual,
+    48 => QuestionMark,
+    49 => SemiColon,
+    50 => Tilde,
+    51 => TripleEqual,
+    52 => TripleGreaterThanEqual,
+    53 => TripleGreaterThan,
+    54 => PipeGreaterThan,
+    55 => DoubleColon,
+    56 => HashOpenBrace,
+    57 => HashOpenBracket,
+    58 => DoubleAtMark,
+}
+
+/// A token stream stored as compact bytes that can be
+/// turned back into `Item`s over the original text without
+/// scanning it again
+///
+/// The cache starts with a fixed size header holding the
+/// ress version, the length and FNV-1a hash of the source
+/// text and the number of items. Each item is then a kind
+/// byte followed by varints for its span and location, as
+/// deltas from the item before it, with all text sliced back
+/// out of the source. Nothing needs to be aligned, so the bytes
+/// can be read directly from a memory mapped file.
+///
+/// ```
+/// use ress::{Scanner, TokenCache};
+/// let js = "let x = 'a';";
+/// let items: Vec<_> = Scanner::new(js).map(|i| i.unwrap()).collect();
+/// let bytes = TokenCache::encode(js, &items);
+/// let cache = TokenCache::load(&bytes, js).unwrap();
+/// assert_eq!(cache.len(), items.len());
+/// let loaded: Vec<_> = cache.map(|i| i.unwrap()).collect();
+/// assert_eq!(loaded, items);
+/// assert!(TokenCache::load(&bytes, "let x = 'b';").is_err());
+/// ```
+pub struct TokenCache<'b, 'a> {
+    bytes: &'b [u8],
+    source: &'a str,
+    remaining: usize,
+    prev_end: usize,
+    prev_line: usize,
+    failed: bool,
+}
+
+impl<'b, 'a> TokenCache<'b, 'a> {
+    /// Write the `items` scanned from `source` into a new cache
+    pub fn encode<'i, T>(source: &str, items: impl IntoIterator<Item = &'i Item<T>>) -> Vec<u8>
+    where
+        T: AsRef<str> + 'i,
+    {
+        let mut out = Vec::with_capacity(source.len());
+        out.extend_from_slice(MAGIC);
+        out.push(FORMAT_VERSION);
+        out.push(VERSION.len() as u8);
+        out.extend_from_slice(VERSION.as_bytes());
+        out.extend_from_slice(&(source.len() as u64).to_le_bytes());
+        out.extend_from_slice(&fnv1a(source.as_bytes()).to_le_bytes());
+        let count_at = out.len();
+        out.extend_from_slice(&0u64.to_le_bytes());
+        let mut count = 0u64;
+        let mut prev_end = 0;
+        let mut prev_line = 0;
+        for item in items {
+            write_token(&item.token, &mut out);
+            write_signed(item.span.start as i64 - prev_end as i64, &mut out);
+            write_varint(item.span.len() as u64, &mut out);
+            let SourceLocation { start, end } = item.location;
+            write_signed(start.line as i64 - prev_line as i64, &mut out);
+            write_varint(start.column as u64, &mut out);
+            write_signed(end.line as i64 - start.line as i64, &mut out);
+            write_varint(end.column as u64, &mut out);
+            prev_end = item.span.end;
+            prev_line = start.line;
+            count += 1;
+        }
+        out[count_at..count_at + 8].copy_from_slice(&count.to_le_bytes());
+        out
+    }
+
+    /// Open a cache for `source`, failing if it was written by
+    /// another version of ress or for some other text
+    pub fn load(bytes: &'b [u8], source: &'a str) -> Result<Self, CacheError> {
+        let mut reader = Reader(bytes);
+        if reader.take(MAGIC.len()) != Some(MAGIC) {
+            return Err(CacheError::NotACache);
+        }
+        let format = reader.byte().ok_or(CacheError::Corrupt)?;
+        let version_len = reader.byte().ok_or(CacheError::Corrupt)?;
+        let version = reader
+            .take(version_len as usize)
+            .ok_or(CacheError::Corrupt)?;
+        if format != FORMAT_VERSION || version != VERSION.as_bytes() {
+            return Err(CacheError::Version(
+                String::from_utf8_lossy(version).into_owned(),
+            ));
+        }
+        let len = reader.u64().ok_or(CacheError::Corrupt)?;
+        let hash = reader.u64().ok_or(CacheError::Corrupt)?;
+        if len != source.len() as u64 || hash != fnv1a(source.as_bytes()) {
+            return Err(CacheError::SourceChanged);
+        }
+        let remaining = reader.u64().ok_or(CacheError::Corrupt)? as usize;
+        Ok(Self {
+            bytes: reader.0,
+            source,
+            remaining,
+            prev_end: 0,
+            prev_line: 0,
+            failed: false,
+        })
+    }
+
+    /// The number of items left in the cache
+    pub fn len(&self) -> usize {
+        self.remaining
+    }
+
+    /// If there are no items left in the cache
+    pub fn is_empty(&self) -> bool {
+        self.remaining == 0
+    }
+
+    fn read_item(&mut self) -> Option<Item<&'a str>> {
+        let mut reader = Reader(self.bytes);
+        let tag = reader.byte()?;
+        let extra = match tag {
+            KEYWORD | PUNCT | STRING | TEMPLATE | COMMENT => reader.byte()?,
+            _ => 0,
+        };
+        let (content_len, tail_len) = if tag == COMMENT {
+            let content = reader.varint()? as usize;
+            let tail = if extra & 4 == 4 {
+                Some(reader.varint()? as usize)
+            } else {
+                None
+            };
+            (content, tail)
+        } else {
+            (0, None)
+        };
+        let start = offset(self.prev_end, reader.signed()?)?;
+        let end = start.checked_add(reader.varint()? as usize)?;
+        let start_line = offset(self.prev_line, reader.signed()?)?;
+        let start_column = reader.varint()? as usize;
+        let end_line = offset(start_line, reader.signed()?)?;
+        let end_column = reader.varint()? as usize;
+        let s = self.source.get(start..end)?;
+        let token = match tag {
+            EOF => Token::EoF,
+            FALSE => Token::Boolean(false.into()),
+            TRUE => Token::Boolean(true.into()),
+            IDENT => Token::Ident(Ident::from(s)),
+            KEYWORD => Token::Keyword(KEYWORDS.get(extra as usize)?.map(|_| s)),
+            NULL => Token::Null,
+            NUMBER => Token::Number(Number::from(s)),
+            PUNCT => Token::Punct(punct_from_code(extra)?),
+            STRING => {
+                let content = s.get(1..s.len().checked_sub(1)?)?;
+                if extra & 1 == 1 {
+                    Token::String(StringLit::double(content, extra & 2 == 2))
+                } else {
+                    Token::String(StringLit::single(content, extra & 2 == 2))
+                }
+            }
+            TEMPLATE => {
+                let (oct, uni, hex) = (extra & 4 == 4, extra & 8 == 8, extra & 16 == 16);
+                let template = match extra & 3 {
+                    0 => {
+                        Template::no_sub_template(s.get(1..s.len().checked_sub(1)?)?, oct, uni, hex)
+                    }
+                    1 => Template::template_head(s.get(1..s.len().checked_sub(2)?)?, oct, uni, hex),
+                    2 => {
+                        Template::template_middle(s.get(1..s.len().checked_sub(2)?)?, oct, uni, hex)
+                    }
+                    _ => Template::template_tail(s.get(1..s.len().checked_sub(1)?)?, oct, uni, hex),
+                };
+                Token::Template(template)
+            }
+            REGEX => {
+                let body_end = s.rfind('/')?;
+                let flags = s.get(body_end + 1..).filter(|f| !f.is_empty());
+                Token::RegEx(RegEx::from_parts(s.get(1..body_end)?, flags))
+            }
+            COMMENT => {
+                let kind = match extra & 3 {
+                    0 => CommentKind::Single,
+                    1 => CommentKind::Multi,
+                    2 => CommentKind::Html,
+                    _ => CommentKind::Hashbang,
+                };
+                let content_start: usize = match kind {
+                    CommentKind::Html if !s.starts_with("<!--") => 0,
+                    CommentKind::Html => 4,
+                    _ => 2,
+                };
+                let content = s.get(content_start..content_start.checked_add(content_len)?)?;
+                let tail = match tail_len {
+                    Some(len) => Some(s.get(s.len().checked_sub(len)?..)?),
+                    None => None,
+                };
+                Token::Comment(Comment::from_parts(content, kind, tail))
+            }
+            _ => return None,
+        };
+        self.bytes = reader.0;
+        self.prev_end = end;
+        self.prev_line = start_line;
+        Some(Item::new(
+            token,
+            Span::new(start, end),
+            SourceLocation::new(
+                Position::new(start_line, start_column),
+                Position::new(end_line, end_column),
+            ),
+        ))
+    }
+}
+
+impl<'b, 'a> Iterator for TokenCache<'b, 'a> {
+    type Item = Result<Item<&'a str>, CacheError>;
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.remaining == 0 || self.failed {
+            return None;
+        }
+        match self.read_item() {
+            Some(item) => {
+                self.remaining -= 1;
+                Some(Ok(item))
+            }
+            None => {
+                self.failed = true;
+                Some(Err(CacheError::Corrupt))
+            }
+        }
+    }
+}
+
+fn write_token<T: AsRef<str>>(token: &Token<T>, out: &mut Vec<u8>) {
+    match token {
+        Token::EoF => out.push(EOF),
+        Token::Boolean(b) => out.push(if b.is_true() { TRUE } else { FALSE }),
+        Token::Ident(_) => out.push(IDENT),
+        Token::Keyword(k) => {
+            out.extend_from_slice(&[KEYWORD, k.index() as u8]);
+        }
+        Token::Null => out.push(NULL),
+        Token::Number(_) => out.push(NUMBER),
+        Token::Punct(p) => out.extend_from_slice(&[PUNCT, punct_code(*p)]),
+        Token::String(s) => {
+            let flags = s.is_double() as u8 | (s.has_octal_escape() as u8) << 1;
+            out.extend_from_slice(&[STRING, flags]);
+        }
+        Token::Template(t) => {
+            let (part, lit) = match t {
+                Template::NoSub(lit) => (0, lit),
+                Template::Head(lit) => (1, lit),
+                Template::Middle(lit) => (2, lit),
+                Template::Tail(lit) => (3, lit),
+            };
+            let flags = part
+                | (lit.contains_octal_escape as u8) << 2
+                | (lit.contains_invalid_unicode_escape as u8) << 3
+                | (lit.contains_invalid_hex_escape as u8) << 4;
+            out.extend_from_slice(&[TEMPLATE, flags]);
+        }
+        Token::RegEx(_) => out.push(REGEX),
+        Token::Comment(c) => {
+            let kind = match c.kind {
+                CommentKind::Single => 0,
+                CommentKind::Multi => 1,
+                CommentKind::Html => 2,
+                CommentKind::Hashbang => 3,
+            };
+            let flags = kind | (c.tail_content.is_some() as u8) << 2;
+            out.extend_from_slice(&[COMMENT, flags]);
+            write_varint(c.content.as_ref().len() as u64, out);
+            if let Some(tail) = &c.tail_content {
+                write_varint(tail.as_ref().len() as u64, out);
+            }
+        }
+    }
+}
+
+/// The FNV-1a hash of `bytes`
+pub(crate) fn fnv1a(bytes: &[u8]) -> u64 {
+    let mut hash = 0xcbf2_9ce4_8422_2325u64;
+    for b in bytes {
+        hash ^= u64::from(*b);
+        hash = hash.wrapping_mul(0x0100_0000_01b3);
+    }
+    hash
+}
+
+fn write_varint(mut value: u64, out: &mut Vec<u8>) {
+    while value >= 0x80 {
+        out.push(value as u8 | 0x80);
+        value >>= 7;
+    }
+    out.push(value as u8);
+}
+
+/// Write a zigzag encoded varint
+fn write_signed(value: i64, out: &mut Vec<u8>) {
+    write_varint(((value << 1) ^ (value >> 63)) as u64, out);
+}
+
+fn offset(base: usize, delta: i64) -> Option<usize> {
+    if delta < 0 {
+        base.checked_sub(delta.unsigned_abs() as usize)
+    } else {
+        base.checked_add(delta as usize)
+    }
+}
+
+/// The unread part of a cache
+struct Reader<'b>(&'b [u8]);
+
+impl<'b> Reader<'b> {
+    fn take(&mut self, len: usize) -> Option<&'b [u8]> {
+        if self.0.len() < len {
+            return None;
+        }
+        let (head, rest) = self.0.split_at(len);
+        self.0 = rest;
+        Some(head)
+    }
+
+    fn byte(&mut self) -> Option<u8> {
+        self.take(1).map(|b| b[0])
+    }
+
+    fn u64(&mut self) -> Option<u64> {
+        let mut buf = [0; 8];
+        buf.copy_from_slice(self.take(8)?);
+        Some(u64::from_le_bytes(buf))
+    }
+
+    fn varint(&mut self) -> Option<u64> {
+        let mut value = 0u64;
+        for shift in (0..64).step_by(7) {
+            let b = self.byte()?;
+            value |= u64::from(b & 0x7f) << shift;
+            if b & 0x80 == 0 {
+                return Some(value);
+            }
+        }
+        None
+    }
+
+    fn signed(&mut self) -> Option<i64> {
+        let value = self.varint()?;
+        Some((value >> 1) as i64 ^ -((value & 1) as i64))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{EsVersion, Scanner, ScannerOptions};
+
+    static JS: &str = "#!/usr/bin/env node
+function thing(a, b) {
+    let x = 'single' + \"double\\07\" / 2;
+    var re = /[a-z]+\\//gi, y = /b/; // single
+    /* multi
+    line */ return `head${x / 2}middle${{a: /b/}}tail` + `\\u{g}`;
+}
+<!-- html comment
+--> html tail
+x = 0xff + 0b1 + 0o7 + 1n + 1.5e2 + true + false + null ?? é;";
+
+    #[test]
+    fn round_trip() {
+        let items: Vec<_> = Scanner::new(JS).map(|i| i.unwrap()).collect();
+        let bytes = TokenCache::encode(JS, &items);
+        let cache = TokenCache::load(&bytes, JS).unwrap();
+        assert_eq!(cache.len(), items.len());
+        let loaded: Vec<_> = cache.map(|i| i.unwrap()).collect();
+        assert_eq!(loaded, items);
+    }
+
+    #[test]
+    fn tables() {
+        // a byte used twice would decode to the wrong punct
+        for code in 0..=u8::MAX {
+            if let Some(p) = punct_from_code(code) {
+                assert_eq!(punct_code(p), code);
+            }
+        }
+        // report instead of stopping at `let` and `const`
+        let es3 = || {
+            ScannerOptions::new()
+                .target(EsVersion::Es3)
+                .on_unsupported(drop)
+        };
+        for (i, k) in KEYWORDS.iter().enumerate() {
+            let word = k.as_str();
+            let first = Scanner::with_options(word, es3()).next().unwrap().unwrap();
+            assert_eq!(first.token, Token::Keyword(k.map(|_| word)));
+            assert_eq!(k.index(), i);
+        }
+    }
+
+    #[test]
+    fn rejected() {
+        let items: Vec<_> = Scanner::new(JS).map(|i| i.unwrap()).collect();
+        let bytes = TokenCache::encode(JS, &items);
+        assert_eq!(
+            TokenCache::load(&bytes[1..], JS).err(),
+            Some(CacheError::NotACache)
+        );
+        let mut old = bytes.clone();
+        old[MAGIC.len() + 2] = b'9';
+        match TokenCache::load(&old, JS) {
+            Err(CacheError::Version(v)) => assert!(v.starts_with('9')),
+            _ => panic!("expected a version error"),
+        }
+        let changed = JS.replace("thing", "thinG");
+        assert_eq!(
+            TokenCache::load(&bytes, &changed).err(),
+            Some(CacheError::SourceChanged)
+        );
+        let truncated = &bytes[..bytes.len() - 3];
+        let loaded: Vec<_> = TokenCache::load(truncated, JS).unwrap().collect();
+        assert_eq!(loaded.len(), items.len());
+        assert_eq!(loaded.last(), Some(&Err(CacheError::Corrupt)));
+    }
+}
@@ -0,0 +1,346 @@
+use crate::{
+    error::{DelimiterError, DelimiterLocation, Error, ErrorKind},
+    tokens::{Punct, Template, Token},
+    Item, Scanner, Span,
+};
+use alloc::vec::Vec;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+/// The 4 kinds of delimited groups
+pub enum Delimiter {
+    /// `(` and `)`
+    Paren,
+    /// `[` and `]`
+    Bracket,
+    /// `{` and `}`
+    Brace,
+    /// A template head and its tail, any
+    /// `${ }` substitutions in between
+    Template,
+}
+
+impl Delimiter {
+    /// The text that opens this kind of group
+    pub fn open_str(self) -> &'static str {
+        match self {
+            Delimiter::Paren => "(",
+            Delimiter::Bracket => "[",
+            Delimiter::Brace => "{",
+            Delimiter::Template => "`${",
+        }
+    }
+    /// The text that closes this kind of group
+    pub fn close_str(self) -> &'static str {
+        match self {
+            Delimiter::Paren => ")",
+            Delimiter::Bracket => "]",
+            Delimiter::Brace => "}",
+            Delimiter::Template => "}`",
+        }
+    }
+    /// Check if a token opens a group, returning the kind
+    /// of group it would open
+    pub fn opened_by<T>(token: &Token<T>) -> Option<Self> {
+        match token {
+            Token::Punct(Punct::OpenParen) => Some(Delimiter::Paren),
+            Token::Punct(Punct::OpenBracket) | Token::Punct(Punct::HashOpenBracket) => {
+                Some(Delimiter::Bracket)
+            }
+            Token::Punct(Punct::OpenBrace) | Token::Punct(Punct::HashOpenBrace) => {
+                Some(Delimiter::Brace)
+            }
+            Token::Template(Template::Head(_)) => Some(Delimiter::Template),
+            _ => None,
+        }
+    }
+    /// Check if a token closes a group, returning the kind
+    /// of group it would close
+    pub fn closed_by<T>(token: &Token<T>) -> Option<Self> {
+        match token {
+            Token::Punct(Punct::CloseParen) => Some(Delimiter::Paren),
+            Token::Punct(Punct::CloseBracket) => Some(Delimiter::Bracket),
+            Token::Punct(Punct::CloseBrace) => Some(Delimiter::Brace),
+            Token::Template(Template::Tail(_)) => Some(Delimiter::Template),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+/// A single node in a token tree, either a single
+/// token or a delimited group of trees
+pub enum TokenTree<T> {
+    Leaf(Item<T>),
+    Group(Group<T>),
+}
+
+#[derive(Clone, Debug, PartialEq)]
+/// A delimited group of token trees
+///
+/// For `Delimiter::Template` groups `open` will be the
+/// template head and `close` the template tail, the
+/// contents of each `${ }` substitution will be in `children`
+/// separated by any template middle parts as leaves.
+pub struct Group<T> {
+    pub delimiter: Delimiter,
+    pub open: Item<T>,
+    pub close: Item<T>,
+    pub children: Vec<TokenTree<T>>,
+}
+
+#[derive(Debug, PartialEq)]
+/// The possible failures when building a token tree
+pub enum TokenTreeError {
+    /// The scanner failed to produce a token
+    Scan(Error),
+    /// The delimiters in the text were not balanced
+    Delimiter(DelimiterError),
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for TokenTreeError {}
+
+impl ::core::fmt::Display for TokenTreeError {
+    fn fmt(&self, f: &mut ::core::fmt::Formatter) -> ::core::fmt::Result {
+        match self {
+            TokenTreeError::Scan(e) => e.fmt(f),
+            TokenTreeError::Delimiter(e) => e.fmt(f),
+        }
+    }
+}
+
+impl From<Error> for TokenTreeError {
+    fn from(e: Error) -> Self {
+        TokenTreeError::Scan(e)
+    }
+}
+
+impl From<DelimiterError> for TokenTreeError {
+    fn from(e: DelimiterError) -> Self {
+        TokenTreeError::Delimiter(e)
+    }
+}
+
+impl<T> Group<T> {
+    /// The span from the start of `open` to the
+    /// end of `close`
+    pub fn span(&self) -> Span {
+        Span::new(self.open.span.start, self.close.span.end)
+    }
+}
+
+impl<T> TokenTree<T> {
+    /// The span this entire tree covers
+    pub fn span(&self) -> Span {
+        match self {
+            TokenTree::Leaf(item) => item.span,
+            TokenTree::Group(group) => group.span(),
+        }
+    }
+    pub fn is_leaf(&self) -> bool {
+        matches!(self, TokenTree::Leaf(_))
+    }
+    pub fn is_group(&self) -> bool {
+        matches!(self, TokenTree::Group(_))
+    }
+}
+
+impl<'a> TokenTree<&'a str> {
+    /// Scan the JS text provided into a list of token trees,
+    /// comments are included as leaves while the `EoF` is not
+    pub fn build(text: &'a str) -> Result<Vec<Self>, TokenTreeError> {
+        Self::from_items(Scanner::new(text))
+    }
+    /// Group a stream of items into a list of token trees
+    pub fn from_items<I>(items: I) -> Result<Vec<Self>, TokenTreeError>
+    where
+        I: IntoIterator<Item = Result<Item<&'a str>, Error>>,
+    {
+        let mut stack: Vec<(Delimiter, Item<&'a str>, Vec<Self>)> = Vec::new();
+        let mut root = Vec::new();
+        for item in items {
+            let item = match item {
+                Ok(item) => item,
+                // the scanner only tracks parens and braces, so the
+                // innermost open group here is the better match
+                Err(Error {
+                    kind: ErrorKind::Delimiter(e),
+                    ..
+                }) => {
+                    return Err(DelimiterError {
+                        open: stack
+                            .last()
+                            .map(|(delimiter, open, _)| delimiter_location(*delimiter, open)),
+                        close: e.close,
+                    }
+                    .into())
+                }
+                Err(e) => return Err(e.into()),
+            };
+            if item.is_eof() {
+                break;
+            }
+            if let Some(delimiter) = Delimiter::opened_by(&item.token) {
+                stack.push((delimiter, item, Vec::new()));
+                continue;
+            }
+            if let Some(delimiter) = Delimiter::closed_by(&item.token) {
+                let (open_delimiter, open, children) = match stack.pop() {
+                    Some(frame) => frame,
+                    None => {
+                        return Err(DelimiterError {
+                            open: None,
+                            close: Some(delimiter_location(delimiter, &item)),
+                        }
+                        .into())
+                    }
+                };
+                if open_delimiter != delimiter {
+                    return Err(DelimiterError {
+                        open: Some(delimiter_location(open_delimiter, &open)),
+                        close: Some(delimiter_location(delimiter, &item)),
+                    }
+                    .into());
+                }
+                let group = TokenTree::Group(Group {
+                    delimiter,
+                    open,
+                    close: item,
+                    children,
+                });
+                match stack.last_mut() {
+                    Some((_, _, parent)) => parent.push(group),
+                    None => root.push(group),
+                }
+                continue;
+            }
+            if item.token.is_template_body() {
+                match stack.last() {
+                    Some((Delimiter::Template, _, _)) => (),
+                    Some((open_delimiter, open, _)) => {
+                        return Err(DelimiterError {
+                            open: Some(delimiter_location(*open_delimiter, open)),
+                            close: Some(delimiter_location(Delimiter::Template, &item)),
+                        }
+                        .into())
+                    }
+                    None => {
+                        return Err(DelimiterError {
+                            open: None,
+                            close: Some(delimiter_location(Delimiter::Template, &item)),
+                        }
+                        .into())
+                    }
+                }
+            }
+            match stack.last_mut() {
+                Some((_, _, parent)) => parent.push(TokenTree::Leaf(item)),
+                None => root.push(TokenTree::Leaf(item)),
+            }
+        }
+        if let Some((delimiter, open, _)) = stack.pop() {
+            return Err(DelimiterError {
+                open: Some(delimiter_location(delimiter, &open)),
+                close: None,
+            }
+            .into());
+        }
+        Ok(root)
+    }
+}
+
+pub(crate) fn delimiter_location<T>(delimiter: Delimiter, item: &Item<T>) -> DelimiterLocation {
+    DelimiterLocation {
+        delimiter,
+        span: item.span,
+        location: item.location,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn nested_groups() {
+        let trees = TokenTree::build("f(a, [1, {b: 2}]); // done").unwrap();
+        assert_eq!(trees.len(), 4);
+        assert!(trees[0].is_leaf());
+        let call = match &trees[1] {
+            TokenTree::Group(g) => g,
+            _ => panic!("expected a group: {:?}", trees[1]),
+        };
+        assert_eq!(call.delimiter, Delimiter::Paren);
+        assert_eq!(call.span(), Span::new(1, 17));
+        assert_eq!(call.children.len(), 3);
+        let array = match &call.children[2] {
+            TokenTree::Group(g) => g,
+            _ => panic!("expected a group: {:?}", call.children[2]),
+        };
+        assert_eq!(array.delimiter, Delimiter::Bracket);
+        assert!(array.children[2].is_group());
+        assert!(trees[3].is_leaf());
+    }
+
+    #[test]
+    fn template_groups() {
+        let trees = TokenTree::build("`a${ {b: 1} }c${ `d${e}` }f`").unwrap();
+        assert_eq!(trees.len(), 1);
+        let template = match &trees[0] {
+            TokenTree::Group(g) => g,
+            _ => panic!("expected a group: {:?}", trees[0]),
+        };
+        assert_eq!(template.delimiter, Delimiter::Template);
+        assert_eq!(template.children.len(), 3);
+        assert!(template.children[0].is_group());
+        assert!(template.children[1].is_leaf());
+        match &template.children[2] {
+            TokenTree::Group(g) => assert_eq!(g.delimiter, Delimiter::Template),
+            _ => panic!("expected a group: {:?}", template.children[2]),
+        }
+    }
+
+    #[test]
+    fn mismatched() {
+        let err = TokenTree::build("(\n  a]").unwrap_err();
+        let err = match err {
+            TokenTreeError::Delimiter(e) => e,
+            _ => panic!("expected delimiter error: {:?}", err),
+        };
+        let open = err.open.unwrap();
+        assert_eq!(open.delimiter, Delimiter::Paren);
+        assert_eq!(open.span, Span::new(0, 1));
+        let close = err.close.unwrap();
+        assert_eq!(close.delimiter, Delimiter::Bracket);
+        assert_eq!(close.span, Span::new(5, 6));
+        assert_eq!(
+            err.to_string(),
+            "mismatched delimiters, ( at 1:1 closed by ] at 2:4"
+        );
+    }
+
+    #[test]
+    fn mismatched_scanner_delimiters() {
+        for (js, open, close) in &[
+            ("( }", Delimiter::Paren, Delimiter::Brace),
+            ("[ }", Delimiter::Bracket, Delimiter::Brace),
+            ("`${ ) }`", Delimiter::Template, Delimiter::Paren),
+        ] {
+            let err = match TokenTree::build(js).unwrap_err() {
+                TokenTreeError::Delimiter(e) => e,
+                err => panic!("expected delimiter error for {:?}: {:?}", js, err),
+            };
+            assert_eq!(err.open.unwrap().delimiter, *open, "{:?}", js);
+            assert_eq!(err.open.unwrap().span.start, 0, "{:?}", js);
+            assert_eq!(err.close.unwrap().delimiter, *close, "{:?}", js);
+        }
+    }
+
+    #[test]
+    fn unclosed() {
+        let err = TokenTree::build("[1, 2").unwrap_err();
+        assert_eq!(err.to_string(), "unclosed delimiter [ at 1:1");
+        let err = TokenTree::build("1]").unwrap_err();
+        assert_eq!(err.to_string(), "unmatched delimiter ] at 1:2");
+    }
+}
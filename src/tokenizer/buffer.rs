@@ -1,4 +1,5 @@
-use std::char;
+use super::unicode::{is_space_separator, IDENT_CLASS, ID_CONTINUE, SLOW_PATH};
+use core::{char, convert::TryInto};
 #[derive(Clone)]
 pub struct JSBuffer<'a> {
     pub buffer: &'a [u8],
@@ -7,6 +8,10 @@ pub struct JSBuffer<'a> {
 }
 const CONT_MASK: u8 = 0b0011_1111;
 const TAG_CONT_U8: u8 = 0b1000_0000;
+/// `0x01` repeated in every byte of a word
+const LO_BYTES: u64 = 0x0101_0101_0101_0101;
+/// `0x80` repeated in every byte of a word
+const HI_BYTES: u64 = 0x8080_8080_8080_8080;
 /// Re-implementation of
 /// the std::str::Chars logic
 impl<'a> JSBuffer<'a> {
@@ -171,35 +176,15 @@ impl<'a> JSBuffer<'a> {
         if self.at_end() {
             return false;
         }
-        self.buffer[self.idx] == 9 //\t
-            || self.buffer[self.idx] == 10 // \n
-            || self.buffer[self.idx] == 11 // \u{000b}
-            || self.buffer[self.idx] == 12 // \f
-            || self.buffer[self.idx] == 13 // \r
-            || self.buffer[self.idx] == 32 // ' '
-            || (self.buffer[self.idx] == 194 && self.idx + 1 < self.len && self.buffer[self.idx+1] == 160)
-            || (self.buffer[self.idx] >= 226 && self.buffer[self.idx] <= 239 && self.len > self.idx + 2 && {
-                match &self.buffer[self.idx..self.idx+3] {
-                    [239, 187, 191] //"\u{feff}",
-                    | [226, 128, 168] //"\u{2028}",
-                    | [226, 128, 169] //"\u{2029}",
-                    | [226, 128, 128] //"\u{2000}",
-                    | [226, 128, 129] //"\u{2001}",
-                    | [226, 128, 130] //"\u{2002}",
-                    | [226, 128, 131] //"\u{2003}",
-                    | [226, 128, 132] //"\u{2004}",
-                    | [226, 128, 133] //"\u{2005}",
-                    | [226, 128, 134] //"\u{2006}",
-                    | [226, 128, 135] //"\u{2007}",
-                    | [226, 128, 136] //"\u{2008}",
-                    | [226, 128, 137] //"\u{2009}",
-                    | [226, 128, 138] //"\u{200a}",
-                    | [226, 128, 175] //"\u{202f}",
-                    | [226, 129, 159] //"\u{205f}",
-                    | [227, 128, 128] => true,  //"\u{3000}",
-                    _ => false,
-                }
-            } )
+        match self.buffer[self.idx] {
+            9 | 10 | 11 | 12 | 13 | 32 => true,
+            b if b < 0x80 => false,
+            _ => match self.peek_char() {
+                Some('\u{feff}') | Some('\u{2028}') | Some('\u{2029}') => true,
+                Some(c) => is_space_separator(c),
+                None => false,
+            },
+        }
     }
     /// Check of the look ahead character is
     /// a valid js new line character
@@ -284,6 +269,68 @@ impl<'a> JSBuffer<'a> {
     pub fn skip_back_bytes(&mut self, count: usize) {
         self.idx -= count;
     }
+    /// Skip forward over any ASCII bytes not in `stops`, stopping
+    /// at the first byte in `stops`, the first non-ASCII byte or
+    /// the end, returning the number of bytes skipped
+    ///
+    /// Since only ASCII is skipped, the number of bytes is also
+    /// the number of characters and the buffer stays aligned.
+    /// Whole words are checked at once for as long as possible.
+    #[inline]
+    pub fn skip_ascii_until(&mut self, stops: &[u8]) -> usize {
+        self.skip_ascii_until_before(stops, self.len)
+    }
+    /// `skip_ascii_until` without going past the byte index `end`
+    #[inline]
+    pub fn skip_ascii_until_before(&mut self, stops: &[u8], end: usize) -> usize {
+        let start = self.idx;
+        while self.idx + 8 <= end {
+            let word = u64::from_le_bytes(
+                self.buffer[self.idx..self.idx + 8]
+                    .try_into()
+                    .expect("slice is 8 bytes"),
+            );
+            if word & HI_BYTES != 0 || stops.iter().any(|&b| Self::word_has_byte(word, b)) {
+                break;
+            }
+            self.idx += 8;
+        }
+        while self.idx < end {
+            let b = self.buffer[self.idx];
+            if b >= 0x80 || stops.contains(&b) {
+                break;
+            }
+            self.idx += 1;
+        }
+        self.idx - start
+    }
+    /// Skip forward over any ASCII identifier characters
+    /// (`a-z`, `A-Z`, `0-9`, `_` and `$`), returning the number
+    /// of bytes skipped
+    #[inline]
+    pub fn skip_ascii_ident(&mut self) -> usize {
+        let start = self.idx;
+        while !self.at_end()
+            && IDENT_CLASS[self.buffer[self.idx] as usize] & (ID_CONTINUE | SLOW_PATH)
+                == ID_CONTINUE
+        {
+            self.idx += 1;
+        }
+        self.idx - start
+    }
+    /// Check if the look ahead byte is a `\\` or
+    /// not ASCII, either of which might continue an
+    /// identifier but need the slow path to be sure
+    #[inline]
+    pub fn at_ident_slow_path(&self) -> bool {
+        !self.at_end() && IDENT_CLASS[self.buffer[self.idx] as usize] & SLOW_PATH != 0
+    }
+    /// Check if any byte in `word` is equal to `b`
+    #[inline]
+    fn word_has_byte(word: u64, b: u8) -> bool {
+        let x = word ^ (LO_BYTES * u64::from(b));
+        x.wrapping_sub(LO_BYTES) & !x & HI_BYTES != 0
+    }
 }
 
 impl<'a> From<&'a str> for JSBuffer<'a> {
@@ -360,6 +407,7 @@ mod test {
             226, 128, 175, // \u{202F}
             226, 129, 159, // \u{205F}
             227, 128, 128, // \u{3000}
+            225, 154, 128, // \u{1680}
         ];
         let mut buf = JSBuffer::new(whitespaces);
         while !buf.at_end() {
@@ -435,4 +483,50 @@ mod test {
             let _ = buf.next_char();
         }
     }
+
+    #[test]
+    fn skip_ascii_until() {
+        let js = "a long run of comment text */ after";
+        let mut buf = JSBuffer::from(js);
+        assert_eq!(buf.skip_ascii_until(b"*\r\n"), 27);
+        assert!(buf.look_ahead_matches(b"*/"));
+        assert_eq!(buf.skip_ascii_until(b"*"), 0);
+        buf.skip_bytes(1);
+        assert_eq!(buf.skip_ascii_until(b"*"), js.len() - 28);
+        assert!(buf.at_end());
+        let mut buf = JSBuffer::from(js);
+        assert_eq!(buf.skip_ascii_until_before(b"*", 20), 20);
+        assert_eq!(buf.skip_ascii_until_before(b"*", 20), 0);
+        assert_eq!(buf.skip_ascii_until_before(b"*", js.len()), 7);
+        // every stop position across the word boundaries
+        for stop in 0..20 {
+            let mut s = "x".repeat(stop);
+            s.push('\'');
+            s.push_str("xxxxxxxxxxxx");
+            let mut buf = JSBuffer::from(s.as_str());
+            assert_eq!(buf.skip_ascii_until(b"'\\\r\n"), stop);
+        }
+    }
+    #[test]
+    fn skip_ascii_until_non_ascii() {
+        let js = "abcdefghij\u{2028}k";
+        let mut buf = JSBuffer::from(js);
+        assert_eq!(buf.skip_ascii_until(b"\n"), 10);
+        assert_eq!(buf.next_char(), Some('\u{2028}'));
+        assert_eq!(buf.skip_ascii_until(b"\n"), 1);
+    }
+    #[test]
+    fn skip_ascii_ident() {
+        let mut buf = JSBuffer::from("a_$1Z + b");
+        assert_eq!(buf.skip_ascii_ident(), 5);
+        assert_eq!(buf.skip_ascii_ident(), 0);
+        assert!(!buf.at_ident_slow_path());
+        assert_eq!(buf.next_char(), Some(' '));
+        let mut buf = JSBuffer::from("ab\\u0063 añ");
+        assert_eq!(buf.skip_ascii_ident(), 2);
+        assert!(buf.at_ident_slow_path());
+        buf.skip_bytes(7);
+        assert_eq!(buf.skip_ascii_ident(), 1);
+        assert!(buf.at_ident_slow_path());
+    }
 }
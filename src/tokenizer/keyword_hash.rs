@@ -0,0 +1,127 @@
+use crate::tokenizer::{RawKeyword, RawToken};
+
+/// Every reserved word and the token it produces
+static RESERVED: [(&str, RawToken); 46] = [
+    ("await", RawToken::Keyword(RawKeyword::Await)),
+    ("break", RawToken::Keyword(RawKeyword::Break)),
+    ("case", RawToken::Keyword(RawKeyword::Case)),
+    ("catch", RawToken::Keyword(RawKeyword::Catch)),
+    ("class", RawToken::Keyword(RawKeyword::Class)),
+    ("const", RawToken::Keyword(RawKeyword::Const)),
+    ("continue", RawToken::Keyword(RawKeyword::Continue)),
+    ("debugger", RawToken::Keyword(RawKeyword::Debugger)),
+    ("default", RawToken::Keyword(RawKeyword::Default)),
+    ("delete", RawToken::Keyword(RawKeyword::Delete)),
+    ("do", RawToken::Keyword(RawKeyword::Do)),
+    ("else", RawToken::Keyword(RawKeyword::Else)),
+    ("enum", RawToken::Keyword(RawKeyword::Enum)),
+    ("export", RawToken::Keyword(RawKeyword::Export)),
+    ("extends", RawToken::Keyword(RawKeyword::Extends)),
+    ("false", RawToken::Boolean(false)),
+    ("finally", RawToken::Keyword(RawKeyword::Finally)),
+    ("for", RawToken::Keyword(RawKeyword::For)),
+    ("function", RawToken::Keyword(RawKeyword::Function)),
+    ("if", RawToken::Keyword(RawKeyword::If)),
+    ("implements", RawToken::Keyword(RawKeyword::Implements)),
+    ("import", RawToken::Keyword(RawKeyword::Import)),
+    ("in", RawToken::Keyword(RawKeyword::In)),
+    ("instanceof", RawToken::Keyword(RawKeyword::InstanceOf)),
+    ("interface", RawToken::Keyword(RawKeyword::Interface)),
+    ("let", RawToken::Keyword(RawKeyword::Let)),
+    ("new", RawToken::Keyword(RawKeyword::New)),
+    ("null", RawToken::Null),
+    ("package", RawToken::Keyword(RawKeyword::Package)),
+    ("private", RawToken::Keyword(RawKeyword::Private)),
+    ("protected", RawToken::Keyword(RawKeyword::Protected)),
+    ("public", RawToken::Keyword(RawKeyword::Public)),
+    ("return", RawToken::Keyword(RawKeyword::Return)),
+    ("static", RawToken::Keyword(RawKeyword::Static)),
+    ("super", RawToken::Keyword(RawKeyword::Super)),
+    ("switch", RawToken::Keyword(RawKeyword::Switch)),
+    ("this", RawToken::Keyword(RawKeyword::This)),
+    ("throw", RawToken::Keyword(RawKeyword::Throw)),
+    ("true", RawToken::Boolean(true)),
+    ("try", RawToken::Keyword(RawKeyword::Try)),
+    ("typeof", RawToken::Keyword(RawKeyword::TypeOf)),
+    ("var", RawToken::Keyword(RawKeyword::Var)),
+    ("void", RawToken::Keyword(RawKeyword::Void)),
+    ("while", RawToken::Keyword(RawKeyword::While)),
+    ("with", RawToken::Keyword(RawKeyword::With)),
+    ("yield", RawToken::Keyword(RawKeyword::Yield)),
+];
+
+const TABLE_SIZE: usize = 128;
+const EMPTY: u8 = u8::MAX;
+/// The index into `RESERVED` for each hash, built at compile
+/// time, a collision in `hash` will fail the build
+static TABLE: [u8; TABLE_SIZE] = build_table();
+
+/// A perfect hash over `RESERVED` using the first
+/// two bytes and the length of a word
+#[inline]
+const fn hash(first: u8, second: u8, len: usize) -> usize {
+    ((first as usize + second as usize) * 4 + len * 3) & (TABLE_SIZE - 1)
+}
+
+const fn build_table() -> [u8; TABLE_SIZE] {
+    let mut table = [EMPTY; TABLE_SIZE];
+    let mut i = 0;
+    while i < RESERVED.len() {
+        let word = RESERVED[i].0.as_bytes();
+        let slot = hash(word[0], word[1], word.len());
+        if table[slot] != EMPTY {
+            panic!("reserved word hash collision");
+        }
+        table[slot] = i as u8;
+        i += 1;
+    }
+    table
+}
+
+/// Look up an ASCII identifier in the reserved words,
+/// returning the token for it if it is one
+#[inline]
+pub(crate) fn reserved_word(ident: &[u8]) -> Option<RawToken> {
+    if ident.len() < 2 || ident.len() > 10 {
+        return None;
+    }
+    let slot = TABLE[hash(ident[0], ident[1], ident.len())];
+    let (word, tok) = RESERVED.get(slot as usize)?;
+    if word.as_bytes() == ident {
+        Some(*tok)
+    } else {
+        None
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn all_reserved_words() {
+        for (word, tok) in RESERVED.iter() {
+            assert_eq!(reserved_word(word.as_bytes()), Some(*tok), "{}", word);
+            let not = format!("{}_", word);
+            assert_eq!(reserved_word(not.as_bytes()), None, "{}", not);
+            assert_eq!(reserved_word(&word.as_bytes()[1..]), None, "{}", word);
+        }
+    }
+
+    #[test]
+    fn not_reserved_words() {
+        for ident in &[
+            "",
+            "a",
+            "x",
+            "If",
+            "Await",
+            "instanceOf",
+            "yields",
+            "of",
+            "get",
+        ] {
+            assert_eq!(reserved_word(ident.as_bytes()), None, "{}", ident);
+        }
+    }
+}
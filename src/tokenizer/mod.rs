@@ -1,16 +1,30 @@
 use crate::tokens::{CommentKind, NumberKind, Punct};
-use crate::{is_line_term, OpenCurlyKind};
+use crate::{
+    error::ErrorKind, is_line_term, lex_state::OpenToken, options::Proposals, Limits, OpenCurlyKind,
+};
+use alloc::{
+    string::{String, ToString},
+    vec::Vec,
+};
 mod buffer;
 
 mod tokens;
 mod unicode;
+mod unicode_tables;
 pub use self::tokens::{RawKeyword, RawToken, StringKind, TemplateKind};
+pub(crate) use self::unicode::is_space_separator;
+pub use self::unicode_tables::UNICODE_VERSION;
 use crate::error::RawError;
 pub(crate) type Res<T> = Result<T, RawError>;
 pub use buffer::JSBuffer;
 use log::trace;
+mod keyword_hash;
 mod keyword_trie;
 
+/// How many bytes can be skipped at once between
+/// checks of a tokenizer's `limits`
+const LIMITS_CHUNK: usize = 1024;
+
 /// A Raw version of the Scanner's `Item`
 /// simply providing the start and end of the
 /// span and the type of token that span
@@ -29,6 +43,31 @@ pub struct Tokenizer<'a> {
     pub(super) stream: buffer::JSBuffer<'a>,
     pub(super) current_start: usize,
     pub(super) curly_stack: Vec<OpenCurlyKind>,
+    /// If index 0 is the start of the source text,
+    /// the only place a hashbang comment can appear
+    pub(super) at_source_start: bool,
+    /// If `<!--` and `-->` start single line comments,
+    /// which is not allowed in modules
+    pub(crate) html_comments: bool,
+    /// The proposed syntax to scan
+    pub(crate) proposals: Proposals,
+    /// If a token cut off by the end of the text should be
+    /// returned instead of an error, for scanning one line
+    /// of a larger text at a time
+    pub(crate) partial: bool,
+    /// The token the text starts in the middle of
+    pub(crate) resume_in: Option<OpenToken>,
+    /// If the last token was the rest of `resume_in`
+    pub(crate) continued: bool,
+    /// The token the text ends in the middle of
+    pub(crate) open: Option<OpenToken>,
+    /// The `Limits` checked while scanning the tokens
+    /// that can run on until the end of the text
+    pub(crate) limits: Option<Limits>,
+    /// The byte index `limits` has been checked up to
+    limits_idx: usize,
+    /// The kind of the last error if it came from `limits`
+    pub(crate) limit_hit: Option<ErrorKind>,
 }
 
 impl<'a> Tokenizer<'a> {
@@ -40,12 +79,36 @@ impl<'a> Tokenizer<'a> {
             current_start: 0,
             stream: stream.into(),
             curly_stack: Vec::with_capacity(2),
+            at_source_start: true,
+            html_comments: true,
+            proposals: Proposals::default(),
+            partial: false,
+            resume_in: None,
+            continued: false,
+            open: None,
+            limits: None,
+            limits_idx: 0,
+            limit_hit: None,
         }
     }
     /// Get the next raw token from the js text
     pub fn next(&mut self, allow_html_comment_close: bool) -> Res<RawItem> {
         trace!("next {} {}", self.stream.idx, self.stream.len);
         self.current_start = self.stream.idx;
+        self.continued = false;
+        if !self.stream.at_end() {
+            self.open = None;
+        }
+        if self.current_start == 0 {
+            if let Some(open) = self.resume_in {
+                if self.stream.at_end() {
+                    self.open = Some(open);
+                } else {
+                    self.continued = true;
+                    return self.resume(open);
+                }
+            }
+        }
         let next_char = match self.stream.next_char() {
             Some(ch) => ch,
             None => {
@@ -58,7 +121,7 @@ impl<'a> Tokenizer<'a> {
         };
 
         if next_char == '"' || next_char == '\'' {
-            return self.string(next_char);
+            return self.string(next_char, 1);
         }
         if next_char == '(' || next_char == ')' || next_char == ';' {
             return self.punct(next_char, allow_html_comment_close);
@@ -67,17 +130,28 @@ impl<'a> Tokenizer<'a> {
             return self.number(next_char);
         }
         if next_char == '`' {
-            return self.template(next_char);
+            return self.template(next_char, 1);
         }
         if next_char == '}' && self.curly_stack.last() == Some(&OpenCurlyKind::Template) {
             self.curly_stack.pop();
-            return self.template(next_char);
+            return self.template(next_char, 1);
         }
         if Self::is_id_start(next_char) {
             return self.ident(next_char);
         }
         self.punct(next_char, allow_html_comment_close)
     }
+    /// Scan the rest of a token the text started in the middle of
+    fn resume(&mut self, open: OpenToken) -> Res<RawItem> {
+        match open {
+            // the comment has already crossed a new line
+            OpenToken::Comment => self.multi_comment(true, 0),
+            OpenToken::Template { head: true } => self.template('`', 0),
+            OpenToken::Template { head: false } => self.template('}', 0),
+            OpenToken::String { double: true } => self.string('"', 0),
+            OpenToken::String { double: false } => self.string('\'', 0),
+        }
+    }
     /// get the next regex token from the js text, providing
     /// the length of the already consumed token (this will be either 1 or 2)
     ///
@@ -93,6 +167,7 @@ impl<'a> Tokenizer<'a> {
         }
         let mut in_class = false;
         while let Some(c) = self.stream.next_char() {
+            self.check_limits(start_len)?;
             if end_of_body {
                 if c == '\\' {
                     if self.look_ahead_byte_matches('u') {
@@ -160,7 +235,19 @@ impl<'a> Tokenizer<'a> {
             self.current_start,
             self.stream.idx
         );
-
+        // an all ASCII identifier can be checked
+        // against the reserved words in one look up,
+        // anything else takes the escape aware path below
+        if start != '\\' && start.is_ascii() {
+            let after_start = self.stream.idx;
+            self.stream.skip_ascii_ident();
+            if !self.stream.at_ident_slow_path() {
+                let ident = &self.stream.buffer[self.current_start..self.stream.idx];
+                let tok = keyword_hash::reserved_word(ident).unwrap_or(RawToken::Ident);
+                return self.gen_token(tok);
+            }
+            self.stream.idx = after_start;
+        }
         let start = if start == '\\' {
             let c = self.escaped_ident_part()?;
             if !Self::is_id_start(c) {
@@ -177,7 +264,13 @@ impl<'a> Tokenizer<'a> {
         if let Some(tok) = self.keyword(start)? {
             return self.gen_token(tok);
         }
-        while let Some(c) = self.stream.next_char() {
+        loop {
+            self.stream.skip_ascii_ident();
+            let c = if let Some(c) = self.stream.next_char() {
+                c
+            } else {
+                break;
+            };
             if c == '\\' {
                 let c = self.escaped_ident_part()?;
                 if !Self::is_id_continue(c) {
@@ -216,7 +309,7 @@ impl<'a> Tokenizer<'a> {
                     idx: self.current_start,
                 });
             };
-            if let Some(c) = std::char::from_u32(x) {
+            if let Some(c) = core::char::from_u32(x) {
                 Ok(c)
             } else {
                 Err(RawError {
@@ -308,8 +401,9 @@ impl<'a> Tokenizer<'a> {
         Ok(code)
     }
     /// Parse a string literal, the provided `quote` should be `'` or `"`
-    /// to signal where the end of the string might be
-    fn string(&mut self, quote: char) -> Res<RawItem> {
+    /// to signal where the end of the string might be, `last_len`
+    /// is the length of what has already been skipped
+    fn string(&mut self, quote: char, mut last_len: usize) -> Res<RawItem> {
         trace!(
             "string {} ({}, {})",
             quote,
@@ -317,12 +411,20 @@ impl<'a> Tokenizer<'a> {
             self.stream.idx
         );
         let mut escaped = false;
-        // we already skipped the quote char
-        // so 1 is appropriate
-        let mut last_len = 1usize;
         let mut new_line_count = 0usize;
         let mut found_octal_escape = false;
-        while let Some(c) = self.stream.next_char() {
+        let stops = [quote as u8, b'\\', b'\r', b'\n'];
+        loop {
+            if !escaped {
+                let skipped = self.skip_ascii_until(&stops)?;
+                last_len = last_len.saturating_add(skipped);
+            }
+            let c = if let Some(c) = self.stream.next_char() {
+                c
+            } else {
+                break;
+            };
+            self.check_limits(0)?;
             if c == '\\' {
                 escaped = !escaped;
                 last_len = last_len.saturating_add(1);
@@ -358,13 +460,8 @@ impl<'a> Tokenizer<'a> {
             } else if c == quote {
                 last_len = last_len.saturating_add(1);
                 if !escaped {
-                    let kind = if quote == '"' {
-                        StringKind::Double
-                    } else {
-                        StringKind::Single
-                    };
                     return self.gen_token(RawToken::String {
-                        kind,
+                        kind: Self::string_kind(quote),
                         new_line_count,
                         last_len,
                         found_octal_escape,
@@ -399,6 +496,18 @@ impl<'a> Tokenizer<'a> {
                 escaped = false;
             }
         }
+        if self.partial && new_line_count > 0 && last_len == 0 {
+            // the text ended on an escaped new line
+            self.open = Some(OpenToken::String {
+                double: quote == '"',
+            });
+            return self.gen_token(RawToken::String {
+                kind: Self::string_kind(quote),
+                new_line_count,
+                last_len,
+                found_octal_escape,
+            });
+        }
         // back up one to avoid splitting a unicode
         // sequence
         let _ = self.stream.prev_char();
@@ -407,6 +516,14 @@ impl<'a> Tokenizer<'a> {
             idx: self.stream.idx,
         })
     }
+    #[inline]
+    fn string_kind(quote: char) -> StringKind {
+        if quote == '"' {
+            StringKind::Double
+        } else {
+            StringKind::Single
+        }
+    }
     /// Parse a punctuation mark or sequence the `c` provided is the
     /// first character in the possible sequence
     ///
@@ -421,13 +538,13 @@ impl<'a> Tokenizer<'a> {
             ',' => self.gen_punct(Punct::Comma),
             '[' => self.gen_punct(Punct::OpenBracket),
             ']' => self.gen_punct(Punct::CloseBracket),
-            ':' => self.gen_punct(Punct::Colon),
+            ':' => self.colon(),
             '?' => self.gen_punct(Punct::QuestionMark),
             '#' => self.hash(),
             '~' => self.gen_punct(Punct::Tilde),
             '{' => self.open_curly(OpenCurlyKind::Block, Punct::OpenBrace),
             '}' => self.close_curly(Punct::CloseBrace),
-            '@' => self.gen_punct(Punct::AtMark),
+            '@' => self.at_mark(),
             '.' => self.period(),
             '>' => self.greater_than(),
             '<' => self.less_than(),
@@ -526,7 +643,7 @@ impl<'a> Tokenizer<'a> {
         } else if self.look_ahead_byte_matches('<') {
             self.stream.skip_bytes(1);
             self.gen_punct(Punct::DoubleLessThan)
-        } else if self.look_ahead_matches("!--") {
+        } else if self.html_comments && self.look_ahead_matches("!--") {
             self.stream.skip_bytes(3);
             self.html_comment()
         } else {
@@ -605,6 +722,9 @@ impl<'a> Tokenizer<'a> {
         } else if self.look_ahead_byte_matches('=') {
             self.stream.skip_bytes(1);
             self.gen_punct(Punct::PipeEqual)
+        } else if self.proposals.pipeline && self.look_ahead_byte_matches('>') {
+            self.stream.skip_bytes(1);
+            self.gen_punct(Punct::PipeGreaterThan)
         } else {
             self.gen_punct(Punct::Pipe)
         }
@@ -629,7 +749,7 @@ impl<'a> Tokenizer<'a> {
         trace!("minus ({}, {})", self.current_start, self.stream.idx);
         if self.look_ahead_byte_matches('-') {
             self.stream.skip_bytes(1);
-            if allow_html_comment_close && self.look_ahead_byte_matches('>') {
+            if allow_html_comment_close && self.html_comments && self.look_ahead_byte_matches('>') {
                 self.single_comment(CommentKind::Html)
             } else {
                 self.gen_punct(Punct::DoubleDash)
@@ -655,7 +775,7 @@ impl<'a> Tokenizer<'a> {
             self.gen_punct(Punct::ForwardSlashEqual)
         } else if self.look_ahead_byte_matches('*') {
             self.stream.skip_bytes(1);
-            self.multi_comment(allow_html_comment_close)
+            self.multi_comment(allow_html_comment_close, 2)
         } else if self.look_ahead_byte_matches('/') {
             self.single_comment(CommentKind::Single)
         } else {
@@ -688,18 +808,49 @@ impl<'a> Tokenizer<'a> {
     #[inline]
     fn hash(&mut self) -> Res<RawItem> {
         trace!("hash ({}, {})", self.current_start, self.stream.idx);
-        // hashbang comment can only appear at the start
-        if self.current_start == 0 && self.look_ahead_byte_matches('!') {
+        // hashbang comment can only appear at the start,
+        // after a byte order mark if there is one
+        let at_start = self.current_start == 0
+            || (self.current_start == 3 && self.stream.buffer.starts_with(b"\xEF\xBB\xBF"));
+        if self.at_source_start && at_start && self.look_ahead_byte_matches('!') {
             while !self.at_new_line() {
                 if self.stream.next_char().is_none() {
                     break;
                 }
             }
             self.gen_comment(CommentKind::Hashbang, 0, 0, self.local_index())
+        } else if self.proposals.records_and_tuples && self.look_ahead_byte_matches('{') {
+            self.stream.skip_bytes(1);
+            self.open_curly(OpenCurlyKind::Block, Punct::HashOpenBrace)
+        } else if self.proposals.records_and_tuples && self.look_ahead_byte_matches('[') {
+            self.stream.skip_bytes(1);
+            self.gen_punct(Punct::HashOpenBracket)
         } else {
             self.gen_punct(Punct::Hash)
         }
     }
+    /// a `:` could be `::` when scanning the bind proposal
+    #[inline]
+    fn colon(&mut self) -> Res<RawItem> {
+        trace!("colon ({}, {})", self.current_start, self.stream.idx);
+        if self.proposals.bind && self.look_ahead_byte_matches(':') {
+            self.stream.skip_bytes(1);
+            self.gen_punct(Punct::DoubleColon)
+        } else {
+            self.gen_punct(Punct::Colon)
+        }
+    }
+    /// an `@` could be `@@` when scanning topic tokens
+    #[inline]
+    fn at_mark(&mut self) -> Res<RawItem> {
+        trace!("at_mark ({}, {})", self.current_start, self.stream.idx);
+        if self.proposals.topic_token && self.look_ahead_byte_matches('@') {
+            self.stream.skip_bytes(1);
+            self.gen_punct(Punct::DoubleAtMark)
+        } else {
+            self.gen_punct(Punct::AtMark)
+        }
+    }
     /// parse a number, this can include decimal or float literals
     /// like `0.01e1` or `10` as well as binary, octal or hex
     /// literals like `0b1`, `0o7`, or `0xf` and BigInt literals
@@ -749,9 +900,10 @@ impl<'a> Tokenizer<'a> {
 
     /// parse the string portion of a template literal
     /// the start will either be a back tick or
-    /// `${`
+    /// `${`, `last_len` is the length of what has already
+    /// been skipped
     #[inline]
-    fn template(&mut self, start: char) -> Res<RawItem> {
+    fn template(&mut self, start: char, mut last_len: usize) -> Res<RawItem> {
         trace!(
             "template {} ({}, {})",
             start,
@@ -759,11 +911,18 @@ impl<'a> Tokenizer<'a> {
             self.stream.idx
         );
         let mut line_count = 0usize;
-        let mut last_len = 1usize; // we already skipped the start char
         let mut found_octal_escape = false;
         let mut found_invalid_unicode = false;
         let mut found_invalid_hex = false;
-        while let Some(c) = self.stream.next_char() {
+        loop {
+            let skipped = self.skip_ascii_until(b"`\\$\r\n")?;
+            last_len = last_len.saturating_add(skipped);
+            let c = if let Some(c) = self.stream.next_char() {
+                c
+            } else {
+                break;
+            };
+            self.check_limits(0)?;
             last_len = last_len.saturating_add(1);
             if c == '\\' {
                 if self.look_ahead_matches("${") {
@@ -890,6 +1049,22 @@ impl<'a> Tokenizer<'a> {
                 }
             }
         }
+        if self.partial {
+            self.open = Some(OpenToken::Template { head: start == '`' });
+            let kind = if start == '`' {
+                TemplateKind::NoSub
+            } else {
+                TemplateKind::Tail
+            };
+            return self.gen_template(
+                kind,
+                line_count,
+                last_len,
+                found_octal_escape,
+                found_invalid_unicode,
+                found_invalid_hex,
+            );
+        }
         Err(RawError {
             msg: format!(
                 "unterminated template: {:?}",
@@ -907,16 +1082,23 @@ impl<'a> Tokenizer<'a> {
             self.current_start,
             self.stream.idx
         );
-        while !self.at_new_line() {
-            if self.stream.next_char().is_none() {
+        loop {
+            self.skip_ascii_until(b"\r\n")?;
+            if self.at_new_line() || self.stream.next_char().is_none() {
                 break;
             }
+            self.check_limits(0)?;
         }
         self.gen_comment(kind, 0, 0, self.local_index())
     }
-    /// parse a multi-line comment after finding `/*`
+    /// parse a multi-line comment after finding `/*`, `last_len`
+    /// is the length of what has already been skipped
     #[inline]
-    fn multi_comment(&mut self, allow_html_comment_close: bool) -> Res<RawItem> {
+    fn multi_comment(
+        &mut self,
+        allow_html_comment_close: bool,
+        mut last_len: usize,
+    ) -> Res<RawItem> {
         trace!(
             "multi_comment ({}, {}) allow_html_comment_close: {}",
             self.current_start,
@@ -924,10 +1106,17 @@ impl<'a> Tokenizer<'a> {
             allow_html_comment_close
         );
         let mut new_line_count = 0usize;
-        let mut last_len = 2usize; // we already skipped the /*
         let mut found_end = false;
         let mut end_idx = None;
-        while let Some(c) = self.stream.next_char() {
+        loop {
+            let skipped = self.skip_ascii_until(b"*\r\n")?;
+            last_len = last_len.saturating_add(skipped);
+            let c = if let Some(c) = self.stream.next_char() {
+                c
+            } else {
+                break;
+            };
+            self.check_limits(0)?;
             if c == '*' && self.look_ahead_byte_matches('/') {
                 self.stream.skip_bytes(1);
                 found_end = true;
@@ -948,16 +1137,28 @@ impl<'a> Tokenizer<'a> {
             }
         }
         if found_end {
-            if (new_line_count > 0 || allow_html_comment_close) && self.look_ahead_matches("-->") {
+            if (new_line_count > 0 || allow_html_comment_close)
+                && self.html_comments
+                && self.look_ahead_matches("-->")
+            {
                 self.stream.skip_bytes(3);
 
                 while !self.stream.at_end() && !self.at_new_line() {
                     self.stream.skip_bytes(1);
+                    self.check_limits(0)?;
                     last_len = last_len.saturating_add(1);
                 }
             }
             let end_idx = end_idx.unwrap_or_else(|| self.local_index());
             self.gen_comment(CommentKind::Multi, new_line_count, last_len, end_idx)
+        } else if self.partial {
+            self.open = Some(OpenToken::Comment);
+            self.gen_comment(
+                CommentKind::Multi,
+                new_line_count,
+                last_len,
+                self.local_index(),
+            )
         } else {
             Err(RawError {
                 idx: self.current_start,
@@ -982,6 +1183,7 @@ impl<'a> Tokenizer<'a> {
             } else {
                 self.stream.skip_bytes(1);
             }
+            self.check_limits(0)?;
         }
         if let Some(end_idx) = end_idx {
             return self.gen_comment(CommentKind::Html, 0, 0, end_idx);
@@ -1379,6 +1581,54 @@ impl<'a> Tokenizer<'a> {
         trace!("at_new_line ({}, {})", self.current_start, self.stream.idx);
         self.stream.at_new_line()
     }
+    /// Skip forward over ASCII until one of the `stops`, a
+    /// chunk at a time when there are `limits` to check so a
+    /// long run can't get past them
+    #[inline]
+    fn skip_ascii_until(&mut self, stops: &[u8]) -> Res<usize> {
+        if self.limits.is_none() {
+            return Ok(self.stream.skip_ascii_until(stops));
+        }
+        let mut skipped = 0;
+        loop {
+            let end = self.stream.len.min(self.stream.idx + LIMITS_CHUNK);
+            skipped += self.stream.skip_ascii_until_before(stops, end);
+            if self.stream.idx < end || end == self.stream.len {
+                return Ok(skipped);
+            }
+            self.check_limits(0)?;
+        }
+    }
+    /// Check the token scanned so far against any `limits`,
+    /// called from inside the loops over strings, comments,
+    /// templates and regexes, `before` is the length of the
+    /// token before `current_start`
+    #[inline]
+    fn check_limits(&mut self, before: usize) -> Res<()> {
+        if self.limits.is_none() {
+            return Ok(());
+        }
+        let len = self.local_index() + before;
+        // a peek or a recovered error can move back over
+        // bytes that were already counted
+        let scanned = self
+            .stream
+            .idx
+            .saturating_sub(self.limits_idx.max(self.current_start));
+        self.limits_idx = self.limits_idx.max(self.stream.idx);
+        let checked = match &mut self.limits {
+            Some(limits) => limits.check_partial(len, scanned),
+            None => Ok(()),
+        };
+        if let Err((kind, msg)) = checked {
+            self.limit_hit = Some(kind);
+            return Err(RawError {
+                idx: self.current_start - before,
+                msg,
+            });
+        }
+        Ok(())
+    }
     /// Get the current position inside of this token
     #[inline]
     fn local_index(&self) -> usize {
@@ -1868,6 +2118,45 @@ mod test {
         }
     }
 
+    #[test]
+    fn long_bodies_counts() {
+        let body = "some longer text with a ünicode char\nand a second line";
+        let js = format!("`{}`", body);
+        let mut t = Tokenizer::new(&js);
+        match t.next(true).unwrap().ty {
+            RawToken::Template {
+                new_line_count,
+                last_len,
+                ..
+            } => assert_eq!((new_line_count, last_len), (1, 18)),
+            _ => panic!("expected template"),
+        }
+        let js = format!("/*{}*/", body);
+        let mut t = Tokenizer::new(&js);
+        match t.next(true).unwrap().ty {
+            RawToken::Comment {
+                new_line_count,
+                last_len,
+                ..
+            } => assert_eq!((new_line_count, last_len), (1, 19)),
+            _ => panic!("expected comment"),
+        }
+        let js = format!("'{}'", body.replace('\n', "\\\n"));
+        let mut t = Tokenizer::new(&js);
+        match t.next(true).unwrap().ty {
+            RawToken::String {
+                new_line_count,
+                last_len,
+                ..
+            } => assert_eq!((new_line_count, last_len), (1, 18)),
+            _ => panic!("expected string"),
+        }
+        let js = format!("// {}", body);
+        let mut t = Tokenizer::new(&js);
+        let item = t.next(true).unwrap();
+        assert_eq!(item.end, body.find('\n').unwrap() + 3);
+    }
+
     #[test]
     fn invalid_unicode_escape_template() {
         let ts = &[r#"`\u{FFFFFFF}`"#, r#"`\u{AAA`"#, r#"`\u{AAG}`"#];
@@ -1958,6 +2247,26 @@ mod test {
         }
     }
 
+    #[test]
+    fn tokenizer_keyword_slow_path() {
+        static CASES: &[(&str, RawToken)] = &[
+            ("i\\u0066", RawToken::Keyword(RawKeyword::If)),
+            ("n\\u{75}ll", RawToken::Null),
+            ("if\\u0061", RawToken::Ident),
+            ("ifé", RawToken::Ident),
+            ("if\u{200C}", RawToken::Ident),
+            ("\\u0069f", RawToken::Keyword(RawKeyword::If)),
+        ];
+        for (js, expected) in CASES {
+            let mut t = Tokenizer::new(js);
+            let item = t.next(true).unwrap();
+            assert_eq!(&item.ty, expected, "{}", js);
+            assert!(t.stream.at_end(), "{}", js);
+        }
+        let mut t = Tokenizer::new("if é");
+        assert_eq!(t.next(true).unwrap().ty, RawToken::Keyword(RawKeyword::If));
+    }
+
     #[test]
     fn tokenizer_comments() {
         static COMMENTS: &[&str] = &[
@@ -1,4 +1,4 @@
-use crate::tokens::{CommentKind, Keyword, NumberKind, Punct};
+use crate::tokens::{CommentKind, Es3Word, Keyword, NumberKind, Punct};
 
 #[derive(PartialEq, Eq, Debug, Clone, Copy)]
 pub enum RawToken {
@@ -92,7 +92,8 @@ pub enum TemplateKind {
     Body,
     Tail,
 }
-#[derive(PartialEq, Eq, Debug, Clone, Copy)]
+#[derive(PartialEq, Eq, Debug, Clone, Copy, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum RawKeyword {
     Await,
     Break,
@@ -137,10 +138,11 @@ pub enum RawKeyword {
     While,
     With,
     Yield,
+    Es3Reserved(Es3Word),
 }
 
 impl RawKeyword {
-    pub fn with_str(self, s: &str) -> crate::tokens::Keyword<&str> {
+    pub fn with_str<T>(self, s: T) -> crate::tokens::Keyword<T> {
         match self {
             RawKeyword::Await => Keyword::Await(s),
             RawKeyword::Break => Keyword::Break(s),
@@ -185,6 +187,7 @@ impl RawKeyword {
             RawKeyword::While => Keyword::While(s),
             RawKeyword::With => Keyword::With(s),
             RawKeyword::Yield => Keyword::Yield(s),
+            RawKeyword::Es3Reserved(w) => Keyword::Es3Reserved(w, s),
         }
     }
 }
@@ -235,6 +238,7 @@ impl<T> From<&Keyword<T>> for RawKeyword {
             Keyword::While(_) => RawKeyword::While,
             Keyword::With(_) => RawKeyword::With,
             Keyword::Yield(_) => RawKeyword::Yield,
+            Keyword::Es3Reserved(w, _) => RawKeyword::Es3Reserved(*w),
         }
     }
 }
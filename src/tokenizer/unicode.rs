@@ -1,69 +1,152 @@
-#![allow(clippy::all)]
-use unicode_xid::UnicodeXID;
+use super::unicode_tables;
 
-/// wrap the `unic_ucd_ident`'s function
-/// first short-circuiting around the ascii
-/// and other non `CJK` characters
+/// The byte can start an identifier
+pub(crate) const ID_START: u8 = 1;
+/// The byte can continue an identifier
+pub(crate) const ID_CONTINUE: u8 = 1 << 1;
+/// The byte is a `\\` or is not ASCII, meaning
+/// the full escape and unicode aware checks are needed
+pub(crate) const SLOW_PATH: u8 = 1 << 2;
+
+/// The identifier class of every byte
+pub(crate) static IDENT_CLASS: [u8; 256] = ident_class_table();
+
+const fn ident_class_table() -> [u8; 256] {
+    let mut table = [0u8; 256];
+    let mut b = 0;
+    while b < 256 {
+        let byte = b as u8;
+        table[b] = if byte.is_ascii_alphabetic() || byte == b'_' || byte == b'$' {
+            ID_START | ID_CONTINUE
+        } else if byte.is_ascii_digit() {
+            ID_CONTINUE
+        } else if byte == b'\\' {
+            ID_START | ID_CONTINUE | SLOW_PATH
+        } else if byte >= 0x80 {
+            SLOW_PATH
+        } else {
+            0
+        };
+        b += 1;
+    }
+    table
+}
+
+/// If `c` is in one of the sorted `ranges`
+fn in_table(ranges: &[(char, char)], c: char) -> bool {
+    ranges
+        .binary_search_by(|&(start, end)| {
+            if c < start {
+                core::cmp::Ordering::Greater
+            } else if c > end {
+                core::cmp::Ordering::Less
+            } else {
+                core::cmp::Ordering::Equal
+            }
+        })
+        .is_ok()
+}
+
+/// If `c` has the unicode property ID_Start,
+/// short-circuiting around ascii
 #[inline]
 pub(crate) fn is_id_start(c: char) -> bool {
-    if c >= 'a' && c <= 'z' {
-        true
-    } else if c >= 'A' && c <= 'Z' {
-        true
-    } else if c == '\\' || c == '_' || c == '$' {
-        true
-    } else if c < '\u{AA}' {
-        false
-    } else if c == '\u{2118}'
-        || c == '\u{212E}'
-        || c == '\u{309B}'
-        || c == '\u{309C}'
-        || c == '\u{1885}'
-        || c == '\u{1886}'
-    {
-        true
+    if c.is_ascii() {
+        IDENT_CLASS[c as usize] & ID_START != 0
     } else {
-        UnicodeXID::is_xid_start(c)
+        in_table(unicode_tables::ID_START, c)
     }
 }
-/// wrap the `unic_ucd_ident`'s function
-/// first short-circuiting around the ascii
-/// and other non `CJK` characters
+/// If `c` has the unicode property ID_Continue or is one
+/// of the joiners ECMAScript allows in identifiers,
+/// short-circuiting around ascii
 #[inline]
 pub(crate) fn is_id_continue(c: char) -> bool {
-    if c >= 'a' && c <= 'z' {
-        true
-    } else if c >= 'A' && c <= 'Z' {
-        true
-    } else if c >= '0' && c <= '9' {
-        true
-    } else if c == '\\' || c == '_' || c == '$' {
-        true
-    } else if c < '\u{AA}' {
-        false
-    } else if c == '\u{200C}'
-        || c == '\u{200D}'
-        || c == '\u{2118}'
-        || c == '\u{212E}'
-        || c == '\u{309B}'
-        || c == '\u{309C}'
-        || c == '\u{1885}'
-        || c == '\u{1886}'
-        || c == '\u{1369}'
-        || c == '\u{136A}'
-        || c == '\u{136B}'
-        || c == '\u{136C}'
-        || c == '\u{136D}'
-        || c == '\u{136E}'
-        || c == '\u{136F}'
-        || c == '\u{1370}'
-        || c == '\u{1371}'
-        || c == '\u{B7}'
-        || c == '\u{387}'
-        || c == '\u{19DA}'
-    {
-        true
+    if c.is_ascii() {
+        IDENT_CLASS[c as usize] & ID_CONTINUE != 0
     } else {
-        UnicodeXID::is_xid_continue(c)
+        c == '\u{200C}' || c == '\u{200D}' || in_table(unicode_tables::ID_CONTINUE, c)
+    }
+}
+/// If `c` is in the unicode general category Zs
+#[inline]
+pub(crate) fn is_space_separator(c: char) -> bool {
+    c == ' ' || (!c.is_ascii() && in_table(unicode_tables::SPACE_SEPARATOR, c))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    /// Every code point listed for `property` in a UCD file
+    fn from_ucd(text: &str, property: &str) -> Vec<bool> {
+        let mut ret = vec![false; 0x11_0000];
+        for line in text.lines() {
+            let line = line.split('#').next().unwrap();
+            let (points, prop) = match line.split_once(';') {
+                Some(pair) => pair,
+                None => continue,
+            };
+            if prop.trim() != property {
+                continue;
+            }
+            let points = points.trim();
+            let (start, end) = points.split_once("..").unwrap_or((points, points));
+            let start = u32::from_str_radix(start, 16).unwrap();
+            let end = u32::from_str_radix(end, 16).unwrap();
+            for cp in start..=end {
+                ret[cp as usize] = true;
+            }
+        }
+        ret
+    }
+
+    fn every_char() -> impl Iterator<Item = char> {
+        (0..0x11_0000).filter_map(char::from_u32)
+    }
+
+    #[test]
+    fn id_start_matches_ucd() {
+        let expected = from_ucd(
+            include_str!("../../assets/ucd/DerivedCoreProperties.txt"),
+            "ID_Start",
+        );
+        for c in every_char() {
+            let expected = expected[c as usize] || c == '$' || c == '_' || c == '\\';
+            assert_eq!(is_id_start(c), expected, "{:?}", c);
+        }
+    }
+
+    #[test]
+    fn id_continue_matches_ucd() {
+        let expected = from_ucd(
+            include_str!("../../assets/ucd/DerivedCoreProperties.txt"),
+            "ID_Continue",
+        );
+        for c in every_char() {
+            let expected =
+                expected[c as usize] || matches!(c, '$' | '\\' | '\u{200C}' | '\u{200D}');
+            assert_eq!(is_id_continue(c), expected, "{:?}", c);
+        }
+    }
+
+    #[test]
+    fn space_separator_matches_ucd() {
+        let expected = from_ucd(
+            include_str!("../../assets/ucd/DerivedGeneralCategory.txt"),
+            "Zs",
+        );
+        for c in every_char() {
+            assert_eq!(is_space_separator(c), expected[c as usize], "{:?}", c);
+        }
+    }
+
+    #[test]
+    fn other_id_start() {
+        for c in [
+            '\u{1885}', '\u{1886}', '\u{2118}', '\u{212E}', '\u{309B}', '\u{309C}',
+        ] {
+            assert!(is_id_start(c), "{:?}", c);
+        }
     }
 }
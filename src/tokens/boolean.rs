@@ -1,4 +1,6 @@
+use alloc::string::{String, ToString};
 #[derive(Debug, PartialEq, Eq, Clone, Copy)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 /// The tokenized representation of `true` or `false`
 pub enum Boolean {
     True,
@@ -1,4 +1,6 @@
+use alloc::string::{String, ToString};
 #[derive(Debug, PartialEq, Eq, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 /// A comment, effectively should be treated
 /// as white space. There are 3 kinds of comments
 /// according to the specification.
@@ -6,6 +8,8 @@
 /// - Single line comments: //comment
 /// - Multi line comments: /* comment */
 /// - HTML comments: <!-- comment --> plus more!
+///
+/// `to_string` gives back the text the comment was scanned from
 pub struct Comment<T> {
     pub kind: CommentKind,
     pub content: T,
@@ -20,6 +24,15 @@ impl<T> Comment<T> {
             tail_content,
         }
     }
+    /// Convert the content and tail content
+    /// of this comment with `f`
+    pub fn map<U>(self, mut f: impl FnMut(T) -> U) -> Comment<U> {
+        Comment {
+            kind: self.kind,
+            content: f(self.content),
+            tail_content: self.tail_content.map(f),
+        }
+    }
     pub fn new_single_line(content: T) -> Self {
         Comment::from_parts(content, CommentKind::Single, None)
     }
@@ -65,16 +78,44 @@ where
     T: AsRef<str>,
 {
     fn to_string(&self) -> String {
-        match self.kind {
-            CommentKind::Single => format!("//{}", self.content.as_ref()),
-            CommentKind::Multi => format!("/*{}*/", self.content.as_ref()),
-            CommentKind::Html => format!("<!--{}-->", self.content.as_ref()),
-            CommentKind::Hashbang => format!("#!{}", self.content.as_ref()),
+        let content = self.content.as_ref();
+        let tail = self.tail_content.as_ref().map(AsRef::as_ref);
+        match (self.kind, tail) {
+            (CommentKind::Single, _) => format!("//{}", content),
+            (CommentKind::Multi, None) => format!("/*{}*/", content),
+            (CommentKind::Multi, Some(tail)) => format!("/*{}*/-->{}", content, tail),
+            (CommentKind::Html, None) => format!("<!--{}", content),
+            // a `-->` comment without an opening `<!--`
+            (CommentKind::Html, Some(tail)) if content.is_empty() => format!("-->{}", tail),
+            (CommentKind::Html, Some(tail)) => format!("<!--{}-->{}", content, tail),
+            (CommentKind::Hashbang, _) => format!("#!{}", content),
+        }
+    }
+}
+
+impl<'a> Comment<&'a str> {
+    /// Split the text of an HTML comment into its content
+    /// and the text after its last `-->`
+    ///
+    /// An opening `<!--` runs to the end of the line, if it
+    /// closes with `-->` the rest of the line is the tail. A
+    /// `-->` without an opening `<!--` has an empty content
+    /// and everything after it as the tail.
+    pub(crate) fn split_html(s: &'a str) -> (&'a str, Option<&'a str>) {
+        match s.strip_prefix("<!--") {
+            Some(body) => match body.rfind("-->") {
+                // `<!---->` can't be told apart from a lone `-->`
+                // so it is kept whole
+                Some(idx) if idx > 0 => (&body[..idx], Some(&body[idx + 3..])),
+                _ => (body, None),
+            },
+            None => ("", Some(s.strip_prefix("-->").unwrap_or(s))),
         }
     }
 }
 
 #[derive(Debug, PartialEq, Eq, Clone, Copy)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 /// The 4 kinds of comments
 pub enum CommentKind {
     Single,
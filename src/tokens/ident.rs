@@ -1,4 +1,8 @@
-#[derive(Debug, PartialEq, Eq, Clone)]
+use crate::{Interned, Symbol};
+use alloc::string::{String, ToString};
+
+#[derive(Debug, PartialEq, Eq, Clone, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 /// An identifier
 pub struct Ident<T>(T);
 
@@ -20,6 +24,21 @@ where
     }
 }
 
+impl<T> Ident<T> {
+    /// Convert the text of this identifier with `f`
+    pub fn map<U>(self, f: impl FnOnce(T) -> U) -> Ident<U> {
+        Ident(f(self.0))
+    }
+}
+
+impl Ident<Interned<'_>> {
+    /// The interned name of this identifier, always
+    /// `Some` for one from an `InterningScanner`
+    pub fn symbol(&self) -> Option<Symbol> {
+        self.0.symbol()
+    }
+}
+
 impl<'a> From<&'a str> for Ident<&'a str> {
     fn from(s: &'a str) -> Self {
         Ident(s)
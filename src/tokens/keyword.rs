@@ -1,4 +1,6 @@
+use alloc::string::{String, ToString};
 #[derive(Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 /// A JS Keyword
 ///
 /// # Standard
@@ -45,6 +47,8 @@
 /// interface
 /// private (40)
 /// public
+/// # ES3 Reserved
+/// Only when targeting `EsVersion::Es3`, see `Es3Word`
 pub enum Keyword<T> {
     Await(T),
     Break(T),
@@ -89,6 +93,77 @@ pub enum Keyword<T> {
     While(T),
     With(T),
     Yield(T),
+    /// A word only ES3 reserves, like `int` or `goto`
+    Es3Reserved(Es3Word, T),
+}
+
+/// The words ES3 reserves that later editions
+/// allow as identifiers
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Es3Word {
+    Abstract,
+    Boolean,
+    Byte,
+    Char,
+    Double,
+    Final,
+    Float,
+    Goto,
+    Int,
+    Long,
+    Native,
+    Short,
+    Synchronized,
+    Throws,
+    Transient,
+    Volatile,
+}
+
+impl Es3Word {
+    /// Every `Es3Word` in declaration order
+    const ALL: [Es3Word; 16] = [
+        Es3Word::Abstract,
+        Es3Word::Boolean,
+        Es3Word::Byte,
+        Es3Word::Char,
+        Es3Word::Double,
+        Es3Word::Final,
+        Es3Word::Float,
+        Es3Word::Goto,
+        Es3Word::Int,
+        Es3Word::Long,
+        Es3Word::Native,
+        Es3Word::Short,
+        Es3Word::Synchronized,
+        Es3Word::Throws,
+        Es3Word::Transient,
+        Es3Word::Volatile,
+    ];
+    /// The `Es3Word` spelled `s`, if any
+    pub fn from_word(s: &str) -> Option<Self> {
+        Self::ALL.iter().copied().find(|w| w.as_str() == s)
+    }
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Es3Word::Abstract => "abstract",
+            Es3Word::Boolean => "boolean",
+            Es3Word::Byte => "byte",
+            Es3Word::Char => "char",
+            Es3Word::Double => "double",
+            Es3Word::Final => "final",
+            Es3Word::Float => "float",
+            Es3Word::Goto => "goto",
+            Es3Word::Int => "int",
+            Es3Word::Long => "long",
+            Es3Word::Native => "native",
+            Es3Word::Short => "short",
+            Es3Word::Synchronized => "synchronized",
+            Es3Word::Throws => "throws",
+            Es3Word::Transient => "transient",
+            Es3Word::Volatile => "volatile",
+        }
+    }
 }
 
 impl<T> Clone for Keyword<T>
@@ -140,6 +215,7 @@ where
             Self::While(i) => Self::While(i.clone()),
             Self::With(i) => Self::With(i.clone()),
             Self::Yield(i) => Self::Yield(i.clone()),
+            Self::Es3Reserved(w, i) => Self::Es3Reserved(*w, i.clone()),
         }
     }
 }
@@ -147,6 +223,9 @@ where
 impl<T, U> PartialEq<Keyword<T>> for Keyword<U> {
     fn eq(&self, other: &Keyword<T>) -> bool {
         use Keyword::*;
+        if let (Es3Reserved(lhs, _), Es3Reserved(rhs, _)) = (self, other) {
+            return lhs == rhs;
+        }
         matches!(
             (self, other),
             (Await(_), Await(_))
@@ -242,6 +321,7 @@ impl Keyword<()> {
             Keyword::While(_) => Keyword::While(s),
             Keyword::With(_) => Keyword::With(s),
             Keyword::Yield(_) => Keyword::Yield(s),
+            Keyword::Es3Reserved(w, _) => Keyword::Es3Reserved(w, s),
         }
     }
 }
@@ -327,6 +407,7 @@ impl<T> Keyword<T> {
     /// - void
     /// - while
     /// - with
+    /// - any `Es3Reserved` word
     pub fn is_reserved(&self) -> bool {
         matches!(
             self,
@@ -361,6 +442,7 @@ impl<T> Keyword<T> {
                 | Keyword::Void(_)
                 | Keyword::While(_)
                 | Keyword::With(_)
+                | Keyword::Es3Reserved(_, _)
         )
     }
 
@@ -409,6 +491,7 @@ impl<T> Keyword<T> {
             Keyword::While(_) => "while",
             Keyword::With(_) => "with",
             Keyword::Yield(_) => "yield",
+            Keyword::Es3Reserved(w, _) => w.as_str(),
         }
     }
 
@@ -457,6 +540,171 @@ impl<T> Keyword<T> {
             Keyword::While(_) => Keyword::While(()),
             Keyword::With(_) => Keyword::With(()),
             Keyword::Yield(_) => Keyword::Yield(()),
+            Keyword::Es3Reserved(w, _) => Keyword::Es3Reserved(*w, ()),
+        }
+    }
+    /// Convert the text of this keyword with `f`
+    pub fn map<U>(self, f: impl FnOnce(T) -> U) -> Keyword<U> {
+        match self {
+            Keyword::Await(s) => Keyword::Await(f(s)),
+            Keyword::Break(s) => Keyword::Break(f(s)),
+            Keyword::Case(s) => Keyword::Case(f(s)),
+            Keyword::Catch(s) => Keyword::Catch(f(s)),
+            Keyword::Class(s) => Keyword::Class(f(s)),
+            Keyword::Const(s) => Keyword::Const(f(s)),
+            Keyword::Continue(s) => Keyword::Continue(f(s)),
+            Keyword::Debugger(s) => Keyword::Debugger(f(s)),
+            Keyword::Default(s) => Keyword::Default(f(s)),
+            Keyword::Import(s) => Keyword::Import(f(s)),
+            Keyword::Delete(s) => Keyword::Delete(f(s)),
+            Keyword::Do(s) => Keyword::Do(f(s)),
+            Keyword::Else(s) => Keyword::Else(f(s)),
+            Keyword::Enum(s) => Keyword::Enum(f(s)),
+            Keyword::Export(s) => Keyword::Export(f(s)),
+            Keyword::Extends(s) => Keyword::Extends(f(s)),
+            Keyword::Finally(s) => Keyword::Finally(f(s)),
+            Keyword::For(s) => Keyword::For(f(s)),
+            Keyword::Function(s) => Keyword::Function(f(s)),
+            Keyword::If(s) => Keyword::If(f(s)),
+            Keyword::In(s) => Keyword::In(f(s)),
+            Keyword::Implements(s) => Keyword::Implements(f(s)),
+            Keyword::InstanceOf(s) => Keyword::InstanceOf(f(s)),
+            Keyword::Interface(s) => Keyword::Interface(f(s)),
+            Keyword::Let(s) => Keyword::Let(f(s)),
+            Keyword::New(s) => Keyword::New(f(s)),
+            Keyword::Package(s) => Keyword::Package(f(s)),
+            Keyword::Private(s) => Keyword::Private(f(s)),
+            Keyword::Protected(s) => Keyword::Protected(f(s)),
+            Keyword::Public(s) => Keyword::Public(f(s)),
+            Keyword::Static(s) => Keyword::Static(f(s)),
+            Keyword::Return(s) => Keyword::Return(f(s)),
+            Keyword::Super(s) => Keyword::Super(f(s)),
+            Keyword::Switch(s) => Keyword::Switch(f(s)),
+            Keyword::This(s) => Keyword::This(f(s)),
+            Keyword::Throw(s) => Keyword::Throw(f(s)),
+            Keyword::Try(s) => Keyword::Try(f(s)),
+            Keyword::TypeOf(s) => Keyword::TypeOf(f(s)),
+            Keyword::Var(s) => Keyword::Var(f(s)),
+            Keyword::Void(s) => Keyword::Void(f(s)),
+            Keyword::While(s) => Keyword::While(f(s)),
+            Keyword::With(s) => Keyword::With(f(s)),
+            Keyword::Yield(s) => Keyword::Yield(f(s)),
+            Keyword::Es3Reserved(w, s) => Keyword::Es3Reserved(w, f(s)),
+        }
+    }
+}
+
+/// Every `Keyword` in declaration order
+pub(crate) static KEYWORDS: [Keyword<()>; 59] = [
+    Keyword::Await(()),
+    Keyword::Break(()),
+    Keyword::Case(()),
+    Keyword::Catch(()),
+    Keyword::Class(()),
+    Keyword::Const(()),
+    Keyword::Continue(()),
+    Keyword::Debugger(()),
+    Keyword::Default(()),
+    Keyword::Delete(()),
+    Keyword::Do(()),
+    Keyword::Else(()),
+    Keyword::Enum(()),
+    Keyword::Export(()),
+    Keyword::Extends(()),
+    Keyword::Finally(()),
+    Keyword::For(()),
+    Keyword::Function(()),
+    Keyword::If(()),
+    Keyword::Implements(()),
+    Keyword::Import(()),
+    Keyword::In(()),
+    Keyword::InstanceOf(()),
+    Keyword::Interface(()),
+    Keyword::Let(()),
+    Keyword::New(()),
+    Keyword::Package(()),
+    Keyword::Private(()),
+    Keyword::Protected(()),
+    Keyword::Public(()),
+    Keyword::Return(()),
+    Keyword::Static(()),
+    Keyword::Super(()),
+    Keyword::Switch(()),
+    Keyword::This(()),
+    Keyword::Throw(()),
+    Keyword::Try(()),
+    Keyword::TypeOf(()),
+    Keyword::Var(()),
+    Keyword::Void(()),
+    Keyword::While(()),
+    Keyword::With(()),
+    Keyword::Yield(()),
+    Keyword::Es3Reserved(Es3Word::Abstract, ()),
+    Keyword::Es3Reserved(Es3Word::Boolean, ()),
+    Keyword::Es3Reserved(Es3Word::Byte, ()),
+    Keyword::Es3Reserved(Es3Word::Char, ()),
+    Keyword::Es3Reserved(Es3Word::Double, ()),
+    Keyword::Es3Reserved(Es3Word::Final, ()),
+    Keyword::Es3Reserved(Es3Word::Float, ()),
+    Keyword::Es3Reserved(Es3Word::Goto, ()),
+    Keyword::Es3Reserved(Es3Word::Int, ()),
+    Keyword::Es3Reserved(Es3Word::Long, ()),
+    Keyword::Es3Reserved(Es3Word::Native, ()),
+    Keyword::Es3Reserved(Es3Word::Short, ()),
+    Keyword::Es3Reserved(Es3Word::Synchronized, ()),
+    Keyword::Es3Reserved(Es3Word::Throws, ()),
+    Keyword::Es3Reserved(Es3Word::Transient, ()),
+    Keyword::Es3Reserved(Es3Word::Volatile, ()),
+];
+
+impl<T> Keyword<T> {
+    /// Where this keyword is in `KEYWORDS`
+    pub(crate) fn index(&self) -> usize {
+        match self {
+            Keyword::Await(_) => 0,
+            Keyword::Break(_) => 1,
+            Keyword::Case(_) => 2,
+            Keyword::Catch(_) => 3,
+            Keyword::Class(_) => 4,
+            Keyword::Const(_) => 5,
+            Keyword::Continue(_) => 6,
+            Keyword::Debugger(_) => 7,
+            Keyword::Default(_) => 8,
+            Keyword::Delete(_) => 9,
+            Keyword::Do(_) => 10,
+            Keyword::Else(_) => 11,
+            Keyword::Enum(_) => 12,
+            Keyword::Export(_) => 13,
+            Keyword::Extends(_) => 14,
+            Keyword::Finally(_) => 15,
+            Keyword::For(_) => 16,
+            Keyword::Function(_) => 17,
+            Keyword::If(_) => 18,
+            Keyword::Implements(_) => 19,
+            Keyword::Import(_) => 20,
+            Keyword::In(_) => 21,
+            Keyword::InstanceOf(_) => 22,
+            Keyword::Interface(_) => 23,
+            Keyword::Let(_) => 24,
+            Keyword::New(_) => 25,
+            Keyword::Package(_) => 26,
+            Keyword::Private(_) => 27,
+            Keyword::Protected(_) => 28,
+            Keyword::Public(_) => 29,
+            Keyword::Return(_) => 30,
+            Keyword::Static(_) => 31,
+            Keyword::Super(_) => 32,
+            Keyword::Switch(_) => 33,
+            Keyword::This(_) => 34,
+            Keyword::Throw(_) => 35,
+            Keyword::Try(_) => 36,
+            Keyword::TypeOf(_) => 37,
+            Keyword::Var(_) => 38,
+            Keyword::Void(_) => 39,
+            Keyword::While(_) => 40,
+            Keyword::With(_) => 41,
+            Keyword::Yield(_) => 42,
+            Keyword::Es3Reserved(w, _) => 43 + *w as usize,
         }
     }
 }
@@ -508,10 +756,21 @@ impl<'a> Keyword<&'a str> {
             "while" => Keyword::While("while"),
             "with" => Keyword::With("with"),
             "yield" => Keyword::Yield("yield"),
-            _ => panic!("Invalid keyword..."),
+            _ => match Es3Word::from_word(s) {
+                Some(w) => Keyword::Es3Reserved(w, w.as_str()),
+                None => panic!("Invalid keyword..."),
+            },
         }
     }
     pub fn has_unicode_escape(&self) -> bool {
+        self.text().contains("\\u")
+    }
+}
+
+impl<T> Keyword<T> {
+    /// The text this keyword was scanned from, which
+    /// may include unicode escapes
+    pub(crate) fn text(&self) -> &T {
         match self {
             Keyword::Await(s) => s,
             Keyword::Break(s) => s,
@@ -556,7 +815,7 @@ impl<'a> Keyword<&'a str> {
             Keyword::While(s) => s,
             Keyword::With(s) => s,
             Keyword::Yield(s) => s,
+            Keyword::Es3Reserved(_, s) => s,
         }
-        .contains("\\u")
     }
 }
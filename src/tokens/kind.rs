@@ -0,0 +1,107 @@
+use super::{CommentKind, Keyword, NumberKind, Punct, StringLit, Template, Token};
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+/// The kind of a `Token` without any of
+/// the text it was created from
+pub enum TokenKind {
+    Boolean(bool),
+    EoF,
+    Ident,
+    Keyword(Keyword<()>),
+    Null,
+    Number(NumberKind),
+    Punct(Punct),
+    String(Quote),
+    RegEx,
+    Template(TemplatePart),
+    Comment(CommentKind),
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+/// The quote used to delimit a string literal
+pub enum Quote {
+    Single,
+    Double,
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+/// The position of a template literal part
+/// relative to its substitutions
+pub enum TemplatePart {
+    NoSub,
+    Head,
+    Middle,
+    Tail,
+}
+
+impl Eq for Keyword<()> {}
+
+impl TokenKind {
+    pub fn is_comment(self) -> bool {
+        matches!(self, TokenKind::Comment(_))
+    }
+    pub fn is_eof(self) -> bool {
+        matches!(self, TokenKind::EoF)
+    }
+    pub fn is_div_punct(self) -> bool {
+        matches!(
+            self,
+            TokenKind::Punct(Punct::ForwardSlash | Punct::ForwardSlashEqual)
+        )
+    }
+}
+
+impl<T> Token<T>
+where
+    T: AsRef<str>,
+{
+    /// The kind of this token
+    pub fn kind(&self) -> TokenKind {
+        match self {
+            Token::Boolean(b) => TokenKind::Boolean(b.into()),
+            Token::EoF => TokenKind::EoF,
+            Token::Ident(_) => TokenKind::Ident,
+            Token::Keyword(k) => TokenKind::Keyword(k.to_empty()),
+            Token::Null => TokenKind::Null,
+            Token::Number(n) => TokenKind::Number(n.kind()),
+            Token::Punct(p) => TokenKind::Punct(*p),
+            Token::String(StringLit::Single(_)) => TokenKind::String(Quote::Single),
+            Token::String(StringLit::Double(_)) => TokenKind::String(Quote::Double),
+            Token::RegEx(_) => TokenKind::RegEx,
+            Token::Template(Template::NoSub(_)) => TokenKind::Template(TemplatePart::NoSub),
+            Token::Template(Template::Head(_)) => TokenKind::Template(TemplatePart::Head),
+            Token::Template(Template::Middle(_)) => TokenKind::Template(TemplatePart::Middle),
+            Token::Template(Template::Tail(_)) => TokenKind::Template(TemplatePart::Tail),
+            Token::Comment(c) => TokenKind::Comment(c.kind),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn kind_is_small() {
+        assert_eq!(core::mem::size_of::<TokenKind>(), 2);
+    }
+
+    #[test]
+    fn kinds() {
+        assert_eq!(
+            Token::Keyword(Keyword::new("if")).kind(),
+            TokenKind::Keyword(Keyword::If(()))
+        );
+        assert_eq!(
+            Token::String(StringLit::double("a", false)).kind(),
+            TokenKind::String(Quote::Double)
+        );
+        assert_eq!(
+            Token::Template(Template::template_middle("", false, false, false)).kind(),
+            TokenKind::Template(TemplatePart::Middle)
+        );
+    }
+}
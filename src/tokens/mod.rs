@@ -1,7 +1,9 @@
+use alloc::string::{String, ToString};
 mod boolean;
 mod comment;
 mod ident;
 mod keyword;
+mod kind;
 mod number;
 mod regex;
 mod string;
@@ -17,13 +19,16 @@ pub mod prelude {
 pub use boolean::Boolean;
 pub use comment::{Comment, CommentKind};
 pub use ident::Ident;
-pub use keyword::Keyword;
+pub(crate) use keyword::KEYWORDS;
+pub use keyword::{Es3Word, Keyword};
+pub use kind::{Quote, TemplatePart, TokenKind};
 pub use number::{Number, NumberKind};
 pub use regex::RegEx;
 pub use string::{InnerString, StringLit};
 pub use template::{Template, TemplateLiteral};
 
 #[derive(PartialEq, Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 /// The representation of any single
 /// JS part
 pub enum Token<T> {
@@ -96,6 +101,30 @@ impl<T> PartialEq<bool> for Token<T> {
 }
 
 impl<T> Token<T> {
+    /// Convert the text held by this token with `f`, this works
+    /// through every variant that holds text
+    ///
+    /// ```
+    /// use ress::tokens::{Token, StringLit};
+    /// let token = Token::String(StringLit::single("a", false));
+    /// let lens = token.map(|s| s.len());
+    /// assert_eq!(lens, Token::String(StringLit::single(1, false)));
+    /// ```
+    pub fn map<U>(self, f: impl FnMut(T) -> U) -> Token<U> {
+        match self {
+            Token::Boolean(b) => Token::Boolean(b),
+            Token::EoF => Token::EoF,
+            Token::Ident(i) => Token::Ident(i.map(f)),
+            Token::Keyword(k) => Token::Keyword(k.map(f)),
+            Token::Null => Token::Null,
+            Token::Number(n) => Token::Number(n.map(f)),
+            Token::Punct(p) => Token::Punct(p),
+            Token::String(s) => Token::String(s.map(f)),
+            Token::RegEx(r) => Token::RegEx(r.map(f)),
+            Token::Template(t) => Token::Template(t.map(f)),
+            Token::Comment(c) => Token::Comment(c.map(f)),
+        }
+    }
     pub fn is_boolean(&self) -> bool {
         matches!(self, Token::Boolean(_))
     }
@@ -252,6 +281,21 @@ impl<T> Token<T>
 where
     T: AsRef<str>,
 {
+    /// Copy the text held by this token into an
+    /// owned type like `String`, `Rc<str>` or `Arc<str>`
+    ///
+    /// ```
+    /// use std::sync::Arc;
+    /// use ress::tokens::{Token, Ident};
+    /// let token: Token<Arc<str>> = Token::Ident(Ident::from("a")).into_owned();
+    /// assert!(token.matches_ident_str("a"));
+    /// ```
+    pub fn into_owned<U>(self) -> Token<U>
+    where
+        U: for<'s> From<&'s str>,
+    {
+        self.map(|s| U::from(s.as_ref()))
+    }
     pub fn is_restricted(&self) -> bool {
         match self {
             Token::Ident(ref i) => i.as_ref() == "arguments" || i.as_ref() == "eval",
@@ -320,7 +364,7 @@ where
             Token::Comment(ref c) => c.to_string(),
             Token::EoF => String::new(),
             Token::Ident(ref i) => i.to_string(),
-            Token::Keyword(ref k) => k.to_string(),
+            Token::Keyword(ref k) => k.text().as_ref().to_string(),
             Token::Null => "null".to_string(),
             Token::Number(ref n) => n.to_string(),
             Token::Punct(ref p) => p.to_string(),
@@ -331,7 +375,8 @@ where
     }
 }
 
-#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 /// All available punctuation
 pub enum Punct {
     Ampersand,
@@ -388,6 +433,16 @@ pub enum Punct {
     TripleEqual,
     TripleGreaterThanEqual,
     TripleGreaterThan,
+    /// `|>`, only with `ScannerOptions::pipeline`
+    PipeGreaterThan,
+    /// `::`, only with `ScannerOptions::bind`
+    DoubleColon,
+    /// `#{`, only with `ScannerOptions::records_and_tuples`
+    HashOpenBrace,
+    /// `#[`, only with `ScannerOptions::records_and_tuples`
+    HashOpenBracket,
+    /// `@@`, only with `ScannerOptions::topic_token`
+    DoubleAtMark,
 }
 
 impl PartialEq<str> for Punct {
@@ -424,6 +479,11 @@ impl Punct {
             Punct::Caret => "^" == s,
             Punct::ForwardSlash => "/" == s,
             Punct::TripleGreaterThanEqual => ">>>=" == s,
+            Punct::PipeGreaterThan => "|>" == s,
+            Punct::DoubleColon => "::" == s,
+            Punct::HashOpenBrace => "#{" == s,
+            Punct::HashOpenBracket => "#[" == s,
+            Punct::DoubleAtMark => "@@" == s,
             Punct::Ellipsis => "..." == s,
             Punct::TripleEqual => "===" == s,
             Punct::BangDoubleEqual => "!==" == s,
@@ -485,6 +545,11 @@ impl ToString for Punct {
             Punct::Caret => "^",
             Punct::ForwardSlash => "/",
             Punct::TripleGreaterThanEqual => ">>>=",
+            Punct::PipeGreaterThan => "|>",
+            Punct::DoubleColon => "::",
+            Punct::HashOpenBrace => "#{",
+            Punct::HashOpenBracket => "#[",
+            Punct::DoubleAtMark => "@@",
             Punct::Ellipsis => "...",
             Punct::TripleEqual => "===",
             Punct::BangDoubleEqual => "!==",
@@ -546,6 +611,38 @@ mod test {
         assert_eq!(f, "false");
     }
 
+    #[test]
+    fn map() {
+        let upper = |s: &str| s.to_uppercase();
+        assert_eq!(
+            Token::Keyword(Keyword::new("if")).map(upper),
+            Token::Keyword(Keyword::If("IF".to_string()))
+        );
+        assert_eq!(
+            Token::Comment(Comment::new_html_with_tail("a", "b")).map(upper),
+            Token::Comment(Comment::new_html_with_tail(
+                "A".to_string(),
+                "B".to_string()
+            ))
+        );
+        assert_eq!(
+            Token::RegEx(RegEx::from_parts("a", Some("g"))).map(upper),
+            Token::RegEx(RegEx::from_parts("A".to_string(), Some("G".to_string())))
+        );
+        assert_eq!(
+            Token::Template(Template::template_tail("a", true, false, true)).map(upper),
+            Token::Template(Template::template_tail("A".to_string(), true, false, true))
+        );
+        assert_eq!(
+            Token::String(StringLit::double("a", true)).map(upper),
+            Token::String(StringLit::double("A".to_string(), true))
+        );
+        assert_eq!(
+            Token::<&str>::Punct(Punct::Bang).map(upper),
+            Token::Punct(Punct::Bang)
+        );
+    }
+
     #[test]
     fn comments() {
         let c1 = Token::Comment(Comment::new_single_line("comment"));
@@ -1,4 +1,6 @@
+use alloc::string::{String, ToString};
 #[derive(Debug, PartialEq, Eq, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 /// A JS number literal. There are 4 kinds of number
 /// literals allowed in JS.
 ///
@@ -23,6 +25,13 @@ pub trait NumberExt {
     fn is_big_int(&self) -> bool;
 }
 
+impl<T> Number<T> {
+    /// Convert the text of this number with `f`
+    pub fn map<U>(self, f: impl FnOnce(T) -> U) -> Number<U> {
+        Number(f(self.0))
+    }
+}
+
 impl<T> Number<T>
 where
     T: AsRef<str>,
@@ -64,6 +73,10 @@ where
     pub fn is_big_int(&self) -> bool {
         self.kind() == NumberKind::BigInt
     }
+    /// If this number uses `_` to separate its digits
+    pub fn has_separator(&self) -> bool {
+        self.0.as_ref().contains('_')
+    }
 }
 
 impl<'a> From<&'a str> for Number<&'a str> {
@@ -91,6 +104,7 @@ where
 }
 
 #[derive(Debug, PartialEq, Eq, Clone, Copy)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 /// The 5 kinds of numbers
 pub enum NumberKind {
     Dec,
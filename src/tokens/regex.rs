@@ -1,4 +1,6 @@
+use alloc::string::{String, ToString};
 #[derive(Debug, PartialEq, Eq, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 /// A Regular Expression Literal
 ///
 /// These being with a `/` and the
@@ -14,6 +16,13 @@ impl<T> RegEx<T> {
     pub fn from_parts(body: T, flags: Option<T>) -> Self {
         RegEx { body, flags }
     }
+    /// Convert the body and flags of this regex with `f`
+    pub fn map<U>(self, mut f: impl FnMut(T) -> U) -> RegEx<U> {
+        RegEx {
+            body: f(self.body),
+            flags: self.flags.map(f),
+        }
+    }
 }
 
 impl<T> ToString for RegEx<T>
@@ -1,4 +1,6 @@
+use alloc::string::{String, ToString};
 #[derive(Debug, PartialEq, Eq, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 /// A single or double quoted string
 /// literal
 pub enum StringLit<T> {
@@ -6,6 +8,7 @@ pub enum StringLit<T> {
     Double(InnerString<T>),
 }
 #[derive(Debug, PartialEq, Eq, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct InnerString<T> {
     pub content: T,
     pub contains_octal_escape: bool,
@@ -34,6 +37,16 @@ where
     }
 }
 
+impl<T> InnerString<T> {
+    /// Convert the content of this string with `f`
+    pub fn map<U>(self, f: impl FnOnce(T) -> U) -> InnerString<U> {
+        InnerString {
+            content: f(self.content),
+            contains_octal_escape: self.contains_octal_escape,
+        }
+    }
+}
+
 impl<T> AsRef<str> for InnerString<T>
 where
     T: AsRef<str>,
@@ -44,6 +57,13 @@ where
 }
 
 impl<T> StringLit<T> {
+    /// Convert the content of this string with `f`
+    pub fn map<U>(self, f: impl FnOnce(T) -> U) -> StringLit<U> {
+        match self {
+            StringLit::Single(s) => StringLit::Single(s.map(f)),
+            StringLit::Double(s) => StringLit::Double(s.map(f)),
+        }
+    }
     pub fn single(content: T, oct: bool) -> Self {
         StringLit::Single(InnerString {
             content,
@@ -1,4 +1,6 @@
+use alloc::string::{String, ToString};
 #[derive(Debug, PartialEq, Eq, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 /// A template string
 ///
 /// These include strings that are wrapped in back ticks (`)
@@ -12,6 +14,7 @@ pub enum Template<T> {
 }
 
 #[derive(Debug, PartialEq, Eq, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct TemplateLiteral<T> {
     pub content: T,
     pub contains_octal_escape: bool,
@@ -32,9 +35,27 @@ impl<T> TemplateLiteral<T> {
             contains_invalid_hex_escape,
         }
     }
+    /// Convert the content of this template with `f`
+    pub fn map<U>(self, f: impl FnOnce(T) -> U) -> TemplateLiteral<U> {
+        TemplateLiteral {
+            content: f(self.content),
+            contains_octal_escape: self.contains_octal_escape,
+            contains_invalid_unicode_escape: self.contains_invalid_unicode_escape,
+            contains_invalid_hex_escape: self.contains_invalid_hex_escape,
+        }
+    }
 }
 
 impl<T> Template<T> {
+    /// Convert the content of this template with `f`
+    pub fn map<U>(self, f: impl FnOnce(T) -> U) -> Template<U> {
+        match self {
+            Template::NoSub(t) => Template::NoSub(t.map(f)),
+            Template::Head(t) => Template::Head(t.map(f)),
+            Template::Middle(t) => Template::Middle(t.map(f)),
+            Template::Tail(t) => Template::Tail(t.map(f)),
+        }
+    }
     pub fn no_sub_template(content: T, oct: bool, uni: bool, hex: bool) -> Self {
         Template::NoSub(TemplateLiteral::new(content, oct, uni, hex))
     }
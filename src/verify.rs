@@ -0,0 +1,250 @@
+use alloc::string::{String, ToString};
+use core::fmt;
+
+use crate::{
+    error::Error, is_line_term, tokenizer::is_space_separator, Item, LineIndex, Position, Scanner,
+    SourceLocation, Span,
+};
+
+/// Scan `text` and check that the items cover it in order
+/// without overlapping, with only whitespace between them,
+/// that each `location` agrees with its `span` and that
+/// each token's `to_string` gives back the text it spans,
+/// returning the first `Violation` found
+///
+/// ```
+/// let js = "let a = `b\n${c}`; /* d */ e /= 2";
+/// ress::verify(js).unwrap();
+/// let err = ress::verify("a;\nb = 'c").unwrap_err();
+/// assert_eq!(
+///     err.to_string(),
+///     "scan error: unterminated string literal at 2:6 (item 4)\n  b = 'c\n       ^"
+/// );
+/// ```
+// a `Violation` is only made once, at the end, so it isn't boxed
+#[allow(clippy::result_large_err)]
+pub fn verify(text: &str) -> Result<(), Violation> {
+    check(text, Scanner::new(text))
+}
+
+#[allow(clippy::result_large_err)]
+fn check<'a>(
+    text: &'a str,
+    items: impl Iterator<Item = Result<Item<&'a str>, Error>>,
+) -> Result<(), Violation> {
+    let lines = LineIndex::new(text);
+    let violation = |kind, index, span: Span| Violation {
+        kind,
+        index,
+        span,
+        position: lines.position(span.start),
+        context: line_of(text, &lines, span.start).to_string(),
+    };
+    let mut prev_end = 0;
+    for (index, item) in items.enumerate() {
+        let item = item.map_err(|e| {
+            let span = Span::new(e.idx, e.idx);
+            violation(ViolationKind::Error(e), index, span)
+        })?;
+        let span = item.span;
+        if span.start < prev_end {
+            return Err(violation(ViolationKind::Overlap { prev_end }, index, span));
+        }
+        let gap = &text[prev_end..span.start];
+        if !gap.chars().all(is_whitespace) {
+            let kind = ViolationKind::Gap {
+                text: gap.to_string(),
+            };
+            return Err(violation(kind, index, Span::new(prev_end, span.start)));
+        }
+        let expected = lines.location(span);
+        if item.location != expected {
+            let kind = ViolationKind::Location {
+                expected,
+                found: item.location,
+            };
+            return Err(violation(kind, index, span));
+        }
+        let printed = item.token.to_string();
+        let source = &text[span.start..span.end];
+        if printed != source {
+            let kind = ViolationKind::RoundTrip {
+                printed,
+                source: source.to_string(),
+            };
+            return Err(violation(kind, index, span));
+        }
+        if item.token.is_eof() {
+            return Ok(());
+        }
+        prev_end = span.end;
+    }
+    let end = Span::new(text.len(), text.len());
+    Err(violation(ViolationKind::MissingEof, 0, end))
+}
+
+/// The first broken invariant `verify` found
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Violation {
+    pub kind: ViolationKind,
+    /// Which item the violation is in, counting from 0
+    pub index: usize,
+    /// The text the violation is about
+    pub span: Span,
+    /// The line/column of the start of `span`
+    pub position: Position,
+    /// The line of text `position` is on
+    pub context: String,
+}
+
+/// The kinds of broken invariant `verify` looks for
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ViolationKind {
+    /// Scanning failed
+    Error(Error),
+    /// The item started before the previous item's end
+    Overlap { prev_end: usize },
+    /// Something other than whitespace was skipped
+    Gap { text: String },
+    /// The item's location doesn't match its span
+    Location {
+        expected: SourceLocation,
+        found: SourceLocation,
+    },
+    /// The item's token prints something other
+    /// than the text it was scanned from
+    RoundTrip { printed: String, source: String },
+    /// The items ended without an `EoF`
+    MissingEof,
+}
+
+impl fmt::Display for ViolationKind {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ViolationKind::Error(e) => write!(f, "scan error: {}", e.msg),
+            ViolationKind::Overlap { prev_end } => {
+                write!(f, "overlaps the previous item ending at {}", prev_end)
+            }
+            ViolationKind::Gap { text } => write!(f, "skipped text {:?}", text),
+            ViolationKind::Location { expected, found } => write!(
+                f,
+                "location {}-{} should be {}-{}",
+                found.start, found.end, expected.start, expected.end
+            ),
+            ViolationKind::RoundTrip { printed, source } => {
+                write!(f, "token prints as {:?} but was {:?}", printed, source)
+            }
+            ViolationKind::MissingEof => write!(f, "no EoF"),
+        }
+    }
+}
+
+impl fmt::Display for Violation {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let column = self
+            .context
+            .char_indices()
+            .take_while(|(i, _)| i + 1 < self.position.column)
+            .count();
+        write!(
+            f,
+            "{} at {} (item {})\n  {}\n  {:>width$}",
+            self.kind,
+            self.position,
+            self.index,
+            self.context,
+            "^",
+            width = column + 1
+        )
+    }
+}
+
+fn is_whitespace(c: char) -> bool {
+    matches!(c, '\t' | '\u{b}' | '\u{c}' | ' ' | '\u{a0}' | '\u{feff}')
+        || is_line_term(c)
+        || is_space_separator(c)
+}
+
+/// The line of `text` that `idx` is on, without its line terminator
+fn line_of<'a>(text: &'a str, lines: &LineIndex, idx: usize) -> &'a str {
+    let line = lines.position(idx).line;
+    let start = lines.line_start(line).unwrap_or(0);
+    text[start..].split(is_line_term).next().unwrap_or_default()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::tokens::{Punct, Token};
+
+    #[test]
+    fn clean() {
+        let js = "#!node\r\nlet a = `b\n${c}d${e}`;\u{2028}/* f\r\n*/--> g\n\
+                  <!-- h -->\u{a0}i\r\n'j\\\nk' /l/g.test(\"é\") \u{1680}m";
+        verify(js).unwrap();
+        verify("").unwrap();
+    }
+
+    #[test]
+    fn scan_error() {
+        let err = verify("a;\nb = 'c").unwrap_err();
+        assert!(matches!(err.kind, ViolationKind::Error(_)));
+        assert_eq!((err.index, err.position), (4, Position::new(2, 6)));
+        assert_eq!(err.context, "b = 'c");
+    }
+
+    fn item(token: Token<&str>, start: usize, end: usize) -> Result<Item<&str>, Error> {
+        let lines = LineIndex::new("a;\nb;");
+        let span = Span::new(start, end);
+        Ok(Item::new(token, span, lines.location(span)))
+    }
+
+    #[test]
+    fn violations() {
+        let js = "a;\nb;";
+        let kind = |items: Vec<_>| check(js, items.into_iter()).unwrap_err().kind;
+        let semi = Token::Punct(Punct::SemiColon);
+        assert_eq!(
+            kind(vec![
+                item(Token::Ident("a".into()), 0, 1),
+                item(semi.clone(), 0, 2)
+            ]),
+            ViolationKind::Overlap { prev_end: 1 }
+        );
+        assert_eq!(
+            kind(vec![item(semi.clone(), 1, 2)]),
+            ViolationKind::Gap { text: "a".into() }
+        );
+        assert_eq!(
+            kind(vec![item(Token::Ident("b".into()), 0, 1)]),
+            ViolationKind::RoundTrip {
+                printed: "b".into(),
+                source: "a".into()
+            }
+        );
+        let mut moved = item(Token::Ident("a".into()), 0, 1).unwrap();
+        moved.location.start.column = 2;
+        let expected = LineIndex::new(js).location(moved.span);
+        assert_eq!(
+            kind(vec![Ok(moved.clone())]),
+            ViolationKind::Location {
+                expected,
+                found: moved.location
+            }
+        );
+        assert_eq!(
+            kind(vec![item(Token::Ident("a".into()), 0, 1)]),
+            ViolationKind::MissingEof
+        );
+    }
+
+    #[test]
+    fn display() {
+        let err = verify("let x = 1;\n  f(\"été, 'bad);").unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "scan error: unterminated string literal at 2:18 (item 7)\n    \
+             f(\"été, 'bad);\n                 ^"
+        );
+    }
+}
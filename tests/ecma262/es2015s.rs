@@ -5,7 +5,7 @@ lazy_static! {
         Token::Comment(Comment::new_multi_line(
             " this file contains all grammatical productions in ECMA-262 edition 5.1 ** * *"
         )),
-        Token::Comment(Comment::new_html(" HTML-style comments ", None)),
+        Token::Comment(Comment::new_html_with_tail(" HTML-style comments ", "")),
         Token::Comment(Comment::new_single_line(" whitespace")),
         Token::Ident("tab".into()),
         Token::Punct(Punct::Colon),
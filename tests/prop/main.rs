@@ -2,9 +2,210 @@ extern crate ress;
 #[macro_use]
 extern crate proptest;
 
+use proptest::{collection::vec, prelude::*, sample::select};
+use ress::{
+    tokens::{Comment, Ident, Keyword, Number, Punct, RegEx, StringLit, Template, Token},
+    Scanner,
+};
+
 proptest! {
     #[test]
     fn function_idents(s in r#"function [a-zA-Z_$\u2118\u212E\u309B\u309C\u1885\u1886][a-zA-Z_]+"#) {
         ress::tokenize(&s).unwrap();
     }
+
+    #[test]
+    fn round_trip((tokens, separators) in (token_stream(), vec(separator(), 64))) {
+        let js = print(&tokens, &separators);
+        if let Err(violation) = ress::verify(&js) {
+            panic!("{}\n{:?}", violation, js);
+        }
+        let rescanned: Vec<Token<String>> = Scanner::new(&js)
+            .map(|item| item.unwrap().token.map(String::from))
+            .filter(|token| !token.is_eof())
+            .collect();
+        prop_assert_eq!(rescanned, tokens, "{:?}", js);
+    }
+}
+
+/// A sequence of tokens that scan back to themselves
+/// when printed with whitespace between each of them
+fn token_stream() -> impl Strategy<Value = Vec<Token<String>>> {
+    // a regular expression always follows `=` so it
+    // can't be mistaken for division
+    let leaf = prop_oneof![
+        4 => token().prop_map(|t| vec![t]),
+        1 => regex().prop_map(|r| vec![Token::Punct(Punct::Equal), r]),
+    ];
+    let fragment = leaf.prop_recursive(3, 64, 8, |inner| {
+        let inner = vec(inner, 1..6).prop_map(|v| v.concat());
+        prop_oneof![
+            (
+                select(vec![
+                    (Punct::OpenParen, Punct::CloseParen),
+                    (Punct::OpenBracket, Punct::CloseBracket),
+                    (Punct::OpenBrace, Punct::CloseBrace),
+                ]),
+                inner.clone()
+            )
+                .prop_map(|((open, close), tokens)| {
+                    let mut ret = vec![Token::Punct(open)];
+                    ret.extend(tokens);
+                    ret.push(Token::Punct(close));
+                    ret
+                }),
+            (template_content(), inner, template_content()).prop_map(|(head, tokens, tail)| {
+                let mut ret = vec![Token::Template(Template::template_head(
+                    head, false, false, false,
+                ))];
+                ret.extend(tokens);
+                ret.push(Token::Template(Template::template_tail(
+                    tail, false, false, false,
+                )));
+                ret
+            }),
+        ]
+    });
+    vec(fragment, 0..16).prop_map(|v| v.concat())
 }
+
+/// Any single token other than a regular expression
+fn token() -> impl Strategy<Value = Token<String>> {
+    prop_oneof![
+        r"[_$\u{e9}\u{3b1}][a-zA-Z0-9_$\u{e9}\u{3b1}]{0,6}"
+            .prop_map(|s| Token::Ident(Ident::from(s.as_str()).map(String::from))),
+        select(KEYWORDS).prop_map(|k| Token::Keyword(k.map(String::from))),
+        select(PUNCTS).prop_map(Token::Punct),
+        number().prop_map(|s| Token::Number(Number::from(s.as_str()).map(String::from))),
+        (
+            any::<bool>(),
+            r#"([a-z0-9 ]|\\[nt'"\\]|\\x[46][0-9a-f]|\\u00[46][0-9a-f]|\\\n)*"#
+        )
+            .prop_map(|(single, s)| if single {
+                Token::String(StringLit::single(s, false))
+            } else {
+                Token::String(StringLit::double(s, false))
+            }),
+        template_content()
+            .prop_map(|s| Token::Template(Template::no_sub_template(s, false, false, false))),
+        any::<bool>().prop_map(|b| Token::Boolean(b.into())),
+        Just(Token::Null),
+        r"[a-z0-9 ]*".prop_map(|s| Token::Comment(Comment::new_single_line(s))),
+        r"[a-z0-9 *\n]*".prop_map(|s| Token::Comment(Comment::new_multi_line(s))),
+    ]
+}
+
+fn regex() -> impl Strategy<Value = Token<String>> {
+    (
+        r"[a-z]([a-z0-9.+*?]|\\/|\[[a-z/]*\])*",
+        proptest::sample::subsequence(vec!['d', 'g', 'i', 'm', 's', 'u', 'y'], 0..=3),
+    )
+        .prop_map(|(body, flags)| {
+            let flags: String = flags.into_iter().collect();
+            let flags = if flags.is_empty() { None } else { Some(flags) };
+            Token::RegEx(RegEx::from_parts(body, flags))
+        })
+}
+
+fn number() -> impl Strategy<Value = String> {
+    prop_oneof![
+        r"(0|[1-9][0-9]{0,4})(\.[0-9]{1,3})?([eE][+-]?[0-9]{1,2})?",
+        r"0[xX][0-9a-fA-F]{1,4}|0[bB][01]{1,8}|0[oO][0-7]{1,4}",
+        r"[1-9][0-9]{0,3}n|[1-9](_[0-9]{1,3}){1,2}",
+    ]
+}
+
+fn template_content() -> impl Strategy<Value = String> {
+    r#"([a-z0-9 '"\n{}]|\\[nt`$\\]|\\u\{[46][0-9a-f]\})*"#
+}
+
+fn separator() -> impl Strategy<Value = &'static str> {
+    select(vec![
+        " ", " ", "\t", "\n", "\r\n", "\u{2028}", "\u{a0}", "\u{3000}",
+    ])
+}
+
+/// Join `tokens` with `separators`, ending single line
+/// comments with a new line
+fn print(tokens: &[Token<String>], separators: &[&str]) -> String {
+    let mut js = String::new();
+    for (i, token) in tokens.iter().enumerate() {
+        js.push_str(&token.to_string());
+        let separator = separators[i % separators.len()];
+        match token {
+            Token::Comment(c) if c.is_single_line() && !separator.contains(['\n', '\u{2028}']) => {
+                js.push('\n')
+            }
+            _ => js.push_str(separator),
+        }
+    }
+    js
+}
+
+const KEYWORDS: &[Keyword<&str>] = &[
+    Keyword::Await("await"),
+    Keyword::Class("class"),
+    Keyword::Const("const"),
+    Keyword::Else("else"),
+    Keyword::For("for"),
+    Keyword::Function("function"),
+    Keyword::If("if"),
+    Keyword::In("in"),
+    Keyword::Let("let"),
+    Keyword::New("new"),
+    Keyword::Return("return"),
+    Keyword::This("this"),
+    Keyword::TypeOf("typeof"),
+    Keyword::Var("var"),
+    Keyword::While("while"),
+    Keyword::Yield("yield"),
+];
+
+/// Every `Punct` that can't change how the tokens around it
+/// are scanned, so no `/` or unbalanced delimiters
+const PUNCTS: &[Punct] = &[
+    Punct::Ampersand,
+    Punct::AmpersandEqual,
+    Punct::Asterisk,
+    Punct::AsteriskEqual,
+    Punct::Bang,
+    Punct::BangDoubleEqual,
+    Punct::BangEqual,
+    Punct::Caret,
+    Punct::CaretEqual,
+    Punct::Colon,
+    Punct::Comma,
+    Punct::Dash,
+    Punct::DashEqual,
+    Punct::DoubleAmpersand,
+    Punct::DoubleAsterisk,
+    Punct::DoubleAsteriskEqual,
+    Punct::DoubleDash,
+    Punct::DoubleEqual,
+    Punct::DoubleGreaterThan,
+    Punct::DoubleGreaterThanEqual,
+    Punct::DoubleLessThan,
+    Punct::DoubleLessThanEqual,
+    Punct::DoublePipe,
+    Punct::DoublePlus,
+    Punct::Ellipsis,
+    Punct::Equal,
+    Punct::EqualGreaterThan,
+    Punct::GreaterThan,
+    Punct::GreaterThanEqual,
+    Punct::LessThan,
+    Punct::LessThanEqual,
+    Punct::Percent,
+    Punct::PercentEqual,
+    Punct::Period,
+    Punct::Pipe,
+    Punct::PipeEqual,
+    Punct::Plus,
+    Punct::PlusEqual,
+    Punct::QuestionMark,
+    Punct::SemiColon,
+    Punct::Tilde,
+    Punct::TripleEqual,
+    Punct::TripleGreaterThan,
+    Punct::TripleGreaterThanEqual,
+];
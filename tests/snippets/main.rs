@@ -1,3 +1,5 @@
+mod regex_corpus;
+
 use ress::prelude::*;
 
 #[test]
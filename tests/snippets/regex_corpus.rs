@@ -0,0 +1,67 @@
+//! Snippets where telling a regular expression from
+//! division depends on what came before the `/`
+use ress::prelude::*;
+
+/// Each snippet and the source text of every regular
+/// expression that should be found in it
+const CORPUS: &[(&str, &[&str])] = &[
+    // template substitutions
+    ("`${/re/}`", &["/re/"]),
+    ("`a${b}c${/re/g.source}`", &["/re/g"]),
+    ("`${a}` / 2", &[]),
+    ("`${ {a: /b/} }`", &["/b/"]),
+    ("`${ {a: 1} / 2 }`", &[]),
+    // for of
+    ("for (x of /re/g.exec(s)) {}", &["/re/g"]),
+    ("for (const [a, b] of /re/.exec(s)) {}", &["/re/"]),
+    ("for await (x of /re/) {}", &["/re/"]),
+    ("let of = 4; of / 2 / 1", &[]),
+    ("for (of of /re/) {}", &["/re/"]),
+    ("for (a.of / 2;;) {}", &[]),
+    ("for (let x = of / 2 / 1;;) {}", &[]),
+    ("for ({a} of /re/) {}", &["/re/"]),
+    ("for (var of of /re/) {}", &["/re/"]),
+    ("for (x of of / 2 / 1) {}", &[]),
+    // arrow bodies
+    ("const f = () => {}\n/re/.test(s)", &["/re/"]),
+    ("f(x => x / 2 / 1)", &[]),
+    ("f(x => /re/)", &["/re/"]),
+    ("f(async x => { await /re/ })", &["/re/"]),
+    // function bodies
+    ("let g = function() {} / 2 / 1", &[]),
+    ("(function() {}) / 2 / 1", &[]),
+    ("x = [function f() {} / 2 / 1]", &[]),
+    ("function f() {}\n/re/.test(s)", &["/re/"]),
+    // class bodies
+    ("let c = class {} / 2 / 1", &[]),
+    ("let c = class A extends B {} / 2 / 1", &[]),
+    ("class A {}\n/re/.test(s)", &["/re/"]),
+    ("class A extends (x => {}) {}\n/re/.test(s)", &["/re/"]),
+    ("x = class { m() { return /re/ } } / 2", &["/re/"]),
+    // yield and await
+    ("function* g() { yield /re/ }", &["/re/"]),
+    ("function* g() { if (a) { yield /re/ } }", &["/re/"]),
+    ("({ *g() { yield /re/ } })", &["/re/"]),
+    ("function f() { var yield = 4; return yield / 2 / 1 }", &[]),
+    ("var yield = 4; yield / 2 / 1", &[]),
+    ("async function f() { await /re/ }", &["/re/"]),
+    ("({ async m() { await /re/ } })", &["/re/"]),
+    ("const f = async () => { await /re/ }", &["/re/"]),
+    ("function f() { var await = 4; return await / 2 / 1 }", &[]),
+    ("await /re/", &["/re/"]),
+    // keywords that end an expression
+    ("this / 2 / 1", &[]),
+    ("typeof /re/", &["/re/"]),
+];
+
+#[test]
+fn regex_or_division() {
+    for (js, expected) in CORPUS {
+        let found: Vec<&str> = Scanner::new(js)
+            .map(|item| item.unwrap_or_else(|e| panic!("{:?}: {}", js, e)))
+            .filter(|item| item.token.is_regex())
+            .map(|item| &js[item.span.start..item.span.end])
+            .collect();
+        assert_eq!(&found, expected, "{:?}", js);
+    }
+}
@@ -0,0 +1,2 @@
+// A fixture imported by other tests, never run on its own
+'unterminated
@@ -0,0 +1,12 @@
+// Copyright (C) 2024 the ress authors. All rights reserved.
+/*---
+description: HTML-like comments are not allowed in module code
+negative:
+  phase: parse
+  type: SyntaxError
+flags: [module]
+---*/
+
+$DONOTEVALUATE();
+
+<!-- not a comment
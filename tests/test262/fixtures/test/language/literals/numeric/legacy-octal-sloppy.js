@@ -0,0 +1,7 @@
+// Copyright (C) 2024 the ress authors. All rights reserved.
+/*---
+description: Legacy octal literals are allowed in sloppy mode code
+flags: [noStrict]
+---*/
+
+assert.sameValue(010, 8);
@@ -0,0 +1,12 @@
+// Copyright (C) 2024 the ress authors. All rights reserved.
+/*---
+description: Legacy octal literals are not allowed in strict mode code
+negative:
+  phase: parse
+  type: SyntaxError
+flags: [onlyStrict]
+---*/
+
+$DONOTEVALUATE();
+
+010;
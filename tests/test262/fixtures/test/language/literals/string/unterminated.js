@@ -0,0 +1,12 @@
+// Copyright (C) 2024 the ress authors. All rights reserved.
+/*---
+description: A line terminator can't appear in a string literal
+negative:
+  phase: parse
+  type: SyntaxError
+---*/
+
+$DONOTEVALUATE();
+
+'unterminated
+';
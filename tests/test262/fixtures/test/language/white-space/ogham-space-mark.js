@@ -0,0 +1,7 @@
+// Copyright (C) 2024 the ress authors. All rights reserved.
+/*---
+description: U+1680 OGHAM SPACE MARK is whitespace
+---*/
+
+var a = 1;
+assert.sameValue(a, 1);
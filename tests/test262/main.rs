@@ -0,0 +1,326 @@
+//! Lexical conformance against a local copy of
+//! [test262](https://github.com/tc39/test262)
+//!
+//! The checkout is expected at `test262` in the root of this
+//! project, or wherever `TEST262_DIR` points. Each test's
+//! frontmatter picks the goal and strictness it is scanned with,
+//! negative tests in the lexical directories have to produce an
+//! error and every other test has to scan cleanly. Tests we know
+//! we get wrong are listed in `known_failures.txt` along with the
+//! test262 revision that list was generated from, set
+//! `TEST262_UPDATE_KNOWN=1` to rewrite that list from the checkout.
+#![cfg(test)]
+
+use std::{
+    collections::BTreeSet,
+    env,
+    fs::{read_to_string, write},
+    path::{Path, PathBuf},
+    process::Command,
+};
+
+use ress::{tokens::Token, Scanner, ScannerOptions};
+
+/// The line in a known failures list that records
+/// the test262 commit it was generated from
+const REVISION: &str = "# revision: ";
+
+/// The directories under `test` whose parse errors
+/// a scanner is able to find
+const LEXICAL: &[&str] = &[
+    "language/comments",
+    "language/identifiers",
+    "language/line-terminators",
+    "language/literals",
+    "language/punctuators",
+    "language/white-space",
+];
+
+#[test]
+fn test262() {
+    let root = env::var_os("TEST262_DIR")
+        .map(PathBuf::from)
+        .unwrap_or_else(|| manifest_dir().join("test262"));
+    if !root.join("test").exists() {
+        eprintln!(
+            "skipping test262, no checkout found at {}. see CONTRIBUTING.md for more info",
+            root.display()
+        );
+        return;
+    }
+    check(
+        &root,
+        &manifest_dir().join("tests/test262/known_failures.txt"),
+    );
+}
+
+#[test]
+fn fixtures() {
+    let root = manifest_dir().join("tests/test262/fixtures");
+    let report = run(&root);
+    assert_eq!(report.total, 5);
+    assert_eq!(
+        report.failures.into_iter().collect::<Vec<_>>(),
+        ["language/comments/module-html-open.js"]
+    );
+    check(&root, &root.join("known_failures.txt"));
+}
+
+#[test]
+fn frontmatter() {
+    let js = "/*---\n\
+              description: a legacy octal\n\
+              negative:\n  phase: parse\n  type: SyntaxError\n\
+              flags: [onlyStrict, raw]\n\
+              ---*/\n\
+              $DONOTEVALUATE();\n";
+    let meta = Meta::parse(js);
+    assert!(meta.negative_parse);
+    assert!(meta.flags.contains(&"onlyStrict".to_string()));
+    assert!(meta.flags.contains(&"raw".to_string()));
+    assert_eq!(meta.modes(), [Mode::Strict]);
+    let meta = Meta::parse("/*---\nnegative:\n  phase: runtime\n---*/");
+    assert!(!meta.negative_parse);
+    assert_eq!(meta.modes(), [Mode::Sloppy, Mode::Strict]);
+    let meta = Meta::parse("/*---\nflags:\n  - module\n---*/");
+    assert_eq!(meta.modes(), [Mode::Module]);
+}
+
+fn manifest_dir() -> PathBuf {
+    PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+}
+
+/// Run everything in `root` and compare the
+/// failures with the list in `known`
+fn check(root: &Path, known: &Path) {
+    let checkout = revision(root);
+    let listed = read_to_string(known).unwrap_or_default();
+    let pinned = listed
+        .lines()
+        .find_map(|l| l.strip_prefix(REVISION))
+        .map(str::trim);
+    let updating = env::var_os("TEST262_UPDATE_KNOWN").is_some();
+    if let (Some(checkout), Some(pinned), false) = (&checkout, pinned, updating) {
+        assert_eq!(
+            checkout,
+            pinned,
+            "{} was generated from test262 {}, check that revision out in {}",
+            known.display(),
+            pinned,
+            root.display()
+        );
+    }
+    let report = run(root);
+    eprintln!(
+        "completed {} test262 tests, {} failed",
+        report.total,
+        report.failures.len()
+    );
+    if updating {
+        let mut list = String::from("# test262 tests that are known to fail, one per line\n");
+        if let Some(checkout) = &checkout {
+            list.push_str(REVISION);
+            list.push_str(checkout);
+            list.push('\n');
+        }
+        for failure in &report.failures {
+            list.push_str(failure);
+            list.push('\n');
+        }
+        write(known, list).unwrap();
+        return;
+    }
+    let expected: BTreeSet<String> = listed
+        .lines()
+        .map(str::trim)
+        .filter(|l| !l.is_empty() && !l.starts_with('#'))
+        .map(String::from)
+        .collect();
+    let unexpected: Vec<_> = report.failures.difference(&expected).collect();
+    let fixed: Vec<_> = expected.difference(&report.failures).collect();
+    if !unexpected.is_empty() || !fixed.is_empty() {
+        panic!(
+            "{} unexpected failures:\n{}\n{} known failures now pass:\n{}\n\
+             set TEST262_UPDATE_KNOWN=1 to update {}",
+            unexpected.len(),
+            report
+                .messages
+                .iter()
+                .filter(|(path, _)| unexpected.contains(&path))
+                .map(|(path, msg)| format!("  {}: {}", path, msg))
+                .collect::<Vec<_>>()
+                .join("\n"),
+            fixed.len(),
+            fixed
+                .iter()
+                .map(|path| format!("  {}", path))
+                .collect::<Vec<_>>()
+                .join("\n"),
+            known.display()
+        );
+    }
+}
+
+/// The commit checked out in `root`, `None` when
+/// it isn't a git checkout
+fn revision(root: &Path) -> Option<String> {
+    if !root.join(".git").exists() {
+        return None;
+    }
+    let out = Command::new("git")
+        .arg("-C")
+        .arg(root)
+        .args(["rev-parse", "HEAD"])
+        .output()
+        .ok()?;
+    if !out.status.success() {
+        return None;
+    }
+    Some(String::from_utf8_lossy(&out.stdout).trim().to_string())
+}
+
+#[derive(Default)]
+struct Report {
+    total: usize,
+    /// Paths relative to `test` with `/` separators
+    failures: BTreeSet<String>,
+    messages: Vec<(String, String)>,
+}
+
+fn run(root: &Path) -> Report {
+    let test_dir = root.join("test");
+    let mut report = Report::default();
+    for entry in walkdir::WalkDir::new(&test_dir).sort_by_file_name() {
+        let path = entry.expect("bad entry").into_path();
+        let name = path.to_string_lossy();
+        if !path.is_file() || !name.ends_with(".js") || name.contains("_FIXTURE") {
+            continue;
+        }
+        let rel = path
+            .strip_prefix(&test_dir)
+            .unwrap()
+            .components()
+            .map(|c| c.as_os_str().to_string_lossy())
+            .collect::<Vec<_>>()
+            .join("/");
+        let js = read_to_string(&path).unwrap();
+        let meta = Meta::parse(&js);
+        if meta.negative_parse && !LEXICAL.iter().any(|dir| rel.starts_with(dir)) {
+            continue;
+        }
+        report.total += 1;
+        for mode in meta.modes() {
+            let result = scan(&js, mode);
+            let msg = match (meta.negative_parse, result) {
+                (true, Ok(())) => format!("expected an error in {:?} mode", mode),
+                (false, Err(e)) => format!("{} in {:?} mode", e, mode),
+                _ => continue,
+            };
+            report.failures.insert(rel.clone());
+            report.messages.push((rel.clone(), msg));
+            break;
+        }
+    }
+    report
+}
+
+/// The goal and strictness a test is scanned with
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Mode {
+    Sloppy,
+    Strict,
+    Module,
+}
+
+/// The parts of a test's frontmatter the scanner cares about
+#[derive(Debug, Default)]
+struct Meta {
+    negative_parse: bool,
+    flags: Vec<String>,
+}
+
+impl Meta {
+    /// Read the YAML between `/*---` and `---*/`, this only
+    /// understands the shapes test262 uses for `negative` and `flags`
+    fn parse(js: &str) -> Self {
+        let mut meta = Meta::default();
+        let block = match (js.find("/*---"), js.find("---*/")) {
+            (Some(start), Some(end)) if start < end => &js[start + 5..end],
+            _ => return meta,
+        };
+        let mut section = "";
+        for line in block.lines() {
+            let trimmed = line.trim();
+            if !line.starts_with(char::is_whitespace) {
+                let (key, value) = trimmed.split_once(':').unwrap_or((trimmed, ""));
+                section = key;
+                if key == "flags" {
+                    meta.flags.extend(
+                        value
+                            .trim()
+                            .trim_start_matches('[')
+                            .trim_end_matches(']')
+                            .split(',')
+                            .map(str::trim)
+                            .filter(|f| !f.is_empty())
+                            .map(String::from),
+                    );
+                }
+            } else if section == "negative" {
+                if let Some(phase) = trimmed.strip_prefix("phase:") {
+                    meta.negative_parse = phase.trim() == "parse";
+                }
+            } else if section == "flags" {
+                if let Some(flag) = trimmed.strip_prefix('-') {
+                    meta.flags.push(flag.trim().to_string());
+                }
+            }
+        }
+        meta
+    }
+
+    fn has_flag(&self, flag: &str) -> bool {
+        self.flags.iter().any(|f| f == flag)
+    }
+
+    /// test262 runs tests without a strictness flag both ways
+    fn modes(&self) -> Vec<Mode> {
+        if self.has_flag("module") {
+            vec![Mode::Module]
+        } else if self.has_flag("onlyStrict") {
+            vec![Mode::Strict]
+        } else if self.has_flag("noStrict") || self.has_flag("raw") {
+            vec![Mode::Sloppy]
+        } else {
+            vec![Mode::Sloppy, Mode::Strict]
+        }
+    }
+}
+
+/// Scan all of `js`, strict mode code also rejects
+/// legacy octal numbers and escapes
+fn scan(js: &str, mode: Mode) -> Result<(), String> {
+    let options = ScannerOptions::new().html_comments(mode != Mode::Module);
+    for item in Scanner::with_options(js, options) {
+        let item = item.map_err(|e| e.to_string())?;
+        if mode == Mode::Sloppy {
+            continue;
+        }
+        let legacy = match &item.token {
+            Token::Number(_) => {
+                let text = &js.as_bytes()[item.span.start..item.span.end];
+                text.len() > 1 && text[0] == b'0' && text[1].is_ascii_digit()
+            }
+            Token::String(s) => s.has_octal_escape(),
+            _ => false,
+        };
+        if legacy {
+            return Err(format!(
+                "legacy octal {:?} in strict mode code at {}",
+                &js[item.span.start..item.span.end],
+                item.location.start
+            ));
+        }
+    }
+    Ok(())
+}